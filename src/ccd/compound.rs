@@ -0,0 +1,137 @@
+use crate::math::{Vector3f, Scalar, Isometry3f, Point3f};
+use crate::ccd::{CCDObject, CCDCriteria, CCDDistance, ConvexHull, DecompositionCriteria, Transformed};
+use crate::ccd::{convex_decomposition, gjk_distance, gjk_intersect, load_convex_hulls, save_convex_hulls};
+use crate::utils::load_indexed_mesh;
+use std::io::{ErrorKind, Result};
+use std::path::Path;
+
+/// Non-convex object made of convex parts, e.g. the convex decomposition of
+/// a mesh, with a pose. Queries run on every part whose bounding sphere is
+/// close enough.
+pub struct Compound {
+    pub tform: Isometry3f,
+    pub parts: Vec<ConvexHull>,
+    spheres:   Vec<(Vector3f, Scalar)>,     // bounding spheres of the parts, in the local frame
+}
+
+impl Compound {
+
+    /// Creates the compound of the parts, at identity pose.
+    pub fn new(parts: Vec<ConvexHull>) -> Self {
+        let spheres = parts.iter()
+            .map(|part| {
+                let center = part.center();
+                let radius = part.vertices.iter()
+                    .map(|v| (part.rotm * v + part.pos - center).norm())
+                    .fold(0., Scalar::max);
+                (center, radius)
+            })
+            .collect();
+        Compound { tform: Isometry3f::identity(), parts, spheres }
+    }
+
+    /// Creates the compound of the convex decomposition of a mesh file. The
+    /// decomposition is loaded from the cache file if it was computed from
+    /// the same mesh and with the same criteria, otherwise it is computed and
+    /// saved to the cache file.
+    ///
+    /// # Arguments
+    ///
+    /// - `mesh_path`: mesh file
+    /// - `cache_path`: OBJ file of the convex hulls
+    /// - `criteria`: decomposition criteria
+    pub fn from_mesh_file<P, Q>(mesh_path: P, cache_path: Q,
+                                criteria: &DecompositionCriteria) -> Result<Self>
+    where P: AsRef<Path>, Q: AsRef<Path> {
+        let mesh = load_indexed_mesh(mesh_path)?;
+        match load_convex_hulls(&cache_path, &mesh, criteria) {
+            Ok(hulls) => return Ok(Compound::new(hulls)),
+            Err(e) if e.kind() == ErrorKind::InvalidData || e.kind() == ErrorKind::NotFound => {},
+            Err(e) => return Err(e),
+        }
+
+        let hulls = convex_decomposition(&mesh, criteria);
+        save_convex_hulls(cache_path, &hulls, &mesh, criteria)?;
+        return Ok(Compound::new(hulls));
+    }
+
+    /// Returns the part moved by the pose of the compound.
+    fn part(&self, i: usize) -> Transformed<&ConvexHull> {
+        Transformed { tform: self.tform, obj: &self.parts[i] }
+    }
+
+    /// Returns the bounding sphere of the part in world coordinates.
+    fn sphere(&self, i: usize) -> (Vector3f, Scalar) {
+        let (center, radius) = self.spheres[i];
+        (self.tform.transform_point(&Point3f::from(center)).coords, radius)
+    }
+
+    /// Returns true if a part intersects the convex object.
+    pub fn intersect(&self, obj: &dyn CCDObject, ccd: &CCDCriteria) -> bool {
+        (0..self.parts.len()).any(|i| gjk_intersect(&self.part(i), obj, ccd))
+    }
+
+    /// Returns true if a part intersects a part of the other compound.
+    pub fn intersect_compound(&self, other: &Compound, ccd: &CCDCriteria) -> bool {
+        for i in 0..self.parts.len() {
+            let (c1, r1) = self.sphere(i);
+            for j in 0..other.parts.len() {
+                let (c2, r2) = other.sphere(j);
+                if (c1 - c2).norm() > r1 + r2 {
+                    continue;
+                }
+                if gjk_intersect(&self.part(i), &other.part(j), ccd) {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    /// Computes the separation distance between the compound and the convex
+    /// object, with the closest points, as `gjk_distance`. Returns false if
+    /// they intersect.
+    pub fn distance(&self, obj: &dyn CCDObject, ccd: &CCDCriteria, info: &mut CCDDistance) -> bool {
+        let mut best = CCDDistance::new();
+        best.distance = Scalar::INFINITY;
+        for i in 0..self.parts.len() {
+            let mut res = CCDDistance::new();
+            if !gjk_distance(&self.part(i), obj, ccd, &mut res) {
+                *info = res;
+                return false;
+            }
+            if res.distance < best.distance {
+                best = res;
+            }
+        }
+        *info = best;
+        return true;
+    }
+
+    /// Computes the separation distance between two compounds, with the
+    /// closest points. Returns false if they intersect.
+    pub fn distance_compound(&self, other: &Compound, ccd: &CCDCriteria, info: &mut CCDDistance) -> bool {
+        let mut best = CCDDistance::new();
+        best.distance = Scalar::INFINITY;
+        for i in 0..self.parts.len() {
+            let (c1, r1) = self.sphere(i);
+            for j in 0..other.parts.len() {
+                // parts further than the closest pair cannot be closer
+                let (c2, r2) = other.sphere(j);
+                if (c1 - c2).norm() - r1 - r2 >= best.distance {
+                    continue;
+                }
+                let mut res = CCDDistance::new();
+                if !gjk_distance(&self.part(i), &other.part(j), ccd, &mut res) {
+                    *info = res;
+                    return false;
+                }
+                if res.distance < best.distance {
+                    best = res;
+                }
+            }
+        }
+        *info = best;
+        return true;
+    }
+}
@@ -0,0 +1,316 @@
+use crate::math::{Vector3f, Matrix3f, Scalar};
+use crate::ccd::CCDObject;
+use crate::utils::{IndexedMesh, IndexedTriangle};
+use kiss3d::resource::Mesh;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Convex hull of a point cloud, with a pose. The vertices and faces are
+/// given in the local frame, the faces are counterclockwise seen from
+/// outside.
+pub struct ConvexHull {
+    pub pos:       Vector3f,
+    pub rotm:      Matrix3f,
+    pub vertices:  Vec<Vector3f>,
+    pub faces:     Vec<[usize; 3]>,
+    pub neighbors: Vec<Vec<usize>>,     // adjacent vertices of each vertex
+    last_support:  Cell<usize>,         // start of the next support search
+}
+
+struct HullFace {
+    vertices: [usize; 3],
+    normal:   Vector3f,
+    offset:   Scalar,
+    outside:  Vec<usize>,               // points above the face
+    alive:    bool,
+}
+
+impl HullFace {
+    fn new(points: &[Vector3f], vertices: [usize; 3]) -> Self {
+        let (a, b, c) = (&points[vertices[0]], &points[vertices[1]], &points[vertices[2]]);
+        let normal = (b - a).cross(&(c - a)).try_normalize(0.).unwrap_or(Vector3f::zeros());
+        HullFace { vertices, normal, offset: normal.dot(a), outside: Vec::new(), alive: true }
+    }
+
+    fn distance(&self, p: &Vector3f) -> Scalar {
+        self.normal.dot(p) - self.offset
+    }
+}
+
+impl ConvexHull {
+
+    /// Computes the convex hull of the points with the quickhull algorithm,
+    /// at identity pose. Returns `None` if the points are coplanar, or if a
+    /// coordinate is not finite.
+    pub fn new(points: &Vec<Vector3f>) -> Option<Self> {
+        let (vertices, faces) = quickhull(points)?;
+
+        let mut neighbors = vec![Vec::new(); vertices.len()];
+        for face in &faces {
+            for k in 0..3 {
+                // each edge is seen once in each direction
+                neighbors[face[k]].push(face[(k + 1) % 3]);
+            }
+        }
+
+        Some(ConvexHull {
+            pos: Vector3f::zeros(),
+            rotm: Matrix3f::identity(),
+            vertices,
+            faces,
+            neighbors,
+            last_support: Cell::new(0),
+        })
+    }
+
+    /// Computes the convex hull of the vertices of the mesh.
+    pub fn from_indexed_mesh(mesh: &IndexedMesh) -> Option<Self> {
+        let points = mesh.vertices.iter()
+            .map(|v| Vector3f::new(v[0] as Scalar, v[1] as Scalar, v[2] as Scalar))
+            .collect();
+        return ConvexHull::new(&points);
+    }
+
+    /// Computes the convex hull of the vertices of a mesh loaded for
+    /// rendering, e.g. by `load_mesh`.
+    pub fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        let coords = mesh.coords().read().unwrap();
+        let points = coords.data().as_ref()?.iter()
+            .map(|p| Vector3f::new(p[0] as Scalar, p[1] as Scalar, p[2] as Scalar))
+            .collect();
+        return ConvexHull::new(&points);
+    }
+
+    /// Computes the volume enclosed by the hull.
+    pub fn volume(&self) -> Scalar {
+        let mut volume = 0.;
+        for f in &self.faces {
+            let (a, b, c) = (&self.vertices[f[0]], &self.vertices[f[1]], &self.vertices[f[2]]);
+            volume += a.dot(&b.cross(c));
+        }
+        return volume / 6.;
+    }
+
+    /// Returns the hull as a triangle mesh in the local frame.
+    pub fn to_indexed_mesh(&self) -> IndexedMesh {
+        let vertices = self.vertices.iter()
+            .map(|v| [v[0] as f32, v[1] as f32, v[2] as f32])
+            .collect();
+        let faces = self.faces.iter()
+            .map(|f| {
+                let (a, b, c) = (&self.vertices[f[0]], &self.vertices[f[1]], &self.vertices[f[2]]);
+                let n = (b - a).cross(&(c - a)).normalize();
+                IndexedTriangle { normal: [n[0] as f32, n[1] as f32, n[2] as f32], vertices: *f }
+            })
+            .collect();
+        return IndexedMesh { vertices, faces };
+    }
+}
+
+impl CCDObject for ConvexHull {
+    fn center(&self) -> Vector3f {
+        let sum = self.vertices.iter().fold(Vector3f::zeros(), |s, v| s + v);
+        self.rotm * (sum / self.vertices.len() as Scalar) + &self.pos
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        let dir_local = self.rotm.transpose() * dir;
+
+        // hill climbing over the vertex adjacency: a vertex without better
+        // neighbor is the furthest one on a convex polytope
+        let mut curr = self.last_support.get();
+        let mut best = self.vertices[curr].dot(&dir_local);
+        loop {
+            let mut next = curr;
+            for &i in &self.neighbors[curr] {
+                let dot = self.vertices[i].dot(&dir_local);
+                if dot > best {
+                    best = dot;
+                    next = i;
+                }
+            }
+            if next == curr {
+                break;
+            }
+            curr = next;
+        }
+        self.last_support.set(curr);
+
+        let vec = self.rotm * &self.vertices[curr] + &self.pos;
+        return vec;
+    }
+}
+
+/// Quickhull: returns the hull vertices and the outward faces indexing
+/// them, or `None` for degenerate point sets.
+fn quickhull(points: &[Vector3f]) -> Option<(Vec<Vector3f>, Vec<[usize; 3]>)> {
+    if points.len() < 4 || points.iter().any(|p| !p.iter().all(|x| x.is_finite())) {
+        return None;
+    }
+
+    // tolerance relative to the extent of the points
+    let mut max_abs = Vector3f::zeros();
+    for p in points {
+        max_abs = max_abs.sup(&p.abs());
+    }
+    let eps = 1e-10 * (max_abs[0] + max_abs[1] + max_abs[2]).max(1.);
+
+    // initial tetrahedron from the extreme points
+    let mut extremes = Vec::new();
+    for k in 0..3 {
+        let (mut imin, mut imax) = (0, 0);
+        for (i, p) in points.iter().enumerate() {
+            if p[k] < points[imin][k] { imin = i; }
+            if p[k] > points[imax][k] { imax = i; }
+        }
+        extremes.push(imin);
+        extremes.push(imax);
+    }
+    let mut v0 = extremes[0];
+    let mut v1 = extremes[1];
+    let mut best = 0.;
+    for &i in &extremes {
+        for &j in &extremes {
+            let d = (points[i] - points[j]).norm_squared();
+            if d > best {
+                best = d;
+                v0 = i;
+                v1 = j;
+            }
+        }
+    }
+    if best.sqrt() <= eps {
+        return None;
+    }
+    let dir = (points[v1] - points[v0]).normalize();
+    let v2 = furthest(points, |p| (p - points[v0]).cross(&dir).norm());
+    let normal = (points[v1] - points[v0]).cross(&(points[v2] - points[v0]));
+    if normal.norm() <= eps * best.sqrt() {
+        return None;
+    }
+    let normal = normal.normalize();
+    let v3 = furthest(points, |p| (p - points[v0]).dot(&normal).abs());
+    let height = (points[v3] - points[v0]).dot(&normal);
+    if height.abs() <= eps {
+        return None;
+    }
+    let (v1, v2) = if height > 0. { (v2, v1) } else { (v1, v2) };
+
+    let mut faces: Vec<HullFace> = [[v0, v1, v2], [v0, v3, v1], [v0, v2, v3], [v1, v3, v2]].iter()
+        .map(|&v| HullFace::new(points, v))
+        .collect();
+    let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for k in 0..3 {
+            edges.insert((face.vertices[k], face.vertices[(k + 1) % 3]), f);
+        }
+    }
+
+    // assign each point to a face it lies above
+    let initial = [v0, v1, v2, v3];
+    for (i, p) in points.iter().enumerate() {
+        if initial.contains(&i) {
+            continue;
+        }
+        if let Some(face) = faces.iter_mut().find(|face| face.distance(p) > eps) {
+            face.outside.push(i);
+        }
+    }
+
+    let mut f = 0;
+    while f < faces.len() {
+        if !faces[f].alive || faces[f].outside.is_empty() {
+            f += 1;
+            continue;
+        }
+
+        // furthest point above the face
+        let eye = *faces[f].outside.iter()
+            .max_by(|&&i, &&j| faces[f].distance(&points[i]).total_cmp(&faces[f].distance(&points[j])))
+            .unwrap();
+
+        // faces visible from the eye, and their horizon
+        let mut visible = vec![f];
+        let mut horizon = Vec::new();
+        let mut visited = vec![f];
+        let mut stack = vec![f];
+        while let Some(g) = stack.pop() {
+            for k in 0..3 {
+                let (a, b) = (faces[g].vertices[k], faces[g].vertices[(k + 1) % 3]);
+                let h = edges[&(b, a)];
+                if visited.contains(&h) {
+                    if !visible.contains(&h) {
+                        horizon.push((a, b));
+                    }
+                    continue;
+                }
+                visited.push(h);
+                if faces[h].distance(&points[eye]) > eps {
+                    visible.push(h);
+                    stack.push(h);
+                } else {
+                    horizon.push((a, b));
+                }
+            }
+        }
+
+        // remove the visible faces
+        let mut orphans = Vec::new();
+        for &g in &visible {
+            faces[g].alive = false;
+            orphans.append(&mut faces[g].outside);
+            for k in 0..3 {
+                edges.remove(&(faces[g].vertices[k], faces[g].vertices[(k + 1) % 3]));
+            }
+        }
+
+        // cone of new faces from the horizon to the eye
+        let first = faces.len();
+        for (a, b) in horizon {
+            edges.insert((a, b), faces.len());
+            edges.insert((b, eye), faces.len());
+            edges.insert((eye, a), faces.len());
+            faces.push(HullFace::new(points, [a, b, eye]));
+        }
+        for i in orphans {
+            if i == eye {
+                continue;
+            }
+            if let Some(face) = faces[first..].iter_mut().find(|face| face.distance(&points[i]) > eps) {
+                face.outside.push(i);
+            }
+        }
+        f += 1;
+    }
+
+    // keep the vertices of the hull
+    let mut map = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut hull_faces = Vec::new();
+    for face in faces.iter().filter(|face| face.alive) {
+        let mut indices = [0; 3];
+        for k in 0..3 {
+            let v = face.vertices[k];
+            indices[k] = *map.entry(v).or_insert_with(|| {
+                vertices.push(points[v]);
+                vertices.len() - 1
+            });
+        }
+        hull_faces.push(indices);
+    }
+
+    return Some((vertices, hull_faces));
+}
+
+fn furthest<F: Fn(&Vector3f) -> Scalar>(points: &[Vector3f], dist: F) -> usize {
+    let mut best = 0;
+    let mut best_dist = Scalar::NEG_INFINITY;
+    for (i, p) in points.iter().enumerate() {
+        let d = dist(p);
+        if d > best_dist {
+            best_dist = d;
+            best = i;
+        }
+    }
+    return best;
+}
@@ -0,0 +1,474 @@
+//! Approximate convex decomposition of triangle meshes.
+//!
+//! The mesh is voxelized, then the set of voxels is split recursively by
+//! axis-aligned planes, starting with the most concave part, until the
+//! concavity of every part is small enough. The concavity of a part is the
+//! volume of its convex hull not filled by its voxels. Each part is finally
+//! replaced by the convex hull of the mesh surface it contains.
+
+use crate::math::{Vector3f, Scalar};
+use crate::ccd::ConvexHull;
+use crate::utils::{IndexedMesh, ObjGroup, RepairCriteria, read_obj_groups, write_obj};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+/// Criteria of the convex decomposition.
+#[derive(Debug, Clone, Copy)]
+pub struct DecompositionCriteria {
+    pub resolution:    usize,   // number of voxels along the longest side of the mesh
+    pub max_concavity: Scalar,  // maximum concavity of a part, relative to the mesh volume
+    pub max_hulls:     usize,   // maximum number of convex hulls
+    pub num_planes:    usize,   // number of split planes tried per axis
+}
+
+impl DecompositionCriteria {
+    pub fn default() -> Self {
+        DecompositionCriteria {
+            resolution:    32,
+            max_concavity: 0.02,
+            max_hulls:     16,
+            num_planes:    5,
+        }
+    }
+}
+
+const OUTSIDE: u8 = 0;
+const INSIDE:  u8 = 1;
+const SURFACE: u8 = 2;
+
+struct VoxelGrid {
+    origin:  Vector3f,
+    size:    Scalar,
+    dims:    [usize; 3],
+    state:   Vec<u8>,
+    samples: HashMap<usize, Vec<Vector3f>>,     // surface points in each surface voxel
+}
+
+impl VoxelGrid {
+
+    /// Voxelizes the surface and the interior of the mesh.
+    fn new(mesh: &IndexedMesh, resolution: usize) -> Self {
+        let points: Vec<Vector3f> = mesh.vertices.iter()
+            .map(|v| Vector3f::new(v[0] as Scalar, v[1] as Scalar, v[2] as Scalar))
+            .collect();
+        let mut lower = points[0];
+        let mut upper = points[0];
+        for p in &points {
+            lower = lower.inf(p);
+            upper = upper.sup(p);
+        }
+        let extent = upper - lower;
+        let size = extent.max() / resolution.max(1) as Scalar;
+        let mut dims = [1; 3];
+        for k in 0..3 {
+            dims[k] = ((extent[k] / size).ceil() as usize).max(1);
+        }
+
+        let mut grid = VoxelGrid {
+            origin: lower,
+            size,
+            dims,
+            state: vec![OUTSIDE; dims[0] * dims[1] * dims[2]],
+            samples: HashMap::new(),
+        };
+
+        // surface voxels, from points sampled on the triangles; the points
+        // are binned slightly inside so that a face lying on a voxel side
+        // marks the voxel inside the mesh
+        for face in &mesh.faces {
+            let (a, b, c) = (points[face.vertices[0]], points[face.vertices[1]], points[face.vertices[2]]);
+            let inward = -(b - a).cross(&(c - a)).try_normalize(0.).unwrap_or(Vector3f::zeros()) * (1e-3 * size);
+            let longest = (b - a).norm().max((c - a).norm()).max((c - b).norm());
+            let n = ((2. * longest / size).ceil() as usize).max(1);
+            for i in 0..=n {
+                for j in 0..=(n - i) {
+                    let p = a + (b - a) * (i as Scalar / n as Scalar) + (c - a) * (j as Scalar / n as Scalar);
+                    let index = grid.index(grid.cell(&(p + inward)));
+                    grid.state[index] = SURFACE;
+                    grid.samples.entry(index).or_insert_with(Vec::new).push(p);
+                }
+            }
+        }
+
+        // interior voxels, by parity of the crossings of rays along z
+        let mut crossings: Vec<Vec<Scalar>> = vec![Vec::new(); dims[0] * dims[1]];
+        for face in &mesh.faces {
+            let (a, b, c) = (points[face.vertices[0]], points[face.vertices[1]], points[face.vertices[2]]);
+            let (lo, hi) = (a.inf(&b).inf(&c), a.sup(&b).sup(&c));
+            let [i0, j0, _] = grid.cell(&lo);
+            let [i1, j1, _] = grid.cell(&hi);
+            for i in i0..=i1 {
+                for j in j0..=j1 {
+                    // slightly off the voxel centers to avoid the mesh edges
+                    let x = lower[0] + (i as Scalar + 0.5 + 1.3e-7) * size;
+                    let y = lower[1] + (j as Scalar + 0.5 + 2.9e-7) * size;
+                    if let Some(z) = ray_triangle_z(x, y, &a, &b, &c) {
+                        crossings[i + dims[0] * j].push(z);
+                    }
+                }
+            }
+        }
+        for i in 0..dims[0] {
+            for j in 0..dims[1] {
+                let zs = &mut crossings[i + dims[0] * j];
+                zs.sort_by(|a, b| a.total_cmp(b));
+                for pair in zs.chunks(2).filter(|pair| pair.len() == 2) {
+                    for k in 0..dims[2] {
+                        let z = lower[2] + (k as Scalar + 0.5) * size;
+                        let index = grid.index([i, j, k]);
+                        if z > pair[0] && z < pair[1] && grid.state[index] == OUTSIDE {
+                            grid.state[index] = INSIDE;
+                        }
+                    }
+                }
+            }
+        }
+
+        return grid;
+    }
+
+    fn index(&self, c: [usize; 3]) -> usize {
+        c[0] + self.dims[0] * (c[1] + self.dims[1] * c[2])
+    }
+
+    fn coords(&self, index: usize) -> [usize; 3] {
+        [index % self.dims[0], (index / self.dims[0]) % self.dims[1], index / (self.dims[0] * self.dims[1])]
+    }
+
+    fn cell(&self, p: &Vector3f) -> [usize; 3] {
+        let mut c = [0; 3];
+        for k in 0..3 {
+            let x = ((p[k] - self.origin[k]) / self.size).floor();
+            c[k] = (x.max(0.) as usize).min(self.dims[k] - 1);
+        }
+        return c;
+    }
+
+    /// Neighbor voxels sharing a face.
+    fn neighbors(&self, index: usize) -> Vec<usize> {
+        let c = self.coords(index);
+        let mut neighbors = Vec::with_capacity(6);
+        for k in 0..3 {
+            if c[k] > 0 {
+                let mut n = c;
+                n[k] -= 1;
+                neighbors.push(self.index(n));
+            }
+            if c[k] + 1 < self.dims[k] {
+                let mut n = c;
+                n[k] += 1;
+                neighbors.push(self.index(n));
+            }
+        }
+        return neighbors;
+    }
+
+    fn corner(&self, c: [usize; 3]) -> Vector3f {
+        self.origin + Vector3f::new(c[0] as Scalar, c[1] as Scalar, c[2] as Scalar) * self.size
+    }
+
+    /// Corners of the voxels of the part spanning the same convex hull: the
+    /// lowest and highest corner of each vertical line of corners.
+    fn hull_corners(&self, voxels: &[usize]) -> Vec<Vector3f> {
+        let mut columns: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for &v in voxels {
+            let c = self.coords(v);
+            for corner in 0..4 {
+                let column = columns.entry((c[0] + (corner & 1), c[1] + (corner >> 1)))
+                    .or_insert((c[2], c[2] + 1));
+                column.0 = column.0.min(c[2]);
+                column.1 = column.1.max(c[2] + 1);
+            }
+        }
+        let mut corners = Vec::with_capacity(2 * columns.len());
+        for ((x, y), (zmin, zmax)) in columns {
+            corners.push(self.corner([x, y, zmin]));
+            corners.push(self.corner([x, y, zmax]));
+        }
+        return corners;
+    }
+
+    /// Concavity of the part: volume of its hull outside the voxels.
+    fn concavity(&self, voxels: &[usize]) -> Scalar {
+        let hull_volume = ConvexHull::new(&self.hull_corners(voxels))
+            .map(|hull| hull.volume())
+            .unwrap_or(0.);
+        let volume = voxels.len() as Scalar * self.size.powi(3);
+        return (hull_volume - volume).max(0.);
+    }
+
+    /// Splits the part into its connected components.
+    fn components(&self, voxels: &[usize]) -> Vec<Vec<usize>> {
+        let mut mask = vec![false; self.state.len()];
+        for &v in voxels {
+            mask[v] = true;
+        }
+        let mut components = Vec::new();
+        for &seed in voxels {
+            if !mask[seed] {
+                continue;
+            }
+            mask[seed] = false;
+            let mut component = vec![seed];
+            let mut queue = VecDeque::new();
+            queue.push_back(seed);
+            while let Some(v) = queue.pop_front() {
+                for n in self.neighbors(v) {
+                    if mask[n] {
+                        mask[n] = false;
+                        component.push(n);
+                        queue.push_back(n);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        return components;
+    }
+
+    /// Convex hull of the mesh surface and interior voxels in the part.
+    fn hull(&self, voxels: &[usize]) -> Option<ConvexHull> {
+        let mut points = Vec::new();
+        for &v in voxels {
+            if self.state[v] == SURFACE {
+                points.extend(self.samples[&v].iter().cloned());
+            } else {
+                let c = self.coords(v);
+                for corner in 0..8 {
+                    points.push(self.corner([c[0] + (corner & 1), c[1] + ((corner >> 1) & 1), c[2] + (corner >> 2)]));
+                }
+            }
+        }
+        // flat surface: hull of the voxels
+        ConvexHull::new(&points).or_else(|| ConvexHull::new(&self.hull_corners(voxels)))
+    }
+}
+
+/// Height of the crossing of the vertical line at (x, y) with the triangle.
+fn ray_triangle_z(x: Scalar, y: Scalar, a: &Vector3f, b: &Vector3f, c: &Vector3f) -> Option<Scalar> {
+    let det = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+    if det == 0. {
+        return None;
+    }
+    let u = ((x - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (y - a[1])) / det;
+    let v = ((b[0] - a[0]) * (y - a[1]) - (x - a[0]) * (b[1] - a[1])) / det;
+    if u < 0. || v < 0. || u + v > 1. {
+        return None;
+    }
+    return Some(a[2] + u * (b[2] - a[2]) + v * (c[2] - a[2]));
+}
+
+/// Splits the part by the axis-aligned plane minimizing the concavity of
+/// both sides, and returns the connected components of the sides.
+fn split_part(grid: &VoxelGrid, voxels: &[usize], num_planes: usize) -> Option<Vec<Vec<usize>>> {
+    let mut lower = [usize::max_value(); 3];
+    let mut upper = [0; 3];
+    for &v in voxels {
+        let c = grid.coords(v);
+        for k in 0..3 {
+            lower[k] = lower[k].min(c[k]);
+            upper[k] = upper[k].max(c[k]);
+        }
+    }
+
+    let mut best: Option<(Scalar, Vec<usize>, Vec<usize>)> = None;
+    for k in 0..3 {
+        let span = upper[k] - lower[k];
+        if span == 0 {
+            continue;
+        }
+        let count = num_planes.max(1).min(span);
+        for s in 1..=count {
+            let plane = lower[k] + 1 + s * span / (count + 1);
+            let (left, right): (Vec<usize>, Vec<usize>) = voxels.iter()
+                .partition(|&&v| grid.coords(v)[k] < plane);
+            if left.is_empty() || right.is_empty() {
+                continue;
+            }
+            let cost = grid.concavity(&left) + grid.concavity(&right);
+            if best.as_ref().map_or(true, |b| cost < b.0) {
+                best = Some((cost, left, right));
+            }
+        }
+    }
+
+    let (_, left, right) = best?;
+    let mut parts = grid.components(&left);
+    parts.extend(grid.components(&right));
+    return Some(parts);
+}
+
+/// Merges the smallest part into the part with the closest center, until
+/// there are at most `max_parts` parts.
+fn merge_parts(grid: &VoxelGrid, parts: &mut Vec<Vec<usize>>, max_parts: usize) {
+    while parts.len() > max_parts.max(1) {
+        let centers: Vec<Vector3f> = parts.iter()
+            .map(|part| {
+                let sum = part.iter().fold(Vector3f::zeros(), |sum, &v| sum + grid.corner(grid.coords(v)));
+                sum / part.len() as Scalar
+            })
+            .collect();
+        let smallest = (0..parts.len()).min_by_key(|&i| parts[i].len()).unwrap();
+        let closest = (0..parts.len())
+            .filter(|&i| i != smallest)
+            .min_by(|&i, &j| (centers[i] - centers[smallest]).norm_squared()
+                .total_cmp(&(centers[j] - centers[smallest]).norm_squared()))
+            .unwrap();
+        let part = parts.swap_remove(smallest);
+        let closest = if closest == parts.len() { smallest } else { closest };
+        parts[closest].extend(part);
+    }
+}
+
+/// Computes an approximate convex decomposition of the mesh, as convex
+/// hulls in the frame of the mesh.
+///
+/// The mesh is repaired first, and should be closed so that its interior
+/// can be voxelized. If the mesh has more connected parts than
+/// `criteria.max_hulls`, the closest parts share a hull. Returns no hull if
+/// the mesh is empty or a vertex is not finite.
+///
+/// # Arguments
+///
+/// - `mesh`: triangle mesh
+/// - `criteria`: decomposition criteria
+pub fn convex_decomposition(mesh: &IndexedMesh, criteria: &DecompositionCriteria) -> Vec<ConvexHull> {
+    if mesh.vertices.iter().any(|v| !v.iter().all(|x| x.is_finite())) {
+        return Vec::new();
+    }
+    let mut mesh = mesh.clone();
+    mesh.repair(&RepairCriteria::default());
+    if mesh.faces.is_empty() {
+        return Vec::new();
+    }
+
+    let grid = VoxelGrid::new(&mesh, criteria.resolution);
+    let voxels: Vec<usize> = (0..grid.state.len()).filter(|&v| grid.state[v] != OUTSIDE).collect();
+    let max_concavity = criteria.max_concavity * voxels.len() as Scalar * grid.size.powi(3);
+
+    let mut components = grid.components(&voxels);
+    merge_parts(&grid, &mut components, criteria.max_hulls);
+
+    // split the most concave part first, as long as the parts fit in the
+    // maximum number of hulls
+    let mut parts: Vec<(Scalar, Vec<usize>)> = components.into_iter()
+        .map(|part| (grid.concavity(&part), part))
+        .collect();
+    let mut done = Vec::new();
+    while parts.len() + done.len() < criteria.max_hulls {
+        let worst = (0..parts.len())
+            .max_by(|&i, &j| parts[i].0.total_cmp(&parts[j].0));
+        let worst = match worst {
+            Some(i) if parts[i].0 > max_concavity => i,
+            _ => break,
+        };
+        let (_, part) = parts.swap_remove(worst);
+        match split_part(&grid, &part, criteria.num_planes) {
+            Some(split) if parts.len() + done.len() + split.len() <= criteria.max_hulls => {
+                parts.extend(split.into_iter().map(|p| (grid.concavity(&p), p)));
+            },
+            _ => done.push(part),
+        }
+    }
+    done.extend(parts.into_iter().map(|(_, part)| part));
+
+    return done.iter().filter_map(|part| grid.hull(part)).collect();
+}
+
+/// Fingerprint of the mesh: its sizes and a FNV-1a hash of its vertices
+/// and faces.
+fn mesh_fingerprint(mesh: &IndexedMesh) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    };
+    for vertex in &mesh.vertices {
+        for x in vertex {
+            feed(&x.to_bits().to_le_bytes());
+        }
+    }
+    for face in &mesh.faces {
+        for &v in &face.vertices {
+            feed(&(v as u64).to_le_bytes());
+        }
+    }
+    format!("{} vertices, {} faces, hash {:016x}", mesh.vertices.len(), mesh.faces.len(), hash)
+}
+
+/// Header comment of the convex hull files, recording the decomposed mesh
+/// and the criteria of the decomposition.
+fn cache_header(mesh: &IndexedMesh, criteria: &DecompositionCriteria) -> String {
+    format!("# convex decomposition of {}: {:?}", mesh_fingerprint(mesh), criteria)
+}
+
+/// Saves convex hulls to an OBJ file, one group per hull, after a header
+/// comment recording a fingerprint of the decomposed mesh and the criteria
+/// of the decomposition.
+///
+/// # Arguments
+///
+/// - `path`: OBJ file
+/// - `hulls`: convex hulls
+/// - `mesh`: decomposed mesh
+/// - `criteria`: criteria of the decomposition
+pub fn save_convex_hulls<P: AsRef<Path>>(path: P, hulls: &[ConvexHull], mesh: &IndexedMesh,
+                                         criteria: &DecompositionCriteria) -> Result<()> {
+    let mut output = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    let mut groups = Vec::new();
+    for (i, hull) in hulls.iter().enumerate() {
+        let part = hull.to_indexed_mesh();
+        let (offset, start) = (output.vertices.len(), output.faces.len());
+        output.vertices.extend(part.vertices);
+        output.faces.extend(part.faces.into_iter().map(|mut face| {
+            for k in 0..3 {
+                face.vertices[k] += offset;
+            }
+            face
+        }));
+        groups.push(ObjGroup { name: format!("hull_{}", i), faces: start..output.faces.len() });
+    }
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", cache_header(mesh, criteria))?;
+    return write_obj(&mut file, &output, &groups);
+}
+
+/// Loads convex hulls saved by `save_convex_hulls`. Returns an error of
+/// kind `InvalidData` if they were computed from another mesh or with other
+/// criteria.
+///
+/// # Arguments
+///
+/// - `path`: OBJ file
+/// - `mesh`: expected decomposed mesh
+/// - `criteria`: expected criteria of the decomposition
+pub fn load_convex_hulls<P: AsRef<Path>>(path: P, mesh: &IndexedMesh,
+                                         criteria: &DecompositionCriteria) -> Result<Vec<ConvexHull>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    if header.trim_end() != cache_header(mesh, criteria) {
+        return Err(Error::new(ErrorKind::InvalidData, "convex hulls computed from another mesh or with other criteria"));
+    }
+    let (hull_mesh, groups) = read_obj_groups(&mut reader)?;
+    let mut hulls = Vec::new();
+    for group in groups.iter().filter(|group| !group.faces.is_empty()) {
+        let mut indices: Vec<usize> = hull_mesh.faces[group.faces.clone()].iter()
+            .flat_map(|face| face.vertices.iter().cloned())
+            .collect();
+        indices.sort();
+        indices.dedup();
+        let points = indices.iter()
+            .map(|&i| Vector3f::new(hull_mesh.vertices[i][0] as Scalar,
+                                    hull_mesh.vertices[i][1] as Scalar,
+                                    hull_mesh.vertices[i][2] as Scalar))
+            .collect();
+        let hull = ConvexHull::new(&points).ok_or_else(|| Error::new(
+            ErrorKind::InvalidData, format!("flat convex hull: {}", group.name)))?;
+        hulls.push(hull);
+    }
+    return Ok(hulls);
+}
@@ -0,0 +1,368 @@
+use crate::math::{Vector3f, Matrix3f, Scalar};
+use crate::ccd::{CCDObject, CCDCriteria, CCDResult, mpr_penetration};
+use crate::ccd::helper::*;
+
+/// Separation distance between two objects, with the closest points on
+/// both objects.
+#[derive(Debug, Clone)]
+pub struct CCDDistance {
+    pub distance: Scalar,
+    pub point1:   Vector3f,             // closest point on obj1
+    pub point2:   Vector3f,             // closest point on obj2
+}
+
+impl CCDDistance {
+    pub fn new() -> Self {
+        CCDDistance {
+            distance: 0.0,
+            point1: Vector3f::zeros(),
+            point2: Vector3f::zeros(),
+        }
+    }
+}
+
+/// Returns true if two given objects intersect - GJK algorithm is used.
+pub fn gjk_intersect(obj1: &dyn CCDObject,
+                     obj2: &dyn CCDObject,
+                     ccd: &CCDCriteria) -> bool {
+    let mut simplex = Vec::new();
+    let (intersect, _) = gjk(obj1, obj2, ccd, &mut simplex);
+    return intersect;
+}
+
+/// Computes the separation distance between obj1 and obj2, and the closest
+/// points on both objects (witness points), using the GJK algorithm.
+///
+/// The distance is accurate up to `ccd.dist_tolerance`. Returns false if
+/// the objects intersect, in which case the distance is zero and both
+/// witness points lie in the intersection.
+pub fn gjk_distance(obj1: &dyn CCDObject,
+                    obj2: &dyn CCDObject,
+                    ccd: &CCDCriteria,
+                    info: &mut CCDDistance) -> bool {
+    let mut simplex = Vec::new();
+    let (intersect, weights) = gjk(obj1, obj2, ccd, &mut simplex);
+
+    info.point1 = Vector3f::zeros();
+    info.point2 = Vector3f::zeros();
+    for (support, w) in simplex.iter().zip(weights.iter()) {
+        info.point1 += support.v1 * *w;
+        info.point2 += support.v2 * *w;
+    }
+    if intersect {
+        info.distance = 0.;
+        info.point1 = 0.5 * (info.point1 + info.point2);
+        info.point2 = info.point1;
+        return false;
+    }
+    info.distance = (info.point1 - info.point2).norm();
+    return true;
+}
+
+/// Computes penetration of obj2 into obj1, with the same conventions as
+/// `mpr_penetration`: if obj2 is translated by the depth in the resulting
+/// direction, obj1 and obj2 have a touching contact.
+///
+/// GJK detects the intersection and EPA (Expanding Polytope Algorithm)
+/// finds the exact penetration, up to `ccd.epa_tolerance`. If the
+/// polytope degenerates, the penetration of `mpr_penetration` is used.
+///
+/// Returns false if the objects do not intersect.
+pub fn gjk_penetration(obj1: &dyn CCDObject,
+                       obj2: &dyn CCDObject,
+                       ccd: &CCDCriteria,
+                       info: &mut CCDResult) -> bool {
+    let mut simplex = Vec::new();
+    let (intersect, weights) = gjk(obj1, obj2, ccd, &mut simplex);
+    if !intersect {
+        return false;
+    }
+
+    let mut pos = Vector3f::zeros();
+    for (support, w) in simplex.iter().zip(weights.iter()) {
+        pos += 0.5 * (support.v1 + support.v2) * *w;
+    }
+    if !simplex_to_polytope(obj1, obj2, ccd, &mut simplex) || !epa(obj1, obj2, ccd, simplex, info) {
+        // flat Minkowski difference or degenerate polytope: MPR still finds a
+        // direction, otherwise the contact is touching
+        if !mpr_penetration(obj1, obj2, ccd, info) {
+            info.depth = 0.;
+            info.dir   = Vector3f::zeros();
+            info.pos   = pos;
+        }
+    }
+    return true;
+}
+
+/// Runs GJK on the Minkowski difference of the objects. The simplex keeps
+/// the support points of the closest feature, with their barycentric
+/// weights. Returns true with the weights of the closest point to origin if
+/// the objects intersect.
+fn gjk(obj1: &dyn CCDObject,
+       obj2: &dyn CCDObject,
+       ccd: &CCDCriteria,
+       simplex: &mut Vec<CCDSupport>) -> (bool, Vec<Scalar>) {
+    let mut dir = obj2.center() - obj1.center();
+    if is_zero_approx(dir.norm_squared()) {
+        dir = Vector3f::new(1., 0., 0.);
+    }
+    simplex.clear();
+    simplex.push(CCDSupport::from(obj1, obj2, &dir.normalize(), ccd));
+
+    let mut weights = vec![1.];
+    for _ in 0..ccd.max_iterations {
+        // reduce the simplex to the feature closest to origin
+        let (indices, lambdas, v) = simplex_closest_point(simplex);
+        *simplex = indices.iter().map(|&i| simplex[i].clone()).collect();
+        weights = lambdas;
+
+        let dist = v.norm();
+        if dist <= ccd.dist_tolerance {
+            return (true, weights);
+        }
+
+        let dir = -v / dist;
+        let w = CCDSupport::from(obj1, obj2, &dir, ccd);
+
+        // no progress towards origin: v is the closest point
+        if dist * dist - v.dot(&w.v) <= ccd.dist_tolerance * dist
+            || simplex.iter().any(|s| vec_eq_approx(&s.v, &w.v)) {
+            return (false, weights);
+        }
+        simplex.push(w);
+    }
+
+    let dist = simplex.iter().zip(weights.iter())
+        .fold(Vector3f::zeros(), |p, (s, w)| p + s.v * *w)
+        .norm();
+    return (dist <= ccd.dist_tolerance, weights);
+}
+
+/// Returns the closest point to origin of the convex hull of the simplex,
+/// with the indices of the vertices of the closest feature and their
+/// barycentric weights.
+///
+/// The closest point is the projection of origin on the affine hull of one
+/// of the faces with non negative weights, so all faces are tried.
+fn simplex_closest_point(simplex: &[CCDSupport]) -> (Vec<usize>, Vec<Scalar>, Vector3f) {
+    let n = simplex.len();
+    let mut best: Option<(Vec<usize>, Vec<Scalar>, Vector3f)> = None;
+    let mut best_dist = Scalar::INFINITY;
+
+    // faces by increasing dimension, to keep the smallest closest feature
+    let mut masks: Vec<usize> = (1..(1 << n)).collect();
+    masks.sort_by_key(|mask| mask.count_ones());
+
+    for mask in masks {
+        let indices: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+        let y0 = simplex[indices[0]].v;
+        let m = indices.len() - 1;
+
+        // normal equations of the projection: G mu = -D^T y0
+        let mut gram = Matrix3f::identity();
+        let mut rhs = Vector3f::zeros();
+        let mut scale: Scalar = 1.;
+        for i in 0..m {
+            let di = simplex[indices[i + 1]].v - y0;
+            for j in 0..m {
+                gram[(i, j)] = di.dot(&(simplex[indices[j + 1]].v - y0));
+            }
+            rhs[i] = -di.dot(&y0);
+            scale *= gram[(i, i)];
+        }
+        if m > 0 && gram.determinant().abs() <= 1e-12 * scale {
+            continue;
+        }
+        let mu = match gram.try_inverse() {
+            Some(inv) => inv * rhs,
+            None => continue,
+        };
+
+        let mut lambdas = vec![1. - (0..m).map(|i| mu[i]).sum::<Scalar>()];
+        lambdas.extend((0..m).map(|i| mu[i]));
+        if lambdas.iter().any(|&l| l < 0.) {
+            continue;
+        }
+
+        let mut p = Vector3f::zeros();
+        for (i, l) in indices.iter().zip(lambdas.iter()) {
+            p += simplex[*i].v * *l;
+        }
+        let dist = p.norm_squared();
+        if dist < best_dist * (1. - 1e-12) {
+            best_dist = dist;
+            best = Some((indices, lambdas, p));
+        }
+    }
+
+    return best.unwrap_or_else(|| (vec![0], vec![1.], simplex[0].v));
+}
+
+/// Grows the simplex containing origin into a tetrahedron, adding support
+/// points off its affine hull. Returns false if the Minkowski difference
+/// is flat around origin.
+fn simplex_to_polytope(obj1: &dyn CCDObject,
+                       obj2: &dyn CCDObject,
+                       ccd: &CCDCriteria,
+                       simplex: &mut Vec<CCDSupport>) -> bool {
+    let axes = [Vector3f::x(), Vector3f::y(), Vector3f::z()];
+
+    while simplex.len() < 4 {
+        let mut dirs = Vec::new();
+        match simplex.len() {
+            1 => dirs.extend(axes.iter().cloned()),
+            2 => {
+                let d = simplex[1].v - simplex[0].v;
+                for axis in &axes {
+                    let n = d.cross(axis);
+                    if !is_zero_approx(n.norm_squared()) {
+                        dirs.push(n.normalize());
+                    }
+                }
+            },
+            _ => {
+                let n = (simplex[1].v - simplex[0].v).cross(&(simplex[2].v - simplex[0].v));
+                if !is_zero_approx(n.norm_squared()) {
+                    dirs.push(n.normalize());
+                }
+            },
+        }
+
+        // furthest support point from the affine hull
+        let mut best: Option<CCDSupport> = None;
+        let mut best_dist = ccd.epa_tolerance * 1e-3;
+        for dir in dirs.iter().flat_map(|d| vec![*d, -*d]) {
+            let w = CCDSupport::from(obj1, obj2, &dir, ccd);
+            let dist = affine_hull_dist(simplex, &w.v);
+            if dist > best_dist {
+                best_dist = dist;
+                best = Some(w);
+            }
+        }
+        match best {
+            Some(w) => simplex.push(w),
+            None => return false,
+        }
+    }
+
+    return true;
+}
+
+/// Distance of the point to the affine hull of the simplex, of at most
+/// three points.
+fn affine_hull_dist(simplex: &[CCDSupport], p: &Vector3f) -> Scalar {
+    let a = p - simplex[0].v;
+    match simplex.len() {
+        1 => a.norm(),
+        2 => {
+            let d = simplex[1].v - simplex[0].v;
+            a.cross(&d).norm() / d.norm()
+        },
+        _ => {
+            let n = (simplex[1].v - simplex[0].v).cross(&(simplex[2].v - simplex[0].v));
+            a.dot(&n).abs() / n.norm()
+        },
+    }
+}
+
+struct EpaFace {
+    vertices: [usize; 3],
+    normal:   Vector3f,
+    dist:     Scalar,
+}
+
+impl EpaFace {
+    fn new(points: &[CCDSupport], vertices: [usize; 3]) -> Option<Self> {
+        let (a, b, c) = (points[vertices[0]].v, points[vertices[1]].v, points[vertices[2]].v);
+        let normal = (b - a).cross(&(c - a));
+        let norm = normal.norm();
+        if is_zero_approx(norm) {
+            return None;
+        }
+        let normal = normal / norm;
+        Some(EpaFace { vertices, normal, dist: normal.dot(&a) })
+    }
+}
+
+/// Expands the polytope of the Minkowski difference containing origin
+/// towards its closest face. Returns false if the polytope degenerates,
+/// i.e. has no face left.
+fn epa(obj1: &dyn CCDObject,
+       obj2: &dyn CCDObject,
+       ccd: &CCDCriteria,
+       mut points: Vec<CCDSupport>,
+       info: &mut CCDResult) -> bool {
+
+    // tetrahedron with faces oriented outwards
+    let volume = (points[1].v - points[0].v)
+        .cross(&(points[2].v - points[0].v))
+        .dot(&(points[3].v - points[0].v));
+    if volume > 0. {
+        points.swap(1, 2);
+    }
+    let mut faces: Vec<EpaFace> = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]].iter()
+        .filter_map(|&v| EpaFace::new(&points, v))
+        .collect();
+
+    let mut iter: usize = 0;
+    loop {
+        let closest = match (0..faces.len()).min_by(|&i, &j| faces[i].dist.total_cmp(&faces[j].dist)) {
+            Some(i) => i,
+            None => return false,
+        };
+        let face = &faces[closest];
+        let w = CCDSupport::from(obj1, obj2, &face.normal, ccd);
+
+        if w.v.dot(&face.normal) - face.dist <= ccd.epa_tolerance || iter >= ccd.max_iterations {
+            find_epa_penetration(&points, face, info);
+            return true;
+        }
+
+        // remove the faces seen from w, keeping their horizon
+        let wi = points.len();
+        points.push(w);
+        let mut horizon: Vec<(usize, usize)> = Vec::new();
+        let mut kept = Vec::new();
+        for face in faces {
+            let a = &points[face.vertices[0]].v;
+            if face.normal.dot(&(points[wi].v - a)) > CCD_EPS {
+                for k in 0..3 {
+                    let (a, b) = (face.vertices[k], face.vertices[(k + 1) % 3]);
+                    match horizon.iter().position(|&e| e == (b, a)) {
+                        Some(i) => { horizon.swap_remove(i); },
+                        None => horizon.push((a, b)),
+                    }
+                }
+            } else {
+                kept.push(face);
+            }
+        }
+        for (a, b) in horizon {
+            if let Some(face) = EpaFace::new(&points, [a, b, wi]) {
+                kept.push(face);
+            }
+        }
+        faces = kept;
+        iter += 1;
+    }
+}
+
+fn find_epa_penetration(points: &[CCDSupport], face: &EpaFace, info: &mut CCDResult) {
+    let depth = face.dist.max(0.);
+    let p = face.normal * depth;
+
+    // barycentric coordinates of the projection of origin on the face
+    let [a, b, c] = face.vertices;
+    let (va, vb, vc) = (points[a].v, points[b].v, points[c].v);
+    let area = (vb - va).cross(&(vc - va)).dot(&face.normal);
+    let la = (vb - p).cross(&(vc - p)).dot(&face.normal) / area;
+    let lb = (vc - p).cross(&(va - p)).dot(&face.normal) / area;
+    let lc = 1. - la - lb;
+
+    let p1 = points[a].v1 * la + points[b].v1 * lb + points[c].v1 * lc;
+    let p2 = points[a].v2 * la + points[b].v2 * lb + points[c].v2 * lc;
+
+    info.depth = depth;
+    info.dir   = face.normal;
+    info.pos   = 0.5 * (p1 + p2);
+}
@@ -1,14 +1,28 @@
 pub mod obb;
 pub mod obb_tree;
+pub mod surface_intersection;
+pub mod surface_distance;
 mod mpr;
+mod gjk;
 mod helper;
 mod object;
+mod convex_hull;
+mod decomposition;
+mod compound;
+mod toi;
 
 pub use self::obb::*;
 pub use self::obb_tree::*;
+pub use self::surface_intersection::*;
+pub use self::surface_distance::*;
 pub use self::mpr::*;
+pub use self::gjk::*;
 pub use self::helper::*;
 pub use self::object::*;
+pub use self::convex_hull::*;
+pub use self::decomposition::*;
+pub use self::compound::*;
+pub use self::toi::*;
 use crate::math::{Vector3f, Scalar};
 
 
@@ -17,6 +31,7 @@ pub struct CCDCriteria {
     pub epa_tolerance:  Scalar,
     pub mpr_tolerance:  Scalar,
     pub dist_tolerance: Scalar,
+    pub toi_tolerance:  Scalar,
 }
 
 pub trait CCDObject {
@@ -37,6 +52,7 @@ impl CCDCriteria {
             epa_tolerance:  1e-4,
             mpr_tolerance:  1e-4,
             dist_tolerance: 1e-6,
+            toi_tolerance:  1e-4,
         }
     }
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
 use crate::math::*;
 use crate::simulation::sim_model::SimScene;
 use kiss3d::scene::SceneNode;
-use crate::geometry::NurbsSurface;
+use crate::geometry::{NurbsSurface, TrimmedNurbsSurface};
+use crate::ccd::OBBTree;
 use core::fmt;
 use std::fmt::Formatter;
 
@@ -138,6 +139,36 @@ impl OBB {
         return true;
     }
 
+    /// Returns a lower bound of the distance between this OBB and `b`.
+    ///
+    /// The gap between the projections of both boxes onto each of the 15
+    /// separating axes candidates is a lower bound of their distance, and
+    /// the largest one is returned. Zero is returned if the boxes intersect.
+    pub fn separation(&self, b: &OBB) -> Scalar {
+        let t: Vector3f = b.pos - self.pos;
+
+        let mut axes = Vec::with_capacity(15);
+        for i in 0..3 {
+            axes.push(self.axis.column(i).into_owned());
+            axes.push(b.axis.column(i).into_owned());
+            for j in 0..3 {
+                let axis = self.axis.column(i).cross(&b.axis.column(j));
+                if let Some(axis) = axis.try_normalize(1e-9) {
+                    axes.push(axis);
+                }
+            }
+        }
+
+        let mut sep: Scalar = 0.;
+        for l in &axes {
+            let ra = (0..3).map(|i| self.r[i] * self.axis.column(i).dot(l).abs()).sum::<Scalar>();
+            let rb = (0..3).map(|i| b.r[i] * b.axis.column(i).dot(l).abs()).sum::<Scalar>();
+            sep = sep.max(t.dot(l).abs() - ra - rb);
+        }
+
+        return sep;
+    }
+
     fn center(points: &Vec<Vector3f>) -> Vector3f {
         assert!(points.len() > 0);
 
@@ -234,6 +265,26 @@ impl <'a> From<&'a NurbsSurface> for OBB {
     }
 }
 
+impl <'a> From<&'a TrimmedNurbsSurface> for OBB {
+    /// Bounds the patches of a 4-level subdivision of the surface which
+    /// are not fully trimmed away.
+    fn from(surf: &'a TrimmedNurbsSurface) -> Self {
+        let tree = OBBTree::from_trimmed_nurbs_surface(surf, 4);
+        let mut corners = Vec::new();
+        for obb in tree.collect_base_obb() {
+            for &(a, b, c) in &[(-1., -1., -1.), (-1., -1., 1.), (-1., 1., -1.), (-1., 1., 1.),
+                                (1., -1., -1.), (1., -1., 1.), (1., 1., -1.), (1., 1., 1.)] {
+                let local = Vector3f::new(a * obb.r[0], b * obb.r[1], c * obb.r[2]);
+                corners.push(obb.pos + obb.axis * local);
+            }
+        }
+        if corners.is_empty() {
+            return Self::from(&surf.surface);
+        }
+        return Self::from_point_cloud(&corners);
+    }
+}
+
 impl fmt::Display for OBB {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "({}, {})", self.axis.transpose(), self.r.transpose())
@@ -1,51 +1,207 @@
-use crate::geometry::NurbsSurface;
-use crate::ccd::OBB;
+use crate::geometry::{NurbsSurface, TrimmedNurbsSurface, Containment};
+use crate::ccd::{OBB, DistanceCriteria, SurfaceDistance, minimize_distance};
 use core::fmt;
 use std::fmt::Formatter;
 use crate::math::*;
 
+/// Parametric domain `(u_min, u_max, v_min, v_max)` of a surface patch.
+pub type ParamDomain = (Scalar, Scalar, Scalar, Scalar);
+
 #[derive(Clone)]
 pub struct OBBTree {
-    left:  Option<Box<OBBTree>>,
-    right: Option<Box<OBBTree>>,
-    obb:   Option<Box<OBB>>,
-    level: usize,
+    left:   Option<Box<OBBTree>>,
+    right:  Option<Box<OBBTree>>,
+    obb:    Option<Box<OBB>>,
+    level:  usize,
+    domain: ParamDomain,     // domain of the patch in the parameters of the root surface
 }
 
 impl OBBTree {
 
     pub fn new() -> Self {
         OBBTree {
-            left:  None,
-            right: None,
-            obb:   None,
-            level: 0,
+            left:   None,
+            right:  None,
+            obb:    None,
+            level:  0,
+            domain: (0., 1., 0., 1.),
         }
     }
 
 
     pub fn from_nurbs_surface(surf: &NurbsSurface, level: usize) -> Self {
+        Self::from_nurbs_surface_patch(surf, level, (0., 1., 0., 1.))
+    }
+
+    /// Builds the OBB tree of a trimmed surface. The patches lying fully
+    /// outside of the trimmed domain are pruned, i.e. their nodes have no
+    /// OBB and no children, and never collide. The patches crossed by the
+    /// trimming loops keep the OBB of the whole patch.
+    pub fn from_trimmed_nurbs_surface(surf: &TrimmedNurbsSurface, level: usize) -> Self {
+        Self::from_nurbs_surface_patch_trimmed(&surf.surface, level, (0., 1., 0., 1.), Some(surf))
+    }
+
+    fn from_nurbs_surface_patch(surf: &NurbsSurface, level: usize, domain: ParamDomain) -> Self {
+        Self::from_nurbs_surface_patch_trimmed(surf, level, domain, None)
+    }
+
+    fn from_nurbs_surface_patch_trimmed(surf: &NurbsSurface, level: usize, domain: ParamDomain,
+                                        trimmed: Option<&TrimmedNurbsSurface>) -> Self {
+        let trimmed = match trimmed.map(|t| (t, t.classify(domain))) {
+            Some((_, Containment::Outside)) => {
+                return OBBTree {
+                    left:   None,
+                    right:  None,
+                    obb:    None,
+                    level:  level,
+                    domain: domain,
+                };
+            },
+            Some((_, Containment::Inside)) => None,
+            other => other.map(|(t, _)| t),
+        };
+
         if level == 0 {
             OBBTree {
-                left:  None,
-                right: None,
-                obb:   Some(Box::new(OBB::from(surf))),
-                level: level,
+                left:   None,
+                right:  None,
+                obb:    Some(Box::new(OBB::from(surf))),
+                level:  level,
+                domain: domain,
             }
         } else {
-            let (surf1, surf2) = if level % 2 == 0 {
-                surf.split_surface_u(0.5)
+            // the patches are split in the middle of their (normalized)
+            // domain, which is also the middle of `domain`
+            let (u0, u1, v0, v1) = domain;
+            let (surf1, surf2, domain1, domain2) = if level % 2 == 0 {
+                let (surf1, surf2) = surf.split_surface_u(0.5);
+                let um = 0.5 * (u0 + u1);
+                (surf1, surf2, (u0, um, v0, v1), (um, u1, v0, v1))
             } else {
-                surf.split_surface_v(0.5)
+                let (surf1, surf2) = surf.split_surface_v(0.5);
+                let vm = 0.5 * (v0 + v1);
+                (surf1, surf2, (u0, u1, v0, vm), (u0, u1, vm, v1))
             };
 
             OBBTree {
-                left:  Some(Box::new(Self::from_nurbs_surface(&surf1, level-1))),
-                right: Some(Box::new(Self::from_nurbs_surface(&surf2, level-1))),
-                obb:   Some(Box::new(OBB::from(surf))),
-                level: level,
+                left:   Some(Box::new(Self::from_nurbs_surface_patch_trimmed(&surf1, level-1, domain1, trimmed))),
+                right:  Some(Box::new(Self::from_nurbs_surface_patch_trimmed(&surf2, level-1, domain2, trimmed))),
+                obb:    Some(Box::new(OBB::from(surf))),
+                level:  level,
+                domain: domain,
+            }
+        }
+    }
+
+    /// Returns the parametric domain covered by this node.
+    pub fn domain(&self) -> ParamDomain {
+        self.domain
+    }
+
+    fn collect_leaf_pairs_helper(&self, other: &OBBTree, epsilon: Scalar,
+                                 list: &mut Vec<(ParamDomain, ParamDomain)>) {
+        if let (Some(a), Some(b)) = (&self.obb, &other.obb) {
+            if !a.intersects(b, epsilon) {
+                return;
+            }
+        } else {
+            return;
+        }
+
+        match (&self.left, &self.right, &other.left, &other.right) {
+            (Some(l1), Some(r1), Some(l2), Some(r2)) => {
+                l1.collect_leaf_pairs_helper(l2, epsilon, list);
+                l1.collect_leaf_pairs_helper(r2, epsilon, list);
+                r1.collect_leaf_pairs_helper(l2, epsilon, list);
+                r1.collect_leaf_pairs_helper(r2, epsilon, list);
+            },
+            (Some(l1), Some(r1), _, _) => {
+                l1.collect_leaf_pairs_helper(other, epsilon, list);
+                r1.collect_leaf_pairs_helper(other, epsilon, list);
+            },
+            (_, _, Some(l2), Some(r2)) => {
+                self.collect_leaf_pairs_helper(l2, epsilon, list);
+                self.collect_leaf_pairs_helper(r2, epsilon, list);
+            },
+            _ => list.push((self.domain, other.domain)),
+        }
+    }
+
+    /// Collects the parametric domains of all pairs of leaves whose OBBs
+    /// intersect.
+    pub fn collect_leaf_pairs(&self, other: &OBBTree, epsilon: Scalar) -> Vec<(ParamDomain, ParamDomain)> {
+        let mut res = Vec::new();
+        self.collect_leaf_pairs_helper(other, epsilon, &mut res);
+        return res;
+    }
+
+    fn children(&self) -> Vec<&OBBTree> {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => vec![left.as_ref(), right.as_ref()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn distance_helper(&self, other: &OBBTree,
+                       surf1: &NurbsSurface, surf2: &NurbsSurface,
+                       criteria: &DistanceCriteria, best: &mut SurfaceDistance) {
+        let separation = match (&self.obb, &other.obb) {
+            (Some(a), Some(b)) => a.separation(b),
+            _ => return,
+        };
+        if separation >= best.distance {
+            return;
+        }
+
+        let children1 = self.children();
+        let children2 = other.children();
+        if children1.is_empty() && children2.is_empty() {
+            let res = minimize_distance(surf1, surf2, &self.domain, &other.domain, criteria);
+            if res.distance < best.distance {
+                *best = res;
             }
+            return;
         }
+
+        // descend into the closest pairs of children first
+        let nodes1 = if children1.is_empty() { vec![self] } else { children1 };
+        let nodes2 = if children2.is_empty() { vec![other] } else { children2 };
+        let mut pairs = Vec::new();
+        for a in &nodes1 {
+            for b in &nodes2 {
+                let separation = match (&a.obb, &b.obb) {
+                    (Some(obb_a), Some(obb_b)) => obb_a.separation(obb_b),
+                    _ => INFINITY,
+                };
+                pairs.push((separation, *a, *b));
+            }
+        }
+        pairs.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+        for (_, a, b) in pairs {
+            a.distance_helper(b, surf1, surf2, criteria, best);
+        }
+    }
+
+    /// Computes the minimum distance between the surfaces `surf1` and
+    /// `surf2`, whose OBB trees are `self` and `other` respectively.
+    ///
+    /// Pairs of nodes are pruned when the separation of their OBBs exceeds
+    /// the smallest distance found so far. For the remaining pairs of leaves
+    /// the distance is minimized locally, starting from the patch centers.
+    ///
+    /// # Arguments
+    ///
+    /// - `other`: OBB tree of `surf2`
+    /// - `surf1`: surface from which `self` was built
+    /// - `surf2`: surface from which `other` was built
+    /// - `criteria`: query parameters
+    pub fn distance(&self, other: &OBBTree,
+                    surf1: &NurbsSurface, surf2: &NurbsSurface,
+                    criteria: &DistanceCriteria) -> SurfaceDistance {
+        let mut best = SurfaceDistance::new();
+        self.distance_helper(other, surf1, surf2, criteria, &mut best);
+        return best;
     }
 
     fn collect_base_obb_helper(&self, list: &mut Vec<OBB>) {
@@ -54,8 +210,10 @@ impl OBBTree {
                 list.push(obb.as_ref().clone());
             }
         } else {
-            self.left.as_ref().unwrap().collect_base_obb_helper(list);
-            self.right.as_ref().unwrap().collect_base_obb_helper(list);
+            // nodes of trimmed away patches have no children
+            for child in self.children() {
+                child.collect_base_obb_helper(list);
+            }
         }
     }
 
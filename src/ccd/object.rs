@@ -1,4 +1,4 @@
-use crate::math::{Vector3f, Matrix3f, Scalar};
+use crate::math::{Vector3f, Matrix3f, Scalar, Isometry3f, Point3f};
 use crate::ccd::{CCDObject, CCD_ZERO, is_zero_approx, CCD_EPS};
 
 pub struct Box {
@@ -40,7 +40,7 @@ impl CCDObject for Sphere {
         let dir_local = self.rotm.transpose() * dir;
         let len = dir_local.norm_squared();
         let vec_local = if len - CCD_EPS > CCD_ZERO {
-            dir * self.radius / len.sqrt()
+            dir_local * self.radius / len.sqrt()
         } else {
             Vector3f::zeros()
         };
@@ -80,3 +80,208 @@ impl CCDObject for Cylinder {
         return vec;
     }
 }
+
+/// Cylinder with hemispherical caps, aligned with the local z-axis. The
+/// height is the one of the cylinder part, as the length of URDF capsules.
+pub struct Capsule {
+    pub pos: Vector3f,
+    pub rotm: Matrix3f,
+    pub radius: Scalar,
+    pub height: Scalar,
+}
+
+impl CCDObject for Capsule {
+    fn center(&self) -> Vector3f {
+        self.pos.clone_owned()
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        let dir_local = self.rotm.transpose() * dir;
+        let len = dir_local.norm_squared();
+        let mut vec_local = if len - CCD_EPS > CCD_ZERO {
+            dir_local * self.radius / len.sqrt()
+        } else {
+            Vector3f::zeros()
+        };
+        vec_local[2] += dir_local[2].signum() * self.height * 0.5;
+        let vec = self.rotm * &vec_local + &self.pos;
+        return vec;
+    }
+}
+
+/// Cone aligned with the local z-axis, with its base at `-height / 2` and
+/// its apex at `height / 2`.
+pub struct Cone {
+    pub pos: Vector3f,
+    pub rotm: Matrix3f,
+    pub radius: Scalar,
+    pub height: Scalar,
+}
+
+impl CCDObject for Cone {
+    fn center(&self) -> Vector3f {
+        // centroid, a quarter of the height above the base
+        self.rotm * Vector3f::new(0., 0., -self.height * 0.25) + &self.pos
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        let dir_local = self.rotm.transpose() * dir;
+
+        // apex or the furthest point of the base circle
+        let apex = Vector3f::new(CCD_ZERO, CCD_ZERO, self.height * 0.5);
+        let dist_z = (dir_local[0] * dir_local[0] + dir_local[1] * dir_local[1]).sqrt();
+        let rim = if is_zero_approx(dist_z) {
+            Vector3f::new(CCD_ZERO, CCD_ZERO, -self.height * 0.5)
+        } else {
+            let rad = self.radius / dist_z;
+            Vector3f::new(rad * dir_local[0], rad * dir_local[1], -self.height * 0.5)
+        };
+        let vec_local = if apex.dot(&dir_local) >= rim.dot(&dir_local) { apex } else { rim };
+
+        let vec = self.rotm * &vec_local + &self.pos;
+        return vec;
+    }
+}
+
+/// Ellipsoid with semi-axes along the local axes.
+pub struct Ellipsoid {
+    pub pos: Vector3f,
+    pub rotm: Matrix3f,
+    pub radii: Vector3f,
+}
+
+impl CCDObject for Ellipsoid {
+    fn center(&self) -> Vector3f {
+        self.pos.clone_owned()
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        // maps the unit sphere support: R^2 d / |R d|
+        let dir_local = self.rotm.transpose() * dir;
+        let scaled = dir_local.component_mul(&self.radii);
+        let len = scaled.norm_squared();
+        let vec_local = if len - CCD_EPS > CCD_ZERO {
+            scaled.component_mul(&self.radii) / len.sqrt()
+        } else {
+            Vector3f::zeros()
+        };
+        let vec = self.rotm * &vec_local + &self.pos;
+        return vec;
+    }
+}
+
+/// Returns the furthest point in the direction.
+fn furthest_point(points: &[Vector3f], dir: &Vector3f) -> Vector3f {
+    let mut best = 0;
+    for i in 1..points.len() {
+        if points[i].dot(dir) > points[best].dot(dir) {
+            best = i;
+        }
+    }
+    return points[best];
+}
+
+/// Triangle given by its vertices in world coordinates.
+pub struct Triangle {
+    pub vertices: [Vector3f; 3],
+}
+
+impl CCDObject for Triangle {
+    fn center(&self) -> Vector3f {
+        (self.vertices[0] + self.vertices[1] + self.vertices[2]) / 3.
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        furthest_point(&self.vertices, dir)
+    }
+}
+
+/// Segment given by its end points in world coordinates.
+pub struct Segment {
+    pub points: [Vector3f; 2],
+}
+
+impl CCDObject for Segment {
+    fn center(&self) -> Vector3f {
+        (self.points[0] + self.points[1]) * 0.5
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        furthest_point(&self.points, dir)
+    }
+}
+
+/// Convex polytope given by its vertices in the local frame. The support
+/// checks all vertices; see `ConvexHull` for large polytopes.
+pub struct Polytope {
+    pub pos: Vector3f,
+    pub rotm: Matrix3f,
+    pub vertices: Vec<Vector3f>,
+}
+
+impl CCDObject for Polytope {
+    fn center(&self) -> Vector3f {
+        let sum = self.vertices.iter().fold(Vector3f::zeros(), |s, v| s + v);
+        self.rotm * (sum / self.vertices.len() as Scalar) + &self.pos
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        let dir_local = self.rotm.transpose() * dir;
+        let vec = self.rotm * furthest_point(&self.vertices, &dir_local) + &self.pos;
+        return vec;
+    }
+}
+
+/// Minkowski sum of two objects, e.g. a shape swept along a segment or
+/// rounded by a sphere at the origin.
+pub struct MinkowskiSum<A, B> {
+    pub obj1: A,
+    pub obj2: B,
+}
+
+impl<A: CCDObject, B: CCDObject> CCDObject for MinkowskiSum<A, B> {
+    fn center(&self) -> Vector3f {
+        self.obj1.center() + self.obj2.center()
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        self.obj1.support(dir) + self.obj2.support(dir)
+    }
+}
+
+/// Object moved by a rigid transform.
+pub struct Transformed<T> {
+    pub tform: Isometry3f,
+    pub obj: T,
+}
+
+impl<T: CCDObject> CCDObject for Transformed<T> {
+    fn center(&self) -> Vector3f {
+        self.tform.transform_point(&Point3f::from(self.obj.center())).coords
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        let dir_local = self.tform.inverse_transform_vector(dir);
+        self.tform.transform_point(&Point3f::from(self.obj.support(&dir_local))).coords
+    }
+}
+
+impl CCDObject for std::boxed::Box<dyn CCDObject> {
+    fn center(&self) -> Vector3f {
+        self.as_ref().center()
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        self.as_ref().support(dir)
+    }
+}
+
+impl<'a, T: CCDObject + ?Sized> CCDObject for &'a T {
+    fn center(&self) -> Vector3f {
+        (**self).center()
+    }
+
+    fn support(&self, dir: &Vector3f) -> Vector3f {
+        (**self).support(dir)
+    }
+}
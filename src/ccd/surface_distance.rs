@@ -0,0 +1,113 @@
+use crate::math::*;
+use crate::geometry::{NurbsSurface, ProjectionCriteria};
+use crate::ccd::{OBBTree, ParamDomain};
+
+/// Parameters of the surface-surface distance query.
+pub struct DistanceCriteria {
+    pub level:          usize,    // depth of the OBB trees
+    pub max_iterations: usize,    // maximum number of local minimization iterations
+    pub tolerance:      Scalar,   // convergence tolerance of the witness points
+}
+
+impl DistanceCriteria {
+
+    pub fn default() -> Self {
+        DistanceCriteria {
+            level:          6,
+            max_iterations: 50,
+            tolerance:      1e-9,
+        }
+    }
+}
+
+/// Minimum distance between two surfaces and the closest points (witness
+/// points) realizing it.
+#[derive(Debug, Clone)]
+pub struct SurfaceDistance {
+    pub distance: Scalar,               // distance between the witness points
+    pub point1:   Vector3f,             // witness point on the first surface
+    pub point2:   Vector3f,             // witness point on the second surface
+    pub params1:  (Scalar, Scalar),     // parameters of the witness point on the first surface
+    pub params2:  (Scalar, Scalar),     // parameters of the witness point on the second surface
+}
+
+impl SurfaceDistance {
+
+    pub fn new() -> Self {
+        SurfaceDistance {
+            distance: INFINITY,
+            point1:   Vector3f::zeros(),
+            point2:   Vector3f::zeros(),
+            params1:  (0., 0.),
+            params2:  (0., 0.),
+        }
+    }
+}
+
+/// Computes the minimum distance between two NURBS surfaces.
+///
+/// Builds the OBB trees of both surfaces and calls `OBBTree::distance`.
+/// Use the latter directly to reuse the trees over several queries.
+///
+/// # Arguments
+///
+/// - `surf1`: first surface
+/// - `surf2`: second surface
+/// - `criteria`: query parameters
+pub fn surface_distance(surf1: &NurbsSurface,
+                        surf2: &NurbsSurface,
+                        criteria: &DistanceCriteria) -> SurfaceDistance {
+    let tree1 = OBBTree::from_nurbs_surface(surf1, criteria.level);
+    let tree2 = OBBTree::from_nurbs_surface(surf2, criteria.level);
+    return tree1.distance(&tree2, surf1, surf2, criteria);
+}
+
+/// Locally minimizes the distance between two surfaces, starting from the
+/// centers of the given patches.
+///
+/// The closest points are found by alternating projections: the current
+/// point of each surface is projected onto the other one until the witness
+/// points stop moving. The parameters are free to leave the starting patches.
+pub(crate) fn minimize_distance(surf1: &NurbsSurface, surf2: &NurbsSurface,
+                                domain1: &ParamDomain, domain2: &ParamDomain,
+                                criteria: &DistanceCriteria) -> SurfaceDistance {
+    let proj_criteria = ProjectionCriteria {
+        max_iterations:   criteria.max_iterations,
+        samples:          0,
+        point_tolerance:  criteria.tolerance,
+        cosine_tolerance: criteria.tolerance,
+    };
+
+    let mut uv1 = (0.5 * (domain1.0 + domain1.1), 0.5 * (domain1.2 + domain1.3));
+    let mut uv2 = (0.5 * (domain2.0 + domain2.1), 0.5 * (domain2.2 + domain2.3));
+    let mut p1 = surf1.evaluate_single(uv1.0, uv1.1);
+    let mut p2 = surf2.evaluate_single(uv2.0, uv2.1);
+    let mut dist = (p1 - p2).norm();
+
+    for _ in 0..criteria.max_iterations {
+        let proj2 = surf2.project_point_newton(&p1, uv2.0, uv2.1, &proj_criteria);
+        let proj1 = surf1.project_point_newton(&proj2.point, uv1.0, uv1.1, &proj_criteria);
+        let dist_new = (proj1.point - proj2.point).norm();
+        if dist_new > dist {
+            break;
+        }
+
+        let movement = (proj1.point - p1).norm() + (proj2.point - p2).norm();
+        uv1 = (proj1.u, proj1.v);
+        uv2 = (proj2.u, proj2.v);
+        p1 = proj1.point;
+        p2 = proj2.point;
+        dist = dist_new;
+        if movement <= criteria.tolerance || dist <= criteria.tolerance {
+            break;
+        }
+    }
+
+    SurfaceDistance {
+        distance: dist,
+        point1:   p1,
+        point2:   p2,
+        params1:  uv1,
+        params2:  uv2,
+    }
+}
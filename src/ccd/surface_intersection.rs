@@ -0,0 +1,364 @@
+use crate::math::*;
+use crate::geometry::NurbsSurface;
+use crate::ccd::{OBBTree, ParamDomain};
+use na::{Matrix3, Matrix4, MatrixMN, Vector4};
+
+/// Parameters of the surface-surface intersection.
+pub struct IntersectionCriteria {
+    pub level:          usize,    // depth of the OBB trees
+    pub obb_epsilon:    Scalar,   // epsilon of the OBB overlap test
+    pub step:           Scalar,   // marching step length
+    pub tolerance:      Scalar,   // distance between the surfaces at the intersection points
+    pub max_iterations: usize,    // maximum number of Newton iterations per point
+    pub max_points:     usize,    // maximum number of points per intersection curve
+}
+
+impl IntersectionCriteria {
+
+    pub fn default() -> Self {
+        IntersectionCriteria {
+            level:          6,
+            obb_epsilon:    1e-6,
+            step:           0.05,
+            tolerance:      1e-9,
+            max_iterations: 20,
+            max_points:     10000,
+        }
+    }
+}
+
+/// Intersection curve of two surfaces, given as a polyline in 3D together
+/// with the matching polylines in the parametric domains of both surfaces.
+#[derive(Debug, Clone)]
+pub struct IntersectionCurve {
+    pub points:  Vec<Vector3f>,
+    pub params1: Vec<(Scalar, Scalar)>,
+    pub params2: Vec<(Scalar, Scalar)>,
+    pub closed:  bool,
+}
+
+/// Point on the intersection of two surfaces.
+#[derive(Debug, Clone, Copy)]
+struct IntersectionPoint {
+    point: Vector3f,
+    uv1:   (Scalar, Scalar),
+    uv2:   (Scalar, Scalar),
+}
+
+fn in_domain(uv: (Scalar, Scalar), domain: &ParamDomain, pad: Scalar) -> bool {
+    uv.0 >= domain.0 - pad && uv.0 <= domain.1 + pad &&
+        uv.1 >= domain.2 - pad && uv.1 <= domain.3 + pad
+}
+
+const UNIT_DOMAIN: ParamDomain = (0., 1., 0., 1.);
+
+/// Computes the intersection curves of two NURBS surfaces.
+///
+/// OBB trees of both surfaces are built and traversed to find the pairs of
+/// leaf patches which may intersect. A point on the intersection is sought
+/// from the center of each pair of patches by Newton iteration, and the
+/// intersection curve through it is traced by marching along the tangent
+/// $n_1 \times n_2$ in both directions. Each marching step is corrected back
+/// onto both surfaces. Seeds lying on already traced curves are skipped.
+///
+/// Tangential intersections, where the normals of both surfaces are
+/// parallel, are not traced.
+///
+/// # Arguments
+///
+/// - `surf1`: first surface
+/// - `surf2`: second surface
+/// - `criteria`: intersection parameters
+pub fn intersect_surfaces(surf1: &NurbsSurface,
+                          surf2: &NurbsSurface,
+                          criteria: &IntersectionCriteria) -> Vec<IntersectionCurve> {
+    let tree1 = OBBTree::from_nurbs_surface(surf1, criteria.level);
+    let tree2 = OBBTree::from_nurbs_surface(surf2, criteria.level);
+    let pairs = tree1.collect_leaf_pairs(&tree2, criteria.obb_epsilon);
+
+    let mut curves: Vec<IntersectionCurve> = Vec::new();
+    for (domain1, domain2) in pairs {
+        let uv1 = (0.5 * (domain1.0 + domain1.1), 0.5 * (domain1.2 + domain1.3));
+        let uv2 = (0.5 * (domain2.0 + domain2.1), 0.5 * (domain2.2 + domain2.3));
+        let seed = match refine_seed(surf1, surf2, uv1, uv2, criteria) {
+            Some(seed) => seed,
+            None => continue,
+        };
+
+        // the seed must belong to the pair of patches
+        let pad = 1e-9;
+        if !in_domain(seed.uv1, &domain1, pad) || !in_domain(seed.uv2, &domain2, pad) {
+            continue;
+        }
+
+        // skip seeds lying on curves which were already traced
+        let traced = curves.iter().any(|curve| {
+            distance_to_polyline(&seed.point, &curve.points) < 0.5 * criteria.step
+        });
+        if traced {
+            continue;
+        }
+
+        if let Some(curve) = trace_curve(surf1, surf2, &seed, criteria) {
+            curves.push(curve);
+        }
+    }
+
+    return curves;
+}
+
+/// Finds a point on both surfaces near the given parameters by minimizing
+/// $|S_1(u_1, v_1) - S_2(u_2, v_2)|^2$ with Gauss-Newton iteration.
+fn refine_seed(surf1: &NurbsSurface, surf2: &NurbsSurface,
+               uv1: (Scalar, Scalar), uv2: (Scalar, Scalar),
+               criteria: &IntersectionCriteria) -> Option<IntersectionPoint> {
+    let mut x = Vector4::new(uv1.0, uv1.1, uv2.0, uv2.1);
+    for _ in 0..criteria.max_iterations * 2 {
+        let d1 = surf1.derivatives(x[0], x[1], 1);
+        let d2 = surf2.derivatives(x[2], x[3], 1);
+        let r = d1[0][0] - d2[0][0];
+        if r.norm() <= criteria.tolerance {
+            return Some(IntersectionPoint {
+                point: 0.5 * (d1[0][0] + d2[0][0]),
+                uv1: (x[0], x[1]),
+                uv2: (x[2], x[3]),
+            });
+        }
+
+        let mut j = MatrixMN::<Scalar, U3, U4>::zeros();
+        j.set_column(0, &d1[1][0]);
+        j.set_column(1, &d1[0][1]);
+        j.set_column(2, &-d2[1][0]);
+        j.set_column(3, &-d2[0][1]);
+        let jjt: Matrix3<Scalar> = &j * j.transpose();
+        let delta = match jjt.try_inverse() {
+            Some(inv) => -j.transpose() * (inv * r),
+            None => return None,
+        };
+        x += delta;
+        for k in 0..4 {
+            x[k] = x[k].max(0.).min(1.);
+        }
+    }
+
+    return None;
+}
+
+/// Corrects the predicted point `target` onto both surfaces, within the
+/// plane through `target` perpendicular to `tangent`.
+fn correct_point(surf1: &NurbsSurface, surf2: &NurbsSurface,
+                 guess: &IntersectionPoint, target: &Vector3f, tangent: &Vector3f,
+                 criteria: &IntersectionCriteria) -> Option<IntersectionPoint> {
+    let mut x = Vector4::new(guess.uv1.0, guess.uv1.1, guess.uv2.0, guess.uv2.1);
+    for _ in 0..criteria.max_iterations {
+        let d1 = surf1.derivatives(x[0], x[1], 1);
+        let d2 = surf2.derivatives(x[2], x[3], 1);
+        let r = d1[0][0] - d2[0][0];
+        let f = Vector4::new(r[0], r[1], r[2], (d1[0][0] - target).dot(tangent));
+
+        let mut j = Matrix4::<Scalar>::zeros();
+        for k in 0..3 {
+            j[(k, 0)] = d1[1][0][k];
+            j[(k, 1)] = d1[0][1][k];
+            j[(k, 2)] = -d2[1][0][k];
+            j[(k, 3)] = -d2[0][1][k];
+        }
+        j[(3, 0)] = d1[1][0].dot(tangent);
+        j[(3, 1)] = d1[0][1].dot(tangent);
+
+        let delta = match j.lu().solve(&-f) {
+            Some(delta) => delta,
+            None => return None,
+        };
+        x += delta;
+
+        if delta.norm() < 1e-14 || r.norm() <= criteria.tolerance && f[3].abs() <= criteria.tolerance {
+            let p1 = surf1.evaluate_single(x[0], x[1]);
+            let p2 = surf2.evaluate_single(x[2], x[3]);
+            if (p1 - p2).norm() > criteria.tolerance * 10. {
+                return None;
+            }
+            return Some(IntersectionPoint {
+                point: 0.5 * (p1 + p2),
+                uv1: (x[0], x[1]),
+                uv2: (x[2], x[3]),
+            });
+        }
+    }
+
+    return None;
+}
+
+/// Finds the point where the intersection curve crosses the boundary of the
+/// parametric domains, between `inside` and `outside`.
+///
+/// The parameter which left the unit domain is fixed on the boundary and the
+/// remaining three are solved from $S_1(u_1, v_1) = S_2(u_2, v_2)$.
+fn land_on_boundary(surf1: &NurbsSurface, surf2: &NurbsSurface,
+                    inside: &IntersectionPoint, outside: &IntersectionPoint,
+                    criteria: &IntersectionCriteria) -> Option<IntersectionPoint> {
+    let x0 = [inside.uv1.0, inside.uv1.1, inside.uv2.0, inside.uv2.1];
+    let x1 = [outside.uv1.0, outside.uv1.1, outside.uv2.0, outside.uv2.1];
+
+    // parameter crossing its bound first along the step
+    let mut fixed = None;
+    let mut t_min = INFINITY;
+    for k in 0..4 {
+        let bound = if x1[k] > 1. { 1. } else if x1[k] < 0. { 0. } else { continue };
+        let t = (bound - x0[k]) / (x1[k] - x0[k]);
+        if t < t_min {
+            t_min = t;
+            fixed = Some((k, bound));
+        }
+    }
+    let (fixed, bound) = fixed?;
+
+    let mut x = Vector4::new(x0[0], x0[1], x0[2], x0[3]);
+    x[fixed] = bound;
+    let free: Vec<usize> = (0..4).filter(|k| *k != fixed).collect();
+    for _ in 0..criteria.max_iterations {
+        let d1 = surf1.derivatives(x[0], x[1], 1);
+        let d2 = surf2.derivatives(x[2], x[3], 1);
+        let r = d1[0][0] - d2[0][0];
+        if r.norm() <= criteria.tolerance {
+            if !in_domain((x[0], x[1]), &UNIT_DOMAIN, 1e-12) ||
+                !in_domain((x[2], x[3]), &UNIT_DOMAIN, 1e-12) {
+                return None;
+            }
+            return Some(IntersectionPoint {
+                point: 0.5 * (d1[0][0] + d2[0][0]),
+                uv1: (x[0], x[1]),
+                uv2: (x[2], x[3]),
+            });
+        }
+
+        let columns = [d1[1][0], d1[0][1], -d2[1][0], -d2[0][1]];
+        let j = Matrix3::from_columns(&[columns[free[0]], columns[free[1]], columns[free[2]]]);
+        let delta = j.lu().solve(&-r)?;
+        for k in 0..3 {
+            x[free[k]] += delta[k];
+        }
+    }
+
+    return None;
+}
+
+/// Unit tangent of the intersection curve, or `None` where the surfaces
+/// touch tangentially.
+fn curve_tangent(surf1: &NurbsSurface, surf2: &NurbsSurface,
+                 p: &IntersectionPoint) -> Option<Vector3f> {
+    let n1 = surf1.normal(p.uv1.0, p.uv1.1);
+    let n2 = surf2.normal(p.uv2.0, p.uv2.1);
+    let t = n1.cross(&n2);
+    if t.norm() < 1e-8 {
+        return None;
+    }
+    return Some(t.normalize());
+}
+
+/// Marches from `seed` along the intersection in the direction of `sign`
+/// times the tangent. Returns the traced points (without the seed) and
+/// whether the curve closed onto the seed.
+fn march(surf1: &NurbsSurface, surf2: &NurbsSurface,
+         seed: &IntersectionPoint, sign: Scalar,
+         criteria: &IntersectionCriteria) -> (Vec<IntersectionPoint>, bool) {
+    let mut points = Vec::new();
+    let mut current = *seed;
+    let mut tangent = match curve_tangent(surf1, surf2, seed) {
+        Some(t) => sign * t,
+        None => return (points, false),
+    };
+
+    let min_step = criteria.step * 1e-3;
+    while points.len() < criteria.max_points {
+        let mut step = criteria.step;
+        let mut next = None;
+        let mut outside = None;
+        while step >= min_step {
+            let target = current.point + step * tangent;
+            if let Some(p) = correct_point(surf1, surf2, &current, &target, &tangent, criteria) {
+                if in_domain(p.uv1, &UNIT_DOMAIN, 0.) && in_domain(p.uv2, &UNIT_DOMAIN, 0.) {
+                    next = Some(p);
+                    break;
+                }
+                outside = Some(p);
+            }
+            step *= 0.5;
+        }
+
+        let next = match (next, outside) {
+            (Some(next), _) => next,
+            // left the domain of either surface: end the curve on the boundary
+            (None, Some(outside)) => {
+                if let Some(p) = land_on_boundary(surf1, surf2, &current, &outside, criteria) {
+                    if (p.point - current.point).norm() > criteria.tolerance {
+                        points.push(p);
+                    }
+                }
+                return (points, false);
+            }
+            // failed to converge
+            (None, None) => return (points, false),
+        };
+
+        // closed onto the seed
+        if points.len() > 2 && (next.point - seed.point).norm() < criteria.step {
+            return (points, true);
+        }
+
+        tangent = match curve_tangent(surf1, surf2, &next) {
+            Some(t) => if t.dot(&tangent) < 0. { -t } else { t },
+            None => {
+                points.push(next);
+                return (points, false);
+            }
+        };
+        points.push(next);
+        current = next;
+    }
+
+    return (points, false);
+}
+
+fn trace_curve(surf1: &NurbsSurface, surf2: &NurbsSurface,
+               seed: &IntersectionPoint,
+               criteria: &IntersectionCriteria) -> Option<IntersectionCurve> {
+    let (forward, closed) = march(surf1, surf2, seed, 1., criteria);
+    let mut points: Vec<IntersectionPoint> = Vec::new();
+    if !closed {
+        let (backward, _) = march(surf1, surf2, seed, -1., criteria);
+        points.extend(backward.into_iter().rev());
+    }
+    points.push(*seed);
+    points.extend(forward);
+    if closed {
+        points.push(*seed);
+    }
+
+    // isolated point, e.g. surfaces touching at a corner
+    if points.len() < 2 {
+        return None;
+    }
+
+    Some(IntersectionCurve {
+        points:  points.iter().map(|p| p.point).collect(),
+        params1: points.iter().map(|p| p.uv1).collect(),
+        params2: points.iter().map(|p| p.uv2).collect(),
+        closed,
+    })
+}
+
+fn distance_to_polyline(point: &Vector3f, polyline: &Vec<Vector3f>) -> Scalar {
+    let mut dist = INFINITY;
+    for k in 1..polyline.len() {
+        let (a, b) = (&polyline[k-1], &polyline[k]);
+        let d = b - a;
+        let t = if d.norm_squared() > 0. {
+            ((point - a).dot(&d) / d.norm_squared()).max(0.).min(1.)
+        } else {
+            0.
+        };
+        dist = dist.min((a + t * d - point).norm());
+    }
+    return dist;
+}
@@ -0,0 +1,158 @@
+use crate::math::{Vector3f, Scalar, Isometry3f};
+use crate::ccd::{CCDObject, CCDCriteria, CCDDistance, CCDResult, Transformed};
+use crate::ccd::{gjk_distance, gjk_penetration};
+use na::geometry::{Translation3, UnitQuaternion};
+
+/// Rigid motion with constant linear and angular velocities: the origin of
+/// the moving frame translates along a line while the frame rotates about
+/// its origin.
+#[derive(Debug, Clone, Copy)]
+pub struct CCDMotion {
+    pub start:  Isometry3f,     // pose at time 0
+    pub linvel: Vector3f,       // velocity of the frame origin
+    pub angvel: Vector3f,       // angular velocity, in the world frame
+}
+
+/// First contact between two moving objects.
+#[derive(Debug, Clone)]
+pub struct CCDImpact {
+    pub time:   Scalar,
+    pub point:  Vector3f,       // contact point, in the world frame
+    pub normal: Vector3f,       // contact normal, from obj1 to obj2
+}
+
+impl CCDMotion {
+
+    pub fn new(start: Isometry3f, linvel: Vector3f, angvel: Vector3f) -> Self {
+        CCDMotion { start, linvel, angvel }
+    }
+
+    /// Object at rest at the pose.
+    pub fn fixed(pose: Isometry3f) -> Self {
+        CCDMotion { start: pose, linvel: Vector3f::zeros(), angvel: Vector3f::zeros() }
+    }
+
+    /// Motion from a pose to another in the given duration, e.g. during a
+    /// simulation step. Returns `None` if the duration is not positive.
+    pub fn from_poses(start: &Isometry3f, end: &Isometry3f, duration: Scalar) -> Option<Self> {
+        if !(duration > 0.) {
+            return None;
+        }
+        let linvel = (end.translation.vector - start.translation.vector) / duration;
+        let angvel = (end.rotation * start.rotation.inverse()).scaled_axis() / duration;
+        return Some(CCDMotion { start: *start, linvel, angvel });
+    }
+
+    /// Returns the pose at the time.
+    pub fn pose(&self, time: Scalar) -> Isometry3f {
+        Isometry3f::from_parts(
+            Translation3::from(self.start.translation.vector + self.linvel * time),
+            UnitQuaternion::new(self.angvel * time) * self.start.rotation)
+    }
+}
+
+impl CCDImpact {
+    pub fn new() -> Self {
+        CCDImpact {
+            time: 0.0,
+            point: Vector3f::zeros(),
+            normal: Vector3f::zeros(),
+        }
+    }
+}
+
+/// Upper bound of the distance from the frame origin to the points of the
+/// object: corner of its bounding box.
+fn radius_bound(obj: &dyn CCDObject) -> Scalar {
+    let mut corner = Vector3f::zeros();
+    for k in 0..3 {
+        let mut dir = Vector3f::zeros();
+        dir[k] = 1.;
+        corner[k] = obj.support(&dir)[k].abs().max(obj.support(&-dir)[k].abs());
+    }
+    return corner.norm();
+}
+
+/// Unit direction from the center of obj1 to the center of obj2, or zero if
+/// the centers coincide.
+fn center_direction(obj1: &dyn CCDObject, obj2: &dyn CCDObject) -> Vector3f {
+    (obj2.center() - obj1.center()).try_normalize(0.).unwrap_or(Vector3f::zeros())
+}
+
+/// Computes the first time of contact of two objects moving over
+/// `[0, duration]`, with the contact point and normal, by conservative
+/// advancement: the objects are moved forward by the time they need to
+/// close their current distance at the highest possible approach speed,
+/// until they are closer than `ccd.toi_tolerance`.
+///
+/// The objects are given in their moving frames. If they intersect at time
+/// 0, the impact is at time 0 with the penetration direction as normal, or
+/// the direction between the centers if the penetration has none. If they
+/// are still apart after `ccd.max_iterations` advancements, e.g. when
+/// grazing, the impact is reported at the time reached, before the actual
+/// contact, with the direction between the closest points as normal.
+/// Returns false if they do not touch during the motion.
+///
+/// # Arguments
+///
+/// - `obj1`: first object, in its moving frame
+/// - `motion1`: motion of the frame of the first object
+/// - `obj2`: second object, in its moving frame
+/// - `motion2`: motion of the frame of the second object
+/// - `duration`: end of the time interval
+/// - `ccd`: criteria
+/// - `impact`: first contact, if any
+pub fn time_of_impact(obj1: &dyn CCDObject, motion1: &CCDMotion,
+                      obj2: &dyn CCDObject, motion2: &CCDMotion,
+                      duration: Scalar,
+                      ccd: &CCDCriteria,
+                      impact: &mut CCDImpact) -> bool {
+    // largest speed of the object points due to the rotations
+    let spin = motion1.angvel.norm() * radius_bound(obj1) + motion2.angvel.norm() * radius_bound(obj2);
+
+    let mut time = 0.;
+    for iter in 0..=ccd.max_iterations {
+        let moved1 = Transformed { tform: motion1.pose(time), obj: obj1 };
+        let moved2 = Transformed { tform: motion2.pose(time), obj: obj2 };
+
+        let mut dist = CCDDistance::new();
+        if !gjk_distance(&moved1, &moved2, ccd, &mut dist) {
+            // only at time 0, the advancement stops before the contact
+            let mut pen = CCDResult::new();
+            impact.time = time;
+            if gjk_penetration(&moved1, &moved2, ccd, &mut pen) {
+                impact.point = pen.pos;
+                impact.normal = pen.dir.try_normalize(0.)
+                    .unwrap_or_else(|| center_direction(&moved1, &moved2));
+            } else {
+                impact.point = dist.point1;
+                impact.normal = center_direction(&moved1, &moved2);
+            }
+            return true;
+        }
+
+        let normal = match (dist.point2 - dist.point1).try_normalize(0.) {
+            Some(normal) if dist.distance > ccd.toi_tolerance && iter < ccd.max_iterations => normal,
+            // contact, or last iteration with the objects still apart
+            normal => {
+                impact.time = time;
+                impact.point = 0.5 * (dist.point1 + dist.point2);
+                impact.normal = normal.unwrap_or_else(|| center_direction(&moved1, &moved2));
+                return true;
+            },
+        };
+
+        // the gap along the normal closes at most at this speed; stop half
+        // the tolerance short of the contact so that the objects stay apart
+        let speed = normal.dot(&(motion1.linvel - motion2.linvel)) + spin;
+        if speed <= 0. {
+            return false;
+        }
+        time += (dist.distance - 0.5 * ccd.toi_tolerance) / speed;
+        if time > duration {
+            return false;
+        }
+    }
+
+    return false;
+}
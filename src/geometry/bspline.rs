@@ -56,85 +56,7 @@ impl <D: Dim + DimName> BSplineCurve<D>
     pub fn basis_function_derivatives(&self,
                                       i: usize, p: usize, u: Scalar,
                                       n: usize, knotvec: Vec<Scalar>) -> Vec<Vec<Scalar>> {
-        let mut left  = vec![1.; p+1];
-        let mut right = vec![1.; p+1];
-        // `ndu` stores the basis functions and knot differences
-        // ndu[0][0] = 1.0 by definition
-        let mut ndu   = vec![vec![1.; p+1]; p+1];
-        // `a` store the two most recently computed rows a_{k, j} and a_{k-1, j} in
-        // an alternating fashion
-        let mut a     = vec![vec![0.; 2]; p+1];
-        // derivatives, ders[k][j] is the k-th derivative of the function N_{i-p+j, p}
-        // where 0 <= k <= n, 0 <= j <= p.
-        let mut ders  = vec![vec![0.; p+1]; min(p, n)+1];
-
-        for j in 1..=p {
-            left[j]  = u - knotvec[i+1-j];
-            right[j] = knotvec[i+1] - u;
-            let mut saved = 0.;
-            for r in 0..j {
-                // lower triangle
-                ndu[j][r] = right[r+1] + left[j-r];
-                let temp = ndu[r][j-1] / ndu[j][r];
-
-                // upper triangle
-                ndu[r][j] = saved + right[r+1] * temp;
-                saved = left[j-r] * temp;
-            }
-            ndu[j][j] = saved;
-        }
-
-        // load the basis functions
-        for j in 0..=p {
-            ders[0][j] = ndu[j][p];
-        }
-
-        // This section computes the derivatives (Eq. 2.9)
-        // loop over function index
-        for r in 0..=p {
-            let mut s1 = 0usize;
-            let mut s2 = 0usize;
-
-            a[0][0] = 1.;
-            // loop to compute the k-th derivative
-            for k in 1..=n {
-                let mut d = 0.;
-                let r_k = r - k;
-                let p_k = p - k;
-
-                if r >= k {
-                    a[s2][0] = a[s1][0] / ndu[p_k + 1][r_k];
-                    d = a[s2][0] * ndu[r_k][p_k];
-                }
-
-                let j1 = if r   >= k-1 {   1 } else { k-r };
-                let j2 = if r-1 <= p_k { k-1 } else { p-r };
-                for j in j1..=j2 {
-                    a[s2][j] = (a[s1][j] - a[s1][j-1]) / ndu[p_k+1][r_k+j];
-                    d += a[s2][j] * ndu[r_k+j][p_k];
-                }
-
-                if r <= p_k {
-                    a[s2][k] = -a[s1][k-1] / ndu[p_k+1][r];
-                    d += a[s2][k] * ndu[r][p_k];
-                }
-                ders[k][r] = d;
-
-                // switch rows
-                let j = s1; s1 = s2; s2 = j;
-            }
-        }
-
-        // Multiply through by the correct factors (Eq. 2.9)
-        let mut r = p;
-        for k in 1..=n {
-            for j in 0..=p {
-                ders[k][j] *= r as Scalar;
-            }
-            r *= p - k;
-        }
-
-        return ders;
+        basis_function_ders(i, u, p, n, &knotvec)
     }
 
 
@@ -170,6 +92,110 @@ impl <D: Dim + DimName> BSplineCurve<D>
         // set new control points and knot vector
         self.ctrlpts = ctrlpts_new;
         self.knotvec = knotvec_new;
+        self.size = self.size + r;
+    }
+
+    /// Computes the curve derivatives up to order `d` at parameter `u`.
+    /// Algorithm A3.2: CurveDerivsAlg1
+    ///
+    /// Returns a vector `ders` where `ders[k]` is the k-th derivative,
+    /// `ders[0]` being the curve point itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - parameter
+    /// * `d` - order of the derivatives
+    pub fn derivatives(&self, u: Scalar, d: usize) -> Vec<VectorN<Scalar, D>> {
+        let p = self.degree;
+        let span = find_span(self.size, p, u, &self.knotvec);
+        let nders = basis_function_ders(span, u, p, d, &self.knotvec);
+
+        let mut ders = vec![VectorN::<Scalar, D>::zeros(); d+1];
+        for k in 0..=min(d, p) {
+            for j in 0..=p {
+                ders[k] = &ders[k] + nders[k][j] * &self.ctrlpts[span-p+j];
+            }
+        }
+        return ders;
+    }
+
+    /// Inserts all the given knots at once.
+    ///
+    /// # Arguments
+    ///
+    /// - `knots`: knots to be inserted, in non-decreasing order
+    pub fn refine_knotvec(&mut self, knots: &Vec<Scalar>) {
+        let (knotvec_new, ctrlpts_new) = refine_knotvec(
+            &self.knotvec, &self.ctrlpts, self.degree, knots);
+        self.size = ctrlpts_new.len();
+        self.ctrlpts = ctrlpts_new;
+        self.knotvec = knotvec_new;
+    }
+
+    /// Removes the knot `u` up to `num` times, as long as the control points
+    /// move by less than `tol`. Returns the number of actual removals.
+    ///
+    /// # Arguments
+    ///
+    /// - `u`: knot
+    /// - `num`: maximum number of knot removals
+    /// - `tol`: tolerance
+    pub fn remove_knot(&mut self, u: Scalar, num: usize, tol: Scalar) -> usize {
+        let (knotvec_new, ctrlpts_new, t) = remove_knot(
+            &self.knotvec, &self.ctrlpts, self.degree, u, num, tol);
+        self.size = ctrlpts_new.len();
+        self.ctrlpts = ctrlpts_new;
+        self.knotvec = knotvec_new;
+        return t;
+    }
+
+    /// Removes as many interior knots as possible, as long as the curve
+    /// moves by less than `tol` in total. Returns the number of removals.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn remove_knots(&mut self, tol: Scalar) -> usize {
+        let (knotvec_new, mut ctrlpts_new, removed, _) = remove_knots(
+            &self.knotvec, &vec![self.ctrlpts.clone()], self.degree, tol);
+        self.ctrlpts = ctrlpts_new.remove(0);
+        self.size = self.ctrlpts.len();
+        self.knotvec = knotvec_new;
+        return removed;
+    }
+
+    /// Elevates the degree of the curve `t` times.
+    ///
+    /// # Arguments
+    ///
+    /// - `t`: number of degree elevations
+    pub fn elevate_degree(&mut self, t: usize) {
+        let (knotvec_new, ctrlpts_new) = elevate_degree(
+            &self.knotvec, &self.ctrlpts, self.degree, t);
+        self.size = ctrlpts_new.len();
+        self.ctrlpts = ctrlpts_new;
+        self.knotvec = knotvec_new;
+        self.degree = self.degree + t;
+    }
+
+    /// Reduces the degree of the curve by one, as long as it moves by less
+    /// than `tol`. Returns whether the degree was reduced; the curve is left
+    /// untouched otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn reduce_degree(&mut self, tol: Scalar) -> bool {
+        match reduce_degree(&self.knotvec, &vec![self.ctrlpts.clone()], self.degree, tol) {
+            Some((knotvec_new, mut ctrlpts_new, _)) => {
+                self.ctrlpts = ctrlpts_new.remove(0);
+                self.size = self.ctrlpts.len();
+                self.knotvec = knotvec_new;
+                self.degree = self.degree - 1;
+                true
+            },
+            None => false,
+        }
     }
 }
 
@@ -249,6 +275,51 @@ impl <D: Dim + DimName> BSplineSurface<D>
         return point;
     }
 
+    /// Computes the surface partial derivatives up to order `d` at the
+    /// input (u, v) parameter pair.
+    /// Algorithm A3.6: SurfaceDerivsAlg1
+    ///
+    /// Returns a 2D array `skl` where `skl[k][l]` is the derivative of the
+    /// surface `k` times with respect to u and `l` times with respect to v,
+    /// for $0 \leq k + l \leq d$. The remaining entries are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - the first parameter
+    /// * `v` - the second parameter
+    /// * `d` - order of the derivatives
+    pub fn derivatives(&self, u: Scalar, v: Scalar, d: usize) -> Vec<Vec<VectorN<Scalar, D>>> {
+        let p = self.degree_u;
+        let q = self.degree_v;
+        let du = min(d, p);
+        let dv = min(d, q);
+
+        let span_u = find_span(self.size_u, p, u, &self.knotvec_u);
+        let span_v = find_span(self.size_v, q, v, &self.knotvec_v);
+        let nders_u = basis_function_ders(span_u, u, p, du, &self.knotvec_u);
+        let nders_v = basis_function_ders(span_v, v, q, dv, &self.knotvec_v);
+
+        let mut skl = vec![vec![VectorN::<Scalar, D>::zeros(); d+1]; d+1];
+        let mut temp = vec![VectorN::<Scalar, D>::zeros(); q+1];
+        for k in 0..=du {
+            for s in 0..=q {
+                temp[s] = VectorN::<Scalar, D>::zeros();
+                for r in 0..=p {
+                    let index = (span_u - p + r) * self.size_v + span_v - q + s;
+                    temp[s] = &temp[s] + nders_u[k][r] * &self.ctrlpts[index];
+                }
+            }
+            let dd = min(d - k, dv);
+            for l in 0..=dd {
+                for s in 0..=q {
+                    skl[k][l] = &skl[k][l] + nders_v[l][s] * &temp[s];
+                }
+            }
+        }
+
+        return skl;
+    }
+
     pub fn insert_knot(&mut self, uv: (Option<Scalar>, Option<Scalar>), num: (usize, usize)) {
         if let Some(u) = uv.0 {
             let r = num.0;
@@ -305,6 +376,167 @@ impl <D: Dim + DimName> BSplineSurface<D>
         }
     }
 
+    /// Removes the knot `uv.0` up to `num.0` times in the u-direction and
+    /// the knot `uv.1` up to `num.1` times in the v-direction, as long as the
+    /// surface moves by less than `tol` in each direction. Returns the number
+    /// of actual removals in each direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `uv`: knots to be removed
+    /// - `num`: maximum number of knot removals
+    /// - `tol`: tolerance
+    pub fn remove_knot(&mut self, uv: (Option<Scalar>, Option<Scalar>),
+                       num: (usize, usize), tol: Scalar) -> (usize, usize) {
+        let mut removed = (0, 0);
+
+        if let Some(u) = uv.0 {
+            let (knotvec_u_new, curves, t) = remove_knot_common(
+                &self.knotvec_u, &self.curves_u(), self.degree_u, u, num.0, tol);
+            if t > 0 {
+                self.knotvec_u = knotvec_u_new;
+                self.set_curves_u(curves);
+            }
+            removed.0 = t;
+        }
+
+        if let Some(v) = uv.1 {
+            let (knotvec_v_new, curves, t) = remove_knot_common(
+                &self.knotvec_v, &self.curves_v(), self.degree_v, v, num.1, tol);
+            if t > 0 {
+                self.knotvec_v = knotvec_v_new;
+                self.set_curves_v(curves);
+            }
+            removed.1 = t;
+        }
+
+        return removed;
+    }
+
+    /// Removes as many interior knots as possible in both directions, as
+    /// long as the surface moves by less than `tol` in total. Returns the
+    /// number of removals in each direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn remove_knots(&mut self, tol: Scalar) -> (usize, usize) {
+        let (knotvec_u_new, curves, removed_u, err) = remove_knots(
+            &self.knotvec_u, &self.curves_u(), self.degree_u, tol);
+        self.knotvec_u = knotvec_u_new;
+        self.set_curves_u(curves);
+
+        let (knotvec_v_new, curves, removed_v, _) = remove_knots(
+            &self.knotvec_v, &self.curves_v(), self.degree_v, tol - err);
+        self.knotvec_v = knotvec_v_new;
+        self.set_curves_v(curves);
+
+        return (removed_u, removed_v);
+    }
+
+    /// Elevates the degree of the surface `t.0` times in the u-direction
+    /// and `t.1` times in the v-direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `t`: number of degree elevations in each direction
+    pub fn elevate_degree(&mut self, t: (usize, usize)) {
+        if t.0 > 0 {
+            let mut curves = Vec::new();
+            let mut knotvec_u_new = Vec::new();
+            for curve in self.curves_u() {
+                let (kv, pts) = elevate_degree(&self.knotvec_u, &curve, self.degree_u, t.0);
+                curves.push(pts);
+                knotvec_u_new = kv;
+            }
+            self.knotvec_u = knotvec_u_new;
+            self.degree_u = self.degree_u + t.0;
+            self.set_curves_u(curves);
+        }
+
+        if t.1 > 0 {
+            let mut curves = Vec::new();
+            let mut knotvec_v_new = Vec::new();
+            for curve in self.curves_v() {
+                let (kv, pts) = elevate_degree(&self.knotvec_v, &curve, self.degree_v, t.1);
+                curves.push(pts);
+                knotvec_v_new = kv;
+            }
+            self.knotvec_v = knotvec_v_new;
+            self.degree_v = self.degree_v + t.1;
+            self.set_curves_v(curves);
+        }
+    }
+
+    /// Reduces the degree of the surface by one in the u-direction (if
+    /// `uv.0`) and/or the v-direction (if `uv.1`), as long as it moves by
+    /// less than `tol`. Returns whether the degree was reduced; the surface
+    /// is left untouched otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// - `uv`: directions to be reduced
+    /// - `tol`: tolerance
+    pub fn reduce_degree(&mut self, uv: (bool, bool), tol: Scalar) -> bool {
+        let mut surf = self.clone();
+        let mut tol = tol;
+
+        if uv.0 {
+            match reduce_degree(&surf.knotvec_u, &surf.curves_u(), surf.degree_u, tol) {
+                Some((knotvec_u_new, curves, err)) => {
+                    surf.knotvec_u = knotvec_u_new;
+                    surf.degree_u = surf.degree_u - 1;
+                    surf.set_curves_u(curves);
+                    tol = tol - err;
+                },
+                None => return false,
+            }
+        }
+
+        if uv.1 {
+            match reduce_degree(&surf.knotvec_v, &surf.curves_v(), surf.degree_v, tol) {
+                Some((knotvec_v_new, curves, _)) => {
+                    surf.knotvec_v = knotvec_v_new;
+                    surf.degree_v = surf.degree_v - 1;
+                    surf.set_curves_v(curves);
+                },
+                None => return false,
+            }
+        }
+
+        *self = surf;
+        return true;
+    }
+
+    /// Returns the control points of the curves along the u-direction, one
+    /// for each column of the control net.
+    fn curves_u(&self) -> Vec<Vec<VectorN<Scalar, D>>> {
+        (0..self.size_v)
+            .map(|j| (0..self.size_u)
+                .map(|i| self.ctrlpts[i * self.size_v + j].clone_owned())
+                .collect())
+            .collect()
+    }
+
+    fn set_curves_u(&mut self, curves: Vec<Vec<VectorN<Scalar, D>>>) {
+        self.size_v = curves.len();
+        self.size_u = curves[0].len();
+        let ctrlpts = curves.into_iter().flatten().collect();
+        self.ctrlpts = Self::flip_ctrlpts_u(ctrlpts, self.size_u, self.size_v);
+    }
+
+    /// Returns the control points of the curves along the v-direction, one
+    /// for each row of the control net.
+    fn curves_v(&self) -> Vec<Vec<VectorN<Scalar, D>>> {
+        self.ctrlpts.chunks(self.size_v).map(|row| row.to_vec()).collect()
+    }
+
+    fn set_curves_v(&mut self, curves: Vec<Vec<VectorN<Scalar, D>>>) {
+        self.size_u = curves.len();
+        self.size_v = curves[0].len();
+        self.ctrlpts = curves.into_iter().flatten().collect();
+    }
+
     fn flip_ctrlpts_u(ctrlpts: Vec<VectorN<Scalar, D>>,
                       size_u: usize, size_v: usize) -> Vec<VectorN<Scalar, D>> {
         let mut ctrlpts_new = Vec::new();
@@ -0,0 +1,372 @@
+use crate::math::*;
+use crate::geometry::{BSplineCurve, NurbsCurve, NurbsSurface, Parameterization};
+use crate::geometry::fitting::{compute_params, knotvec_averaging, basis_matrix, solve_points};
+use std::f64::consts::PI;
+
+/// Makes the curves compatible, i.e. of the same degree and defined on the
+/// same knot vector, by degree elevation and knot refinement. Returns the
+/// weighted b-splines of the curves.
+pub(crate) fn make_compatible(curves: &Vec<NurbsCurve>) -> Vec<BSplineCurve<U4>> {
+    let degree = curves.iter().map(|c| c.degree).max().unwrap();
+    let mut splines: Vec<BSplineCurve<U4>> = curves.iter()
+        .map(|c| {
+            let mut spline = c.bspline.clone();
+            spline.elevate_degree(degree - c.degree);
+            spline
+        })
+        .collect();
+
+    // union of the knot vectors, with the largest multiplicities
+    let mut knots: Vec<Scalar> = Vec::new();
+    for spline in &splines {
+        let mut i = 0;
+        while i < spline.knotvec.len() {
+            let u = spline.knotvec[i];
+            let s = spline.knotvec[i..].iter().take_while(|k| (*k - u).abs() < 1e-10).count();
+            let have = knots.iter().filter(|k| (*k - u).abs() < 1e-10).count();
+            for _ in have..s {
+                knots.push(u);
+            }
+            i += s;
+        }
+    }
+    knots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    for spline in splines.iter_mut() {
+        let mut missing = Vec::new();
+        let mut i = 0;
+        while i < knots.len() {
+            let u = knots[i];
+            let s = knots[i..].iter().take_while(|k| (*k - u).abs() < 1e-10).count();
+            let have = spline.knotvec.iter().filter(|k| (*k - u).abs() < 1e-10).count();
+            for _ in have..s {
+                missing.push(u);
+            }
+            i += s;
+        }
+        spline.refine_knotvec(&missing);
+    }
+
+    return splines;
+}
+
+/// Builds the surface whose rows of control points along v are made of the
+/// control points of the compatible sections, i.e. the section `k` gives
+/// the control points `(i, k)`.
+fn from_sections(sections: &Vec<BSplineCurve<U4>>,
+                 knot_vec_v: Vec<Scalar>, degree_v: usize) -> NurbsSurface {
+    let size_u = sections[0].size;
+    let size_v = sections.len();
+    let mut ctrlpts = Vec::with_capacity(size_u * size_v);
+    let mut weights = Vec::with_capacity(size_u * size_v);
+    for i in 0..size_u {
+        for section in sections {
+            let point = &section.ctrlpts[i];
+            ctrlpts.push(Vector3f::new(point[0], point[1], point[2]) / point[3]);
+            weights.push(point[3]);
+        }
+    }
+
+    NurbsSurface::new(
+        ctrlpts,
+        sections[0].knotvec.clone(),
+        knot_vec_v,
+        sections[0].degree,
+        degree_v,
+        size_u,
+        size_v,
+        weights,
+    )
+}
+
+/// Interpolates compatible sections at the parameters `params` along v
+/// (skinning, The NURBS Book Section 10.3). Returns `None` if the
+/// interpolation matrix is singular, e.g. for coincident parameters.
+fn skin(sections: &Vec<BSplineCurve<U4>>, params: &Vec<Scalar>, degree_v: usize) -> Option<NurbsSurface> {
+    let size_u = sections[0].size;
+    let size_v = sections.len();
+    let knot_vec_v = knotvec_averaging(params, degree_v, false);
+    let a = basis_matrix(params, degree_v, size_v, &knot_vec_v);
+
+    // interpolate the weighted control points of the sections
+    let mut interpolated = sections.clone();
+    for i in 0..size_u {
+        let column: Vec<Vector4f> = sections.iter().map(|s| s.ctrlpts[i]).collect();
+        let ctrlpts = solve_points(a.clone(), &column)?;
+        for (j, point) in ctrlpts.into_iter().enumerate() {
+            interpolated[j].ctrlpts[i] = point;
+        }
+    }
+
+    return Some(from_sections(&interpolated, knot_vec_v, degree_v));
+}
+
+/// Moving frame of a curve at the given parameters, computed by the double
+/// reflection method (Wang et al., Computation of rotation minimizing
+/// frames, 2008). Returns the points of the curve and the frames, whose
+/// third column is the unit tangent, or `None` if the tangent vanishes at
+/// one of the parameters.
+fn rotation_minimizing_frames(curve: &NurbsCurve, params: &Vec<Scalar>) -> Option<Vec<(Vector3f, Matrix3f)>> {
+    let eps = 1e-12;
+    let mut frames: Vec<(Vector3f, Matrix3f)> = Vec::with_capacity(params.len());
+    for (k, u) in params.iter().enumerate() {
+        let ders = curve.derivatives(*u, 1);
+        let t = match ders[1].try_normalize(eps) {
+            Some(t) => t,
+            None => return None,
+        };
+
+        let r = if k == 0 {
+            // any direction perpendicular to the tangent
+            let mut axis = Vector3f::zeros();
+            axis[t.iamin()] = 1.;
+            t.cross(&axis).normalize()
+        } else {
+            let (x0, rotm0) = &frames[k-1];
+            let r0: Vector3f = rotm0.column(0).into_owned();
+            let t0: Vector3f = rotm0.column(2).into_owned();
+            let v1 = ders[0] - x0;
+            let c1 = v1.dot(&v1);
+            if c1 < 1e-24 {
+                r0
+            } else {
+                let rl = r0 - (2. / c1) * v1.dot(&r0) * v1;
+                let tl = t0 - (2. / c1) * v1.dot(&t0) * v1;
+                let v2 = t - tl;
+                let c2 = v2.dot(&v2);
+                if c2 < 1e-24 { rl } else { rl - (2. / c2) * v2.dot(&rl) * v2 }
+            }
+        };
+        let r = match (r - r.dot(&t) * t).try_normalize(eps) {
+            Some(r) => r,
+            None => return None,
+        };
+        let s = t.cross(&r);
+        frames.push((ders[0], Matrix3f::from_columns(&[r, s, t])));
+    }
+    return Some(frames);
+}
+
+impl NurbsSurface {
+
+    /// Creates the surface swept by moving `curve` along `direction`.
+    ///
+    /// The u-direction follows the curve and the v-direction the
+    /// extrusion, i.e. $S(u, v) = C(u) + v d$.
+    ///
+    /// # Arguments
+    ///
+    /// - `curve`: profile curve
+    /// - `direction`: extrusion vector
+    pub fn extrude(curve: &NurbsCurve, direction: &Vector3f) -> Self {
+        let mut ctrlpts = Vec::new();
+        let mut weights = Vec::new();
+        for (point, w) in curve.ctrlpts.iter().zip(curve.weights.iter()) {
+            ctrlpts.push(point.clone());
+            ctrlpts.push(point + direction);
+            weights.push(*w);
+            weights.push(*w);
+        }
+
+        NurbsSurface::new(
+            ctrlpts,
+            curve.knotvec.clone(),
+            vec![0., 0., 1., 1.],
+            curve.degree,
+            1,
+            curve.size(),
+            2,
+            weights,
+        )
+    }
+
+    /// Creates the surface of revolution of `curve` about an axis.
+    /// Algorithm A8.1: MakeRevolvedSurf
+    ///
+    /// The u-direction runs along the circular arcs, which are represented
+    /// exactly by rational quadratic segments of at most 90 degrees, and the
+    /// v-direction along the generatrix.
+    ///
+    /// # Arguments
+    ///
+    /// - `curve`: generatrix
+    /// - `point`: point on the axis
+    /// - `axis`: direction of the axis
+    /// - `angle`: angle of revolution in radians, in `(0, 2 pi]`
+    pub fn revolve(curve: &NurbsCurve, point: &Vector3f, axis: &Vector3f, angle: Scalar) -> Self {
+        assert!(angle > 0. && angle <= 2. * PI + 1e-12, "invalid angle of revolution: {}", angle);
+        let axis = axis.normalize();
+
+        let narcs = if angle <= PI / 2. + 1e-12 {
+            1
+        } else if angle <= PI + 1e-12 {
+            2
+        } else if angle <= 1.5 * PI + 1e-12 {
+            3
+        } else {
+            4
+        };
+        let dtheta = angle / narcs as Scalar;
+        let wm = (dtheta / 2.).cos();
+
+        let mut knot_vec_u = vec![0.; 3];
+        for i in 1..narcs {
+            let u = i as Scalar / narcs as Scalar;
+            knot_vec_u.push(u);
+            knot_vec_u.push(u);
+        }
+        knot_vec_u.extend(vec![1.; 3]);
+
+        let size_u = 2 * narcs + 1;
+        let size_v = curve.size();
+        let mut ctrlpts = vec![Vector3f::zeros(); size_u * size_v];
+        let mut weights = vec![0.; size_u * size_v];
+        for j in 0..size_v {
+            let pj = curve.ctrlpts[j];
+            let wj = curve.weights[j];
+
+            // circle of the control point about the axis
+            let o = point + (pj - point).dot(&axis) * axis;
+            let x = pj - o;
+            let r = x.norm();
+            let (x, y) = if r > 1e-12 {
+                let x = x / r;
+                (x, axis.cross(&x))
+            } else {
+                (Vector3f::zeros(), Vector3f::zeros())
+            };
+
+            ctrlpts[j] = pj;
+            weights[j] = wj;
+            let mut p0 = pj;
+            let mut t0 = y;
+            let mut index = 0;
+            let mut theta: Scalar = 0.;
+            for _ in 1..=narcs {
+                theta += dtheta;
+                let p2 = o + r * theta.cos() * x + r * theta.sin() * y;
+                let t2 = -theta.sin() * x + theta.cos() * y;
+
+                // middle control point at the intersection of the tangents
+                let n = t0.cross(&t2);
+                let p1 = if n.norm_squared() > 1e-24 {
+                    p0 + ((p2 - p0).cross(&t2).dot(&n) / n.norm_squared()) * t0
+                } else {
+                    o
+                };
+
+                ctrlpts[(index + 1) * size_v + j] = p1;
+                weights[(index + 1) * size_v + j] = wm * wj;
+                ctrlpts[(index + 2) * size_v + j] = p2;
+                weights[(index + 2) * size_v + j] = wj;
+                index += 2;
+                p0 = p2;
+                t0 = t2;
+            }
+        }
+
+        NurbsSurface::new(
+            ctrlpts,
+            knot_vec_u,
+            curve.knotvec.clone(),
+            2,
+            curve.degree,
+            size_u,
+            size_v,
+            weights,
+        )
+    }
+
+    /// Creates the ruled surface between two curves, linear in the
+    /// v-direction. The curves are made compatible first.
+    ///
+    /// # Arguments
+    ///
+    /// - `curve1`: boundary curve at `v = 0`
+    /// - `curve2`: boundary curve at `v = 1`
+    pub fn ruled(curve1: &NurbsCurve, curve2: &NurbsCurve) -> Self {
+        let sections = make_compatible(&vec![curve1.clone(), curve2.clone()]);
+        return from_sections(&sections, vec![0., 0., 1., 1.], 1);
+    }
+
+    /// Creates the surface passing through a set of section curves
+    /// (skinning, The NURBS Book Section 10.3).
+    ///
+    /// The sections are made compatible, and their control points are then
+    /// interpolated in the v-direction. The section `k` is the isoparametric
+    /// curve of the surface at the parameter $v_k$, computed from the
+    /// control points of the sections with the given parameterization.
+    /// Returns `None` if there are not enough sections for the degree, or if
+    /// sections coincide, so that their parameters do.
+    ///
+    /// # Arguments
+    ///
+    /// - `curves`: section curves, at least `degree_v + 1`
+    /// - `degree_v`: degree in the v-direction
+    /// - `method`: parameterization of the sections
+    pub fn loft(curves: &Vec<NurbsCurve>, degree_v: usize, method: Parameterization) -> Option<Self> {
+        if degree_v < 1 || curves.len() <= degree_v {
+            return None;
+        }
+
+        let sections = make_compatible(curves);
+        let size_u = sections[0].size;
+        let mut params = vec![0.; sections.len()];
+        for i in 0..size_u {
+            let column: Vec<Vector3f> = sections.iter()
+                .map(|s| Vector3f::new(s.ctrlpts[i][0], s.ctrlpts[i][1], s.ctrlpts[i][2]) / s.ctrlpts[i][3])
+                .collect();
+            for (k, v) in compute_params(&column, method).iter().enumerate() {
+                params[k] += v / size_u as Scalar;
+            }
+        }
+
+        return skin(&sections, &params, degree_v);
+    }
+
+    /// Creates the surface swept by the profile curve along a trajectory
+    /// curve (The NURBS Book Section 10.4).
+    ///
+    /// The profile is given in its position at the start of the trajectory.
+    /// Copies of the profile are placed along the trajectory with rotation
+    /// minimizing frames, and skinned with the degree of the trajectory. The
+    /// surface passes exactly through the placed copies, and approximates
+    /// the sweep in between. Returns `None` if there are fewer than two
+    /// sections or the frames are undefined, e.g. where the trajectory has a
+    /// vanishing derivative.
+    ///
+    /// # Arguments
+    ///
+    /// - `profile`: profile curve, the u-direction of the surface
+    /// - `trajectory`: trajectory curve, the v-direction of the surface
+    /// - `num_sections`: number of profile copies, at least two
+    pub fn sweep(profile: &NurbsCurve, trajectory: &NurbsCurve, num_sections: usize) -> Option<Self> {
+        if num_sections < 2 {
+            return None;
+        }
+        let degree_v = trajectory.degree.min(num_sections - 1);
+
+        let params: Vec<Scalar> = (0..num_sections)
+            .map(|k| k as Scalar / (num_sections - 1) as Scalar)
+            .collect();
+        let (a, b) = trajectory.domain();
+        let params_t: Vec<Scalar> = params.iter().map(|t| a + (b - a) * t).collect();
+        let frames = rotation_minimizing_frames(trajectory, &params_t)?;
+        let (x0, rotm0) = &frames[0];
+
+        let mut sections = Vec::with_capacity(num_sections);
+        for (x, rotm) in &frames {
+            let rotm = rotm * rotm0.transpose();
+            let mut section = profile.bspline.clone();
+            for point in section.ctrlpts.iter_mut() {
+                let w = point[3];
+                let p = Vector3f::new(point[0], point[1], point[2]) / w;
+                let p = x + rotm * (p - x0);
+                *point = Vector4f::new(p[0] * w, p[1] * w, p[2] * w, w);
+            }
+            sections.push(section);
+        }
+
+        return skin(&sections, &params, degree_v);
+    }
+}
@@ -1,10 +1,68 @@
-use core::ptr::NonNull;
 use crate::geometry::*;
+use crate::utils::{IndexedMesh, IndexedTriangle, Triangle, Vertex};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
+/// Doubly connected edge list. The list owns its vertices, half-edges and
+/// faces, and frees them when dropped; the handles returned by its
+/// accessors borrow the list.
 pub struct DoubleEdgeList<V, E, F> {
-    pub vertices: Vec<DcelVertexPtr<V, E, F>>,
-    pub edges: Vec<DcelHalfEdgePtr<V, E, F>>,
-    pub faces: Vec<DcelFacePtr<V, E, F>>,
+    vertices: Vec<DcelVertexPtr<'static, V, E, F>>,
+    edges: Vec<DcelHalfEdgePtr<'static, V, E, F>>,
+    faces: Vec<DcelFacePtr<'static, V, E, F>>,
+    marker: PhantomData<(V, E, F)>,
+}
+
+impl<V, E, F> DoubleEdgeList<V, E, F> {
+
+    /// Get the vertices
+    pub fn vertices(&self) -> &[DcelVertexPtr<'_, V, E, F>] {
+        &self.vertices
+    }
+
+    /// Get the half-edges
+    pub fn edges(&self) -> &[DcelHalfEdgePtr<'_, V, E, F>] {
+        &self.edges
+    }
+
+    /// Get the faces
+    pub fn faces(&self) -> &[DcelFacePtr<'_, V, E, F>] {
+        &self.faces
+    }
+
+    fn add_vertex(&mut self, element: V) -> DcelVertexPtr<'static, V, E, F> {
+        let vertex = DcelVertexPtr::new(element);
+        self.vertices.push(vertex);
+        return vertex;
+    }
+
+    fn add_edge(&mut self, element: E) -> DcelHalfEdgePtr<'static, V, E, F> {
+        let edge = DcelHalfEdgePtr::new(element);
+        self.edges.push(edge);
+        return edge;
+    }
+
+    fn add_face(&mut self, element: F) -> DcelFacePtr<'static, V, E, F> {
+        let face = DcelFacePtr::new(element);
+        self.faces.push(face);
+        return face;
+    }
+}
+
+impl<V, E, F> Drop for DoubleEdgeList<V, E, F> {
+    fn drop(&mut self) {
+        unsafe {
+            for vertex in &self.vertices {
+                drop(Box::from_raw(vertex.as_raw()));
+            }
+            for edge in &self.edges {
+                drop(Box::from_raw(edge.as_raw()));
+            }
+            for face in &self.faces {
+                drop(Box::from_raw(face.as_raw()));
+            }
+        }
+    }
 }
 
 impl<V, E, F> DoubleEdgeList<V, E, F>
@@ -14,20 +72,20 @@ impl<V, E, F> DoubleEdgeList<V, E, F>
             vertices: Default::default(),
             edges: Default::default(),
             faces: Default::default(),
+            marker: PhantomData,
         }
     }
 
     pub fn initialize(&mut self, vertex_element: &Vec<V>, edge_element: &Vec<E>, face_element: &F) {
-        let face = DcelFacePtr::new(face_element.clone());
-        self.faces.push(face);
+        let face = self.add_face(face_element.clone());
 
         let mut prev_left_edge  = DcelHalfEdgePtr::null();
         let mut prev_right_edge = DcelHalfEdgePtr::null();
 
         for i in 0..vertex_element.len() {
-            let vertex = DcelVertexPtr::new(vertex_element[i].clone());
-            let mut left   = DcelHalfEdgePtr::new(edge_element[2 * i].clone());
-            let mut right  = DcelHalfEdgePtr::new(edge_element[2 * i + 1].clone());
+            let vertex = self.add_vertex(vertex_element[i].clone());
+            let mut left   = self.add_edge(edge_element[2 * i].clone());
+            let mut right  = self.add_edge(edge_element[2 * i + 1].clone());
 
             left.set_face(face);
             left.set_next(DcelHalfEdgePtr::null());
@@ -39,11 +97,7 @@ impl<V, E, F> DoubleEdgeList<V, E, F>
             right.set_origin(DcelVertexPtr::null());
             right.set_twin(left);
 
-            self.edges.push(left);
-            self.edges.push(right);
-
             vertex.set_leaving(left);
-            self.vertices.push(vertex);
 
             if ! prev_left_edge.is_null() {
                 prev_left_edge.set_next(left);
@@ -67,3 +121,143 @@ impl<V, E, F> DoubleEdgeList<V, E, F>
         face.set_edge(first_left_edge);
     }
 }
+
+impl<V, E, F> DoubleEdgeList<V, E, F>
+    where V: Clone, E: Clone, F: Clone {
+
+    /// Builds the half-edge structure of a polygon mesh. Twin half-edges are
+    /// linked across the shared edges, and the edges used by a single face
+    /// get a boundary twin without face, linked along the holes.
+    ///
+    /// Returns `None` if a face has less than three vertices or an invalid
+    /// index, or if a directed edge is used twice, i.e. the faces are not
+    /// consistently oriented or an edge is shared by more than two faces.
+    ///
+    /// # Arguments
+    ///
+    /// - `vertex_elements`: elements of the vertices
+    /// - `faces`: counterclockwise indices of the vertices of each face
+    /// - `edge_element`: element of every half-edge
+    /// - `face_elements`: elements of the faces
+    pub fn from_faces(vertex_elements: &Vec<V>, faces: &Vec<Vec<usize>>,
+                      edge_element: &E, face_elements: &Vec<F>) -> Option<Self> {
+        let mut dcel = DoubleEdgeList::new();
+        if faces.len() != face_elements.len() {
+            return None;
+        }
+        for element in vertex_elements {
+            dcel.add_vertex(element.clone());
+        }
+
+        let mut half_edges = HashMap::new();
+        let mut keys = Vec::new();
+        for (face_indices, face_element) in faces.iter().zip(face_elements.iter()) {
+            let n = face_indices.len();
+            if n < 3 {
+                return None;
+            }
+            let face = dcel.add_face(face_element.clone());
+
+            let first = dcel.edges.len();
+            for k in 0..n {
+                let (a, b) = (face_indices[k], face_indices[(k + 1) % n]);
+                if a >= dcel.vertices.len() || b >= dcel.vertices.len() || a == b
+                    || half_edges.contains_key(&(a, b)) {
+                    return None;
+                }
+                let mut edge = dcel.add_edge(edge_element.clone());
+                edge.set_origin(dcel.vertices[a]);
+                edge.set_face(face);
+                if dcel.vertices[a].leaving().is_null() {
+                    dcel.vertices[a].set_leaving(edge);
+                }
+                half_edges.insert((a, b), edge);
+                keys.push((a, b));
+            }
+            for k in 0..n {
+                let next = dcel.edges[first + (k + 1) % n];
+                dcel.edges[first + k].set_next(next);
+            }
+            face.set_edge(dcel.edges[first]);
+        }
+
+        // twins, with boundary half-edges for the unshared edges
+        let mut boundary: HashMap<usize, Vec<DcelHalfEdgePtr<V, E, F>>> = HashMap::new();
+        let mut boundary_edges = Vec::new();
+        for &(a, b) in &keys {
+            let mut edge = half_edges[&(a, b)];
+            match half_edges.get(&(b, a)) {
+                Some(&twin) => edge.set_twin(twin),
+                None => {
+                    let mut twin = dcel.add_edge(edge_element.clone());
+                    twin.set_origin(dcel.vertices[b]);
+                    twin.set_twin(edge);
+                    edge.set_twin(twin);
+                    // boundary vertices leave along the boundary
+                    dcel.vertices[b].set_leaving(twin);
+                    boundary.entry(b).or_insert_with(Vec::new).push(twin);
+                    boundary_edges.push((a, twin));
+                },
+            }
+        }
+
+        // a boundary half-edge from b to a continues with the one leaving a
+        for (a, mut edge) in boundary_edges {
+            let next = boundary.get_mut(&a).and_then(|edges| edges.pop());
+            edge.set_next(next.unwrap_or(DcelHalfEdgePtr::null()));
+        }
+
+        return Some(dcel);
+    }
+
+    /// Returns the half-edges without face, along the holes of the mesh.
+    pub fn boundary_edges(&self) -> Vec<DcelHalfEdgePtr<'_, V, E, F>> {
+        self.edges.iter().filter(|edge| edge.is_boundary()).cloned().collect()
+    }
+
+    /// Whether every half-edge has a face, i.e. the mesh has no holes.
+    pub fn is_closed(&self) -> bool {
+        self.edges.iter().all(|edge| !edge.is_boundary())
+    }
+}
+
+impl DoubleEdgeList<Vertex, (), usize> {
+
+    /// Builds the half-edge structure of an indexed triangle mesh. The
+    /// vertices keep their positions and order, and each face keeps the
+    /// index of its triangle in the mesh.
+    pub fn from_indexed_mesh(mesh: &IndexedMesh) -> Option<Self> {
+        let faces = mesh.faces.iter().map(|face| face.vertices.to_vec()).collect();
+        let face_elements = (0..mesh.faces.len()).collect();
+        return DoubleEdgeList::from_faces(&mesh.vertices, &faces, &(), &face_elements);
+    }
+
+    /// Builds the half-edge structure of a triangle soup, welding the
+    /// vertices closer than the tolerance. The triangles collapsed by the
+    /// welding are skipped, and each face keeps the index of its triangle.
+    ///
+    /// # Arguments
+    ///
+    /// - `triangles`: triangles with their own vertices
+    /// - `tolerance`: maximum distance between welded vertices
+    pub fn from_triangles(triangles: &[Triangle], tolerance: f32) -> Option<Self> {
+        let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+        for triangle in triangles {
+            let n = mesh.vertices.len();
+            mesh.vertices.extend_from_slice(&triangle.vertices);
+            mesh.faces.push(IndexedTriangle { normal: triangle.normal, vertices: [n, n + 1, n + 2] });
+        }
+        mesh.weld_vertices(tolerance);
+
+        let mut faces = Vec::new();
+        let mut face_elements = Vec::new();
+        for (i, face) in mesh.faces.iter().enumerate() {
+            let [a, b, c] = face.vertices;
+            if a != b && b != c && c != a {
+                faces.push(face.vertices.to_vec());
+                face_elements.push(i);
+            }
+        }
+        return DoubleEdgeList::from_faces(&mesh.vertices, &faces, &(), &face_elements);
+    }
+}
@@ -1,52 +1,66 @@
 use crate::geometry::*;
+use std::marker::PhantomData;
 use std::ptr;
 
+/// Face node, owned by its double edge list.
 pub struct DoubleEdgeListFace<V, E, F> {
-    pub element: F,
-    pub edge: DcelHalfEdgePtr<V, E, F>,
+    element: F,
+    edge: *mut DoubleEdgeListHalfEdge<V, E, F>,
 }
 
-pub struct DcelFacePtr<V, E, F> {
-    pub ptr: *mut DoubleEdgeListFace<V, E, F>,
+/// Handle to a face, borrowed from the double edge list owning it.
+pub struct DcelFacePtr<'a, V, E, F> {
+    ptr: *mut DoubleEdgeListFace<V, E, F>,
+    marker: PhantomData<&'a ()>,
 }
 
-impl<V, E, F> DcelFacePtr<V, E, F> {
+impl<'a, V, E, F> DcelFacePtr<'a, V, E, F> {
 
-    pub fn new(element: F) -> Self {
+    /// Allocates a face node, to be freed by the double edge list
+    pub(super) fn new(element: F) -> Self {
         let face = DoubleEdgeListFace {
             element,
-            edge: DcelHalfEdgePtr::null(),
+            edge: ptr::null_mut(),
         };
+        DcelFacePtr::from_raw(Box::into_raw(Box::new(face)))
+    }
+
+    #[inline]
+    pub(super) fn from_raw(ptr: *mut DoubleEdgeListFace<V, E, F>) -> Self {
         DcelFacePtr {
-            ptr: Box::into_raw(Box::new(face)),
+            ptr,
+            marker: PhantomData,
         }
     }
 
     #[inline]
-    pub fn edge(&self) -> DcelHalfEdgePtr<V, E, F> {
+    pub(super) fn as_raw(&self) -> *mut DoubleEdgeListFace<V, E, F> {
+        self.ptr
+    }
+
+    #[inline]
+    pub fn edge(&self) -> DcelHalfEdgePtr<'a, V, E, F> {
         if self.is_null() {
             return DcelHalfEdgePtr::null();
         }
         unsafe {
-            (*self.ptr).edge.clone()
+            DcelHalfEdgePtr::from_raw((*self.ptr).edge)
         }
     }
 
     #[inline]
-    pub fn set_edge(&self, edge: DcelHalfEdgePtr<V, E, F>) {
+    pub(super) fn set_edge(&self, edge: DcelHalfEdgePtr<'a, V, E, F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).edge = edge;
+            (*self.ptr).edge = edge.as_raw();
         }
     }
 
     #[inline]
-    pub fn null() -> DcelFacePtr<V, E, F> {
-        DcelFacePtr {
-            ptr: ptr::null_mut(),
-        }
+    pub fn null() -> DcelFacePtr<'a, V, E, F> {
+        DcelFacePtr::from_raw(ptr::null_mut())
     }
 
     #[inline]
@@ -55,24 +69,22 @@ impl<V, E, F> DcelFacePtr<V, E, F> {
     }
 }
 
-impl<V,E,F> Clone for DcelFacePtr<V,E,F> {
+impl<'a,V,E,F> Clone for DcelFacePtr<'a,V,E,F> {
     fn clone(&self) -> Self {
-        DcelFacePtr {
-            ptr: self.ptr,
-        }
+        *self
     }
 }
 
-impl<V,E,F> Copy for DcelFacePtr<V,E,F> {
+impl<'a,V,E,F> Copy for DcelFacePtr<'a,V,E,F> {
 }
 
-impl<V,E,F> PartialEq for DcelFacePtr<V,E,F> {
-    fn eq(&self, other: &DcelFacePtr<V,E,F>) -> bool {
+impl<'a,V,E,F> PartialEq for DcelFacePtr<'a,V,E,F> {
+    fn eq(&self, other: &DcelFacePtr<'a,V,E,F>) -> bool {
         self.ptr == other.ptr
     }
 }
 
-impl<V,E,F> DcelFacePtr<V,E,F> {
+impl<'a,V,E,F> DcelFacePtr<'a,V,E,F> {
     pub fn get_edge_count(&self) -> usize {
         let mut edge = self.edge();
 
@@ -87,3 +99,65 @@ impl<V,E,F> DcelFacePtr<V,E,F> {
         return count;
     }
 }
+
+impl<'a,V,E,F> DcelFacePtr<'a,V,E,F> {
+
+    /// Get face element
+    pub fn element(&self) -> &'a F {
+        assert!(!self.is_null());
+        unsafe {
+            &(*self.ptr).element
+        }
+    }
+
+    /// Iterate over the half-edges of the face loop
+    pub fn edges(&self) -> DcelFaceEdgeIter<'a,V,E,F> {
+        DcelFaceEdgeIter::new(self.edge())
+    }
+
+    /// Iterate over the vertices of the face loop
+    pub fn vertices(&self) -> impl Iterator<Item = DcelVertexPtr<'a,V,E,F>> {
+        self.edges().map(|edge| edge.origin())
+    }
+
+    /// Iterate over the faces sharing an edge with the face
+    pub fn neighbors(&self) -> impl Iterator<Item = DcelFacePtr<'a,V,E,F>> {
+        self.edges()
+            .map(|edge| edge.twin().face())
+            .filter(|face| !face.is_null())
+    }
+}
+
+/// Iterator over the half-edges of a loop, following the next half-edges.
+/// It also walks along a hole when started from a boundary half-edge.
+pub struct DcelFaceEdgeIter<'a,V,E,F> {
+    start: DcelHalfEdgePtr<'a,V,E,F>,
+    current: DcelHalfEdgePtr<'a,V,E,F>,
+}
+
+impl<'a,V,E,F> DcelFaceEdgeIter<'a,V,E,F> {
+
+    /// Creates the iterator over the loop of the half-edge
+    pub fn new(edge: DcelHalfEdgePtr<'a,V,E,F>) -> Self {
+        DcelFaceEdgeIter {
+            start: edge,
+            current: edge,
+        }
+    }
+}
+
+impl<'a,V,E,F> Iterator for DcelFaceEdgeIter<'a,V,E,F> {
+    type Item = DcelHalfEdgePtr<'a,V,E,F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_null() {
+            return None;
+        }
+        let edge = self.current;
+        self.current = edge.next();
+        if self.current == self.start {
+            self.current = DcelHalfEdgePtr::null();
+        }
+        return Some(edge);
+    }
+}
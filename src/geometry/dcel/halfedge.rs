@@ -1,85 +1,96 @@
 use crate::geometry::*;
+use std::marker::PhantomData;
 use std::ptr;
 
+/// Half-edge node, owned by its double edge list.
 pub struct DoubleEdgeListHalfEdge<V, E, F> {
-    pub element: E,
+    element: E,
 
-    pub origin: DcelVertexPtr<V, E, F>,
+    origin: *mut DoubleEdgeListVertex<V, E, F>,
     // The incident half edge in the list having the same face
-    pub next: DcelHalfEdgePtr<V, E, F>,
-    pub twin: DcelHalfEdgePtr<V, E, F>,
+    next: *mut DoubleEdgeListHalfEdge<V, E, F>,
+    twin: *mut DoubleEdgeListHalfEdge<V, E, F>,
 
     // The incident face of this half edge
-    pub face: DcelFacePtr<V, E, F>,
-
-    pub visited: bool,
+    face: *mut DoubleEdgeListFace<V, E, F>,
 }
 
-pub struct DcelHalfEdgePtr<V, E, F> {
-    pub ptr: *mut DoubleEdgeListHalfEdge<V, E, F>,
+/// Handle to a half-edge, borrowed from the double edge list owning it.
+pub struct DcelHalfEdgePtr<'a, V, E, F> {
+    ptr: *mut DoubleEdgeListHalfEdge<V, E, F>,
+    marker: PhantomData<&'a ()>,
 }
 
-impl<V, E, F> DcelHalfEdgePtr<V, E, F> {
+impl<'a, V, E, F> DcelHalfEdgePtr<'a, V, E, F> {
 
-    pub fn new(element: E) -> Self {
+    /// Allocates a half-edge node, to be freed by the double edge list
+    pub(super) fn new(element: E) -> Self {
         let edge = DoubleEdgeListHalfEdge {
             element,
-            origin: DcelVertexPtr::null(),
-            next: DcelHalfEdgePtr::null(),
-            twin: DcelHalfEdgePtr::null(),
-            face: DcelFacePtr::null(),
-            visited: false,
+            origin: ptr::null_mut(),
+            next: ptr::null_mut(),
+            twin: ptr::null_mut(),
+            face: ptr::null_mut(),
         };
+        DcelHalfEdgePtr::from_raw(Box::into_raw(Box::new(edge)))
+    }
+
+    #[inline]
+    pub(super) fn from_raw(ptr: *mut DoubleEdgeListHalfEdge<V, E, F>) -> Self {
         DcelHalfEdgePtr {
-            ptr: Box::into_raw(Box::new(edge)),
+            ptr,
+            marker: PhantomData,
         }
     }
 
     #[inline]
-    pub fn origin(&self) -> DcelVertexPtr<V, E, F> {
+    pub(super) fn as_raw(&self) -> *mut DoubleEdgeListHalfEdge<V, E, F> {
+        self.ptr
+    }
+
+    #[inline]
+    pub fn origin(&self) -> DcelVertexPtr<'a, V, E, F> {
         if self.is_null() {
             return DcelVertexPtr::null();
         }
         unsafe {
-            (*self.ptr).origin.clone()
+            DcelVertexPtr::from_raw((*self.ptr).origin)
         }
     }
 
     #[inline]
-    pub fn next(&self) -> DcelHalfEdgePtr<V, E, F> {
+    pub fn next(&self) -> DcelHalfEdgePtr<'a, V, E, F> {
         if self.is_null() {
             return DcelHalfEdgePtr::null();
         }
         unsafe {
-            (*self.ptr).next.clone()
+            DcelHalfEdgePtr::from_raw((*self.ptr).next)
         }
     }
 
     #[inline]
-    pub fn twin(&self) -> DcelHalfEdgePtr<V, E, F> {
+    pub fn twin(&self) -> DcelHalfEdgePtr<'a, V, E, F> {
         if self.is_null() {
             return DcelHalfEdgePtr::null();
         }
         unsafe {
-            (*self.ptr).twin.clone()
+            DcelHalfEdgePtr::from_raw((*self.ptr).twin)
         }
     }
 
     #[inline]
-    pub fn face(&self) -> DcelFacePtr<V, E, F> {
+    pub fn face(&self) -> DcelFacePtr<'a, V, E, F> {
         if self.is_null() {
             return DcelFacePtr::null();
         }
         unsafe {
-            (*self.ptr).face.clone()
+            DcelFacePtr::from_raw((*self.ptr).face)
         }
     }
 
     #[inline]
-    pub fn null() -> DcelHalfEdgePtr<V, E, F> {
-        DcelHalfEdgePtr {
-            ptr: ptr::null_mut(),
-        }
+    pub fn null() -> DcelHalfEdgePtr<'a, V, E, F> {
+        DcelHalfEdgePtr::from_raw(ptr::null_mut())
     }
 
     #[inline]
@@ -88,31 +99,29 @@ impl<V, E, F> DcelHalfEdgePtr<V, E, F> {
     }
 }
 
-impl<V,E,F> Clone for DcelHalfEdgePtr<V,E,F> {
+impl<'a,V,E,F> Clone for DcelHalfEdgePtr<'a,V,E,F> {
     fn clone(&self) -> Self {
-        DcelHalfEdgePtr {
-            ptr: self.ptr,
-        }
+        *self
     }
 }
 
-impl<V,E,F> Copy for DcelHalfEdgePtr<V,E,F> { }
+impl<'a,V,E,F> Copy for DcelHalfEdgePtr<'a,V,E,F> { }
 
-impl<V,E,F> PartialEq for DcelHalfEdgePtr<V,E,F> {
-    fn eq(&self, other: &DcelHalfEdgePtr<V,E,F>) -> bool {
+impl<'a,V,E,F> PartialEq for DcelHalfEdgePtr<'a,V,E,F> {
+    fn eq(&self, other: &DcelHalfEdgePtr<'a,V,E,F>) -> bool {
         self.ptr == other.ptr
     }
 }
 
-impl<V,E,F> DcelHalfEdgePtr<V,E,F> {
+impl<'a,V,E,F> DcelHalfEdgePtr<'a,V,E,F> {
 
     /// Get destination vertex
-    pub fn get_destination(&self) -> DcelVertexPtr<V,E,F> {
+    pub fn get_destination(&self) -> DcelVertexPtr<'a,V,E,F> {
         self.next().origin()
     }
 
     /// Get previous vertex
-    pub fn get_previous(&self) -> DcelHalfEdgePtr<V,E,F> {
+    pub fn get_previous(&self) -> DcelHalfEdgePtr<'a,V,E,F> {
         let mut edge = self.twin().next().twin();
         // walk around the face
         while *self != edge.next() {
@@ -121,48 +130,66 @@ impl<V,E,F> DcelHalfEdgePtr<V,E,F> {
         return edge;
     }
 
+    /// Get half-edge element
+    pub fn element(&self) -> &'a E {
+        assert!(!self.is_null());
+        unsafe {
+            &(*self.ptr).element
+        }
+    }
+
+    /// Whether the half-edge lies on a hole, without adjacent face
+    pub fn is_boundary(&self) -> bool {
+        self.face().is_null()
+    }
+
+    /// Iterate over the half-edges of the loop of this half-edge
+    pub fn loop_edges(&self) -> DcelFaceEdgeIter<'a,V,E,F> {
+        DcelFaceEdgeIter::new(*self)
+    }
+
     /// Get adjacent face
-    pub fn get_face(&self) -> DcelFacePtr<V,E,F> {
+    pub fn get_face(&self) -> DcelFacePtr<'a,V,E,F> {
         self.face()
     }
 
     /// Set adjacent face
-    pub fn set_face(&mut self, face: DcelFacePtr<V, E, F>) {
+    pub(super) fn set_face(&mut self, face: DcelFacePtr<'a, V, E, F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).face = face;
+            (*self.ptr).face = face.as_raw();
         }
     }
 
     /// Set next edge
-    pub fn set_next(&mut self, next: DcelHalfEdgePtr<V, E, F>) {
+    pub(super) fn set_next(&mut self, next: DcelHalfEdgePtr<'a, V, E, F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).next = next;
+            (*self.ptr).next = next.as_raw();
         }
     }
 
     /// Set origin vertex
-    pub fn set_origin(&mut self, origin: DcelVertexPtr<V, E, F>) {
+    pub(super) fn set_origin(&mut self, origin: DcelVertexPtr<'a, V, E, F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).origin = origin;
+            (*self.ptr).origin = origin.as_raw();
         }
     }
 
     /// Set twin edge
-    pub fn set_twin(&mut self, twin: DcelHalfEdgePtr<V, E, F>) {
+    pub(super) fn set_twin(&mut self, twin: DcelHalfEdgePtr<'a, V, E, F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).twin = twin;
+            (*self.ptr).twin = twin.as_raw();
         }
     }
 }
@@ -1,52 +1,66 @@
 use crate::geometry::*;
+use std::marker::PhantomData;
 use std::ptr;
 
+/// Vertex node, owned by its double edge list.
 pub struct DoubleEdgeListVertex<V,E,F> {
-    pub element: V,
-    pub leaving: DcelHalfEdgePtr<V,E,F>,
+    element: V,
+    leaving: *mut DoubleEdgeListHalfEdge<V,E,F>,
 }
 
-pub struct DcelVertexPtr<V,E,F> {
-    pub ptr: *mut DoubleEdgeListVertex<V,E,F>,
+/// Handle to a vertex, borrowed from the double edge list owning it.
+pub struct DcelVertexPtr<'a,V,E,F> {
+    ptr: *mut DoubleEdgeListVertex<V,E,F>,
+    marker: PhantomData<&'a ()>,
 }
 
-impl<V,E,F> DcelVertexPtr<V,E,F> {
+impl<'a,V,E,F> DcelVertexPtr<'a,V,E,F> {
 
-    pub fn new(element: V) -> Self {
+    /// Allocates a vertex node, to be freed by the double edge list
+    pub(super) fn new(element: V) -> Self {
         let vertex = DoubleEdgeListVertex {
             element,
-            leaving: DcelHalfEdgePtr::null(),
+            leaving: ptr::null_mut(),
         };
+        DcelVertexPtr::from_raw(Box::into_raw(Box::new(vertex)))
+    }
+
+    #[inline]
+    pub(super) fn from_raw(ptr: *mut DoubleEdgeListVertex<V,E,F>) -> Self {
         DcelVertexPtr {
-            ptr: Box::into_raw(Box::new(vertex)),
+            ptr,
+            marker: PhantomData,
         }
     }
 
     #[inline]
-    pub fn leaving(&self) -> DcelHalfEdgePtr<V,E,F> {
+    pub(super) fn as_raw(&self) -> *mut DoubleEdgeListVertex<V,E,F> {
+        self.ptr
+    }
+
+    #[inline]
+    pub fn leaving(&self) -> DcelHalfEdgePtr<'a,V,E,F> {
         if self.is_null() {
             return DcelHalfEdgePtr::null();
         }
         unsafe {
-            (*self.ptr).leaving.clone()
+            DcelHalfEdgePtr::from_raw((*self.ptr).leaving)
         }
     }
 
     #[inline]
-    pub fn set_leaving(&self, leaving: DcelHalfEdgePtr<V,E,F>) {
+    pub(super) fn set_leaving(&self, leaving: DcelHalfEdgePtr<'a,V,E,F>) {
         if self.is_null() {
             return;
         }
         unsafe {
-            (*self.ptr).leaving = leaving;
+            (*self.ptr).leaving = leaving.as_raw();
         }
     }
 
     #[inline]
-    pub fn null() -> DcelVertexPtr<V,E,F> {
-        DcelVertexPtr {
-            ptr: ptr::null_mut(),
-        }
+    pub fn null() -> DcelVertexPtr<'a,V,E,F> {
+        DcelVertexPtr::from_raw(ptr::null_mut())
     }
 
     #[inline]
@@ -55,29 +69,27 @@ impl<V,E,F> DcelVertexPtr<V,E,F> {
     }
 }
 
-impl<V,E,F> PartialEq for DcelVertexPtr<V,E,F> {
-    fn eq(&self, other: &DcelVertexPtr<V,E,F>) -> bool {
+impl<'a,V,E,F> PartialEq for DcelVertexPtr<'a,V,E,F> {
+    fn eq(&self, other: &DcelVertexPtr<'a,V,E,F>) -> bool {
         self.ptr == other.ptr
     }
 }
 
-impl<V,E,F> Copy for DcelVertexPtr<V,E,F> { }
+impl<'a,V,E,F> Copy for DcelVertexPtr<'a,V,E,F> { }
 
-impl<V,E,F> Clone for DcelVertexPtr<V,E,F> {
+impl<'a,V,E,F> Clone for DcelVertexPtr<'a,V,E,F> {
     fn clone(&self) -> Self {
-        DcelVertexPtr {
-            ptr: self.ptr,
-        }
+        *self
     }
 }
 
-impl<V,E,F> DcelVertexPtr<V,E,F> {
+impl<'a,V,E,F> DcelVertexPtr<'a,V,E,F> {
 
     /// Get edge to a given vertex
-    pub fn get_edge_to(&self, node: DcelVertexPtr<V,E,F>) -> DcelHalfEdgePtr<V,E,F> {
+    pub fn get_edge_to(&self, node: DcelVertexPtr<'a,V,E,F>) -> DcelHalfEdgePtr<'a,V,E,F> {
         if ! self.leaving().is_null() {
             if self.leaving().twin().origin() == node {
-                return self.leaving().clone();
+                return self.leaving();
             } else {
                 let mut edge = self.leaving().twin().next();
                 while edge != self.leaving() {
@@ -93,4 +105,62 @@ impl<V,E,F> DcelVertexPtr<V,E,F> {
         return DcelHalfEdgePtr::null();
     }
 
-}
\ No newline at end of file
+}
+impl<'a,V,E,F> DcelVertexPtr<'a,V,E,F> {
+
+    /// Get vertex element
+    pub fn element(&self) -> &'a V {
+        assert!(!self.is_null());
+        unsafe {
+            &(*self.ptr).element
+        }
+    }
+
+    /// Iterate over the half-edges leaving the vertex
+    pub fn leaving_edges(&self) -> DcelVertexEdgeIter<'a,V,E,F> {
+        DcelVertexEdgeIter {
+            start: self.leaving(),
+            current: self.leaving(),
+        }
+    }
+
+    /// Iterate over the neighbor vertices, the one-ring of the vertex
+    pub fn one_ring(&self) -> impl Iterator<Item = DcelVertexPtr<'a,V,E,F>> {
+        self.leaving_edges().map(|edge| edge.get_destination())
+    }
+
+    /// Iterate over the faces around the vertex
+    pub fn faces(&self) -> impl Iterator<Item = DcelFacePtr<'a,V,E,F>> {
+        self.leaving_edges()
+            .map(|edge| edge.face())
+            .filter(|face| !face.is_null())
+    }
+
+    /// Whether the vertex lies on a hole
+    pub fn is_boundary(&self) -> bool {
+        self.leaving_edges().any(|edge| edge.is_boundary())
+    }
+}
+
+/// Iterator over the half-edges leaving a vertex, turning around it from
+/// twin to next.
+pub struct DcelVertexEdgeIter<'a,V,E,F> {
+    start: DcelHalfEdgePtr<'a,V,E,F>,
+    current: DcelHalfEdgePtr<'a,V,E,F>,
+}
+
+impl<'a,V,E,F> Iterator for DcelVertexEdgeIter<'a,V,E,F> {
+    type Item = DcelHalfEdgePtr<'a,V,E,F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_null() {
+            return None;
+        }
+        let edge = self.current;
+        self.current = edge.twin().next();
+        if self.current == self.start {
+            self.current = DcelHalfEdgePtr::null();
+        }
+        return Some(edge);
+    }
+}
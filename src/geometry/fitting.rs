@@ -0,0 +1,650 @@
+use na::*;
+use na::allocator::Allocator;
+use crate::math::{Scalar, Vector3f, Matrix3f, INFINITY};
+use crate::geometry::{BSplineCurve, BSplineSurface, NurbsCurve, NurbsSurface, ProjectionCriteria};
+use crate::geometry::helper::*;
+
+/// Parameterization of the data points for interpolation and approximation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Parameterization {
+    Uniform,        // equally spaced parameters
+    ChordLength,    // parameters proportional to the chord lengths
+    Centripetal,    // parameters proportional to the square roots of the chord lengths
+}
+
+/// Parameters of the least-squares fit of a surface to scattered points.
+pub struct SurfaceFitCriteria {
+    pub iterations: usize,    // number of reparameterization iterations
+    pub smoothing:  Scalar,   // weight of the smoothing term, relative to the number of points
+    pub samples:    usize,    // number of seed samples per direction to project onto the base surface
+}
+
+impl SurfaceFitCriteria {
+
+    pub fn default() -> Self {
+        SurfaceFitCriteria {
+            iterations: 5,
+            smoothing:  1e-6,
+            samples:    10,
+        }
+    }
+}
+
+/// Computes the parameters of the data points in `[0, 1]`
+/// (The NURBS Book, Eq. 9.4 - 9.6).
+///
+/// Falls back to the uniform parameterization if all the points coincide.
+///
+/// # Arguments
+///
+/// - `points`: data points
+/// - `method`: parameterization
+pub(crate) fn compute_params<D: Dim + DimName>(
+    points: &Vec<VectorN<Scalar, D>>,
+    method: Parameterization) -> Vec<Scalar>
+where DefaultAllocator: Allocator<Scalar, D> {
+    let n = points.len() - 1;
+    let uniform: Vec<Scalar> = (0..=n).map(|k| k as Scalar / n as Scalar).collect();
+
+    let exponent = match method {
+        Parameterization::Uniform => return uniform,
+        Parameterization::ChordLength => 1.,
+        Parameterization::Centripetal => 0.5,
+    };
+
+    let chords: Vec<Scalar> = (1..=n)
+        .map(|k| (&points[k] - &points[k-1]).norm().powf(exponent))
+        .collect();
+    let total: Scalar = chords.iter().sum();
+    if total <= 0. {
+        return uniform;
+    }
+
+    let mut params = vec![0.; n+1];
+    for k in 1..n {
+        params[k] = params[k-1] + chords[k-1] / total;
+    }
+    params[n] = 1.;
+
+    return params;
+}
+
+/// Computes the clamped knot vector of a spline interpolating data points
+/// at `params` by averaging (The NURBS Book, Eq. 9.8 and 9.22).
+///
+/// If the end derivatives are interpolated as well, the spline has two
+/// additional control points and the knot vector two additional knots.
+///
+/// # Arguments
+///
+/// - `params`: parameters of the data points
+/// - `degree`: degree
+/// - `end_ders`: whether the end derivatives are interpolated
+pub(crate) fn knotvec_averaging(params: &Vec<Scalar>, degree: usize, end_ders: bool) -> Vec<Scalar> {
+    let p = degree;
+    let n = params.len() - 1;
+    let (first, last) = if end_ders { (0, n - p + 1) } else { (1, n - p) };
+
+    let mut knot_vec = vec![0.; p+1];
+    for j in first..=last {
+        let sum: Scalar = params[j..j+p].iter().sum();
+        knot_vec.push(sum / p as Scalar);
+    }
+    knot_vec.extend(vec![1.; p+1]);
+
+    return knot_vec;
+}
+
+/// Computes the clamped knot vector of a spline with `num_ctrlpts` control
+/// points approximating data points at `params`, so that every knot span
+/// contains at least one parameter (The NURBS Book, Eq. 9.68 - 9.69).
+///
+/// # Arguments
+///
+/// - `params`: parameters of the data points
+/// - `degree`: degree
+/// - `num_ctrlpts`: number of control points
+pub(crate) fn knotvec_approximation(params: &Vec<Scalar>, degree: usize,
+                                    num_ctrlpts: usize) -> Vec<Scalar> {
+    let p = degree;
+    let n = num_ctrlpts - 1;
+    let d = params.len() as Scalar / (n - p + 1) as Scalar;
+
+    let mut knot_vec = vec![0.; p+1];
+    for j in 1..=n-p {
+        let i = (j as Scalar * d).floor() as usize;
+        let alpha = j as Scalar * d - i as Scalar;
+        knot_vec.push((1. - alpha) * params[i-1] + alpha * params[i]);
+    }
+    knot_vec.extend(vec![1.; p+1]);
+
+    return knot_vec;
+}
+
+/// Returns the `k`-th derivatives of all the `num_ctrlpts` basis functions
+/// at parameter `u`.
+pub(crate) fn basis_row(u: Scalar, degree: usize, k: usize, num_ctrlpts: usize,
+                        knot_vec: &Vec<Scalar>) -> Vec<Scalar> {
+    let span = find_span(num_ctrlpts, degree, u, knot_vec);
+    let ders = basis_function_ders(span, u, degree, k, knot_vec);
+    let mut row = vec![0.; num_ctrlpts];
+    for j in 0..=degree {
+        row[span - degree + j] = ders[k][j];
+    }
+    return row;
+}
+
+/// Returns the matrix of the basis functions at the parameters, one row per
+/// parameter and one column per control point.
+pub(crate) fn basis_matrix(params: &Vec<Scalar>, degree: usize, num_ctrlpts: usize,
+                           knot_vec: &Vec<Scalar>) -> DMatrix<Scalar> {
+    let mut a = DMatrix::zeros(params.len(), num_ctrlpts);
+    for (k, u) in params.iter().enumerate() {
+        let span = find_span(num_ctrlpts, degree, *u, knot_vec);
+        let basis = basis_functions(span, *u, degree, knot_vec);
+        for j in 0..=degree {
+            a[(k, span - degree + j)] = basis[j];
+        }
+    }
+    return a;
+}
+
+/// Solves the dense linear system `a * x = b`.
+///
+/// Kept out of the generic functions below, where the allocator bounds on
+/// `D` get in the way of the type inference of nalgebra.
+fn solve_dense(a: DMatrix<Scalar>, b: &DMatrix<Scalar>) -> Option<DMatrix<Scalar>> {
+    a.lu().solve(b)
+}
+
+/// Solves the linear system `a * x = b` where each row of `b` is a point.
+/// Returns `None` if the matrix is singular.
+pub(crate) fn solve_points<D: Dim + DimName>(
+    a: DMatrix<Scalar>,
+    b: &Vec<VectorN<Scalar, D>>) -> Option<Vec<VectorN<Scalar, D>>>
+where DefaultAllocator: Allocator<Scalar, D> {
+    let rhs = DMatrix::from_fn(b.len(), D::dim(), |i, j| b[i][j]);
+    let x = solve_dense(a, &rhs)?;
+    if x.iter().any(|c| !c.is_finite()) {
+        return None;
+    }
+    let points = (0..x.nrows())
+        .map(|i| VectorN::<Scalar, D>::from_fn(|j, _| x[(i, j)]))
+        .collect();
+    return Some(points);
+}
+
+impl <D: Dim + DimName> BSplineCurve<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+
+    /// Computes the curve passing through the data points.
+    /// Global interpolation, The NURBS Book Sections 9.2.1 - 9.2.2.
+    ///
+    /// If the end derivatives are given, two control points are added and
+    /// the curve is tangent to them at its ends. Returns the curve and the
+    /// distance from each data point to the curve at its parameter, or
+    /// `None` if there are not enough data points for the degree or the
+    /// interpolation matrix is singular, e.g. when consecutive data points
+    /// coincide.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: data points
+    /// - `degree`: degree, smaller than the number of data points
+    /// - `method`: parameterization of the data points
+    /// - `end_ders`: optional first derivatives at the start and the end
+    pub fn interpolate(points: &Vec<VectorN<Scalar, D>>,
+                       degree: usize,
+                       method: Parameterization,
+                       end_ders: Option<(VectorN<Scalar, D>, VectorN<Scalar, D>)>) -> Option<(Self, Vec<Scalar>)> {
+        let p = degree;
+        if p < 1 || points.len() <= p {
+            return None;
+        }
+        let n = points.len() - 1;
+
+        let params = compute_params(points, method);
+        let extra = if end_ders.is_some() { 2 } else { 0 };
+        let knot_vec = knotvec_averaging(&params, p, end_ders.is_some());
+        let num_ctrlpts = n + 1 + extra;
+
+        // one equation per data point, and one per end derivative
+        let mut rows = Vec::new();
+        let mut rhs = Vec::new();
+        for k in 0..=n {
+            if k == n {
+                if let Some((_, d1)) = &end_ders {
+                    rows.push(basis_row(params[n], p, 1, num_ctrlpts, &knot_vec));
+                    rhs.push(d1.clone_owned());
+                }
+            }
+            rows.push(basis_row(params[k], p, 0, num_ctrlpts, &knot_vec));
+            rhs.push(points[k].clone_owned());
+            if k == 0 {
+                if let Some((d0, _)) = &end_ders {
+                    rows.push(basis_row(params[0], p, 1, num_ctrlpts, &knot_vec));
+                    rhs.push(d0.clone_owned());
+                }
+            }
+        }
+
+        let a = DMatrix::from_fn(num_ctrlpts, num_ctrlpts, |i, j| rows[i][j]);
+        let ctrlpts = solve_points(a, &rhs)?;
+        let curve = BSplineCurve::new(p, ctrlpts, knot_vec);
+        let residuals = curve.residuals(points, &params);
+
+        return Some((curve, residuals));
+    }
+
+    /// Computes the curve with `num_ctrlpts` control points approximating
+    /// the data points in the weighted least-squares sense.
+    /// The NURBS Book Section 9.4.1.
+    ///
+    /// The curve passes through the first and the last data points. Returns
+    /// the curve and the distance from each data point to the curve at its
+    /// parameter, or `None` if the input is invalid or the normal equations
+    /// are singular.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: data points
+    /// - `weights`: positive weight of each data point
+    /// - `degree`: degree
+    /// - `num_ctrlpts`: number of control points, greater than the degree
+    ///   and smaller than the number of data points
+    /// - `method`: parameterization of the data points
+    pub fn approximate(points: &Vec<VectorN<Scalar, D>>,
+                       weights: &Vec<Scalar>,
+                       degree: usize,
+                       num_ctrlpts: usize,
+                       method: Parameterization) -> Option<(Self, Vec<Scalar>)> {
+        let p = degree;
+        if points.len() != weights.len() || weights.iter().any(|w| !(*w > 0.)) {
+            return None;
+        }
+        if p < 1 || num_ctrlpts <= p || num_ctrlpts >= points.len() {
+            return None;
+        }
+        let m = points.len() - 1;
+        let n = num_ctrlpts - 1;
+
+        let params = compute_params(points, method);
+        let knot_vec = knotvec_approximation(&params, p, num_ctrlpts);
+
+        // normal equations of the interior control points
+        let mut ntn = DMatrix::<Scalar>::zeros(n - 1, n - 1);
+        let mut ntr = vec![VectorN::<Scalar, D>::zeros(); n - 1];
+        for k in 1..m {
+            let row = basis_row(params[k], p, 0, num_ctrlpts, &knot_vec);
+            let r = &points[k] - row[0] * &points[0] - row[n] * &points[m];
+            for i in 1..n {
+                if row[i] == 0. {
+                    continue;
+                }
+                ntr[i-1] = &ntr[i-1] + weights[k] * row[i] * &r;
+                for j in 1..n {
+                    ntn[(i-1, j-1)] += weights[k] * row[i] * row[j];
+                }
+            }
+        }
+
+        let mut ctrlpts = vec![points[0].clone_owned()];
+        if n > 1 {
+            ctrlpts.extend(solve_points(ntn, &ntr)?);
+        }
+        ctrlpts.push(points[m].clone_owned());
+
+        let curve = BSplineCurve::new(p, ctrlpts, knot_vec);
+        let residuals = curve.residuals(points, &params);
+
+        return Some((curve, residuals));
+    }
+
+    /// Distance from each data point to the curve at its parameter.
+    fn residuals(&self, points: &Vec<VectorN<Scalar, D>>, params: &Vec<Scalar>) -> Vec<Scalar> {
+        points.iter().zip(params.iter())
+            .map(|(point, u)| (self.curve_point(*u) - point).norm())
+            .collect()
+    }
+}
+
+impl NurbsCurve {
+
+    /// Computes the (non-rational) curve passing through the data points.
+    /// See `BSplineCurve::interpolate`.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: data points
+    /// - `degree`: degree, smaller than the number of data points
+    /// - `method`: parameterization of the data points
+    /// - `end_ders`: optional first derivatives at the start and the end
+    pub fn interpolate(points: &Vec<Vector3f>,
+                       degree: usize,
+                       method: Parameterization,
+                       end_ders: Option<(Vector3f, Vector3f)>) -> Option<(Self, Vec<Scalar>)> {
+        let (curve, residuals) = BSplineCurve::interpolate(points, degree, method, end_ders)?;
+        return Some((Self::from_nonrational(curve), residuals));
+    }
+
+    /// Computes the (non-rational) curve with `num_ctrlpts` control points
+    /// approximating the data points in the weighted least-squares sense.
+    /// See `BSplineCurve::approximate`.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: data points
+    /// - `weights`: positive weight of each data point
+    /// - `degree`: degree
+    /// - `num_ctrlpts`: number of control points
+    /// - `method`: parameterization of the data points
+    pub fn approximate(points: &Vec<Vector3f>,
+                       weights: &Vec<Scalar>,
+                       degree: usize,
+                       num_ctrlpts: usize,
+                       method: Parameterization) -> Option<(Self, Vec<Scalar>)> {
+        let (curve, residuals) = BSplineCurve::approximate(
+            points, weights, degree, num_ctrlpts, method)?;
+        return Some((Self::from_nonrational(curve), residuals));
+    }
+
+    fn from_nonrational(curve: BSplineCurve<U3>) -> Self {
+        let weights = vec![1.; curve.size];
+        NurbsCurve::new(curve.ctrlpts, curve.knotvec, curve.degree, weights)
+    }
+}
+
+/// Computes the clamped knot vector with uniformly spaced interior knots.
+fn knotvec_uniform(degree: usize, num_ctrlpts: usize) -> Vec<Scalar> {
+    let p = degree;
+    let num_spans = num_ctrlpts - p;
+    let mut knot_vec = vec![0.; p+1];
+    for j in 1..num_spans {
+        knot_vec.push(j as Scalar / num_spans as Scalar);
+    }
+    knot_vec.extend(vec![1.; p+1]);
+    return knot_vec;
+}
+
+/// Parameterizes the points by their coordinates in the best fitting plane,
+/// scaled to `[0, 1] x [0, 1]`.
+fn plane_params(points: &Vec<Vector3f>) -> Vec<(Scalar, Scalar)> {
+    let center = points.iter().fold(Vector3f::zeros(), |acc, p| acc + p) / points.len() as Scalar;
+    let mut cov = Matrix3f::zeros();
+    for point in points {
+        let d = point - center;
+        cov += d * d.transpose();
+    }
+
+    // principal directions spanning the plane
+    let eigen = cov.symmetric_eigen();
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| eigen.eigenvalues[*b].partial_cmp(&eigen.eigenvalues[*a]).unwrap());
+    let e1 = eigen.eigenvectors.column(order[0]).into_owned();
+    let e2 = eigen.eigenvectors.column(order[1]).into_owned();
+
+    let coords: Vec<(Scalar, Scalar)> = points.iter()
+        .map(|p| ((p - center).dot(&e1), (p - center).dot(&e2)))
+        .collect();
+    let (mut a0, mut a1, mut b0, mut b1) = (INFINITY, -INFINITY, INFINITY, -INFINITY);
+    for (a, b) in &coords {
+        a0 = a0.min(*a);
+        a1 = a1.max(*a);
+        b0 = b0.min(*b);
+        b1 = b1.max(*b);
+    }
+
+    coords.iter()
+        .map(|(a, b)| ((a - a0) / (a1 - a0).max(1e-300), (b - b0) / (b1 - b0).max(1e-300)))
+        .collect()
+}
+
+impl <D: Dim + DimName> BSplineSurface<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+
+    /// Computes the surface passing through a rectangular grid of points.
+    /// Global interpolation, The NURBS Book Section 9.2.5.
+    ///
+    /// The grid is ordered like the control points, i.e. the point `(i, j)`
+    /// is at index `i * size_v + j`. Returns the surface and the distance
+    /// from each grid point to the surface at its parameters, or `None` if
+    /// the grid does not match its size or is too small for the degrees, or
+    /// an interpolation matrix is singular, e.g. when rows or columns of
+    /// points coincide.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: grid of data points
+    /// - `size_u`: number of points along u
+    /// - `size_v`: number of points along v
+    /// - `degree_u`: degree along u, smaller than `size_u`
+    /// - `degree_v`: degree along v, smaller than `size_v`
+    /// - `method`: parameterization of the data points
+    pub fn interpolate(points: &Vec<VectorN<Scalar, D>>,
+                       size_u: usize,
+                       size_v: usize,
+                       degree_u: usize,
+                       degree_v: usize,
+                       method: Parameterization) -> Option<(Self, Vec<Scalar>)> {
+        if points.len() != size_u * size_v {
+            return None;
+        }
+        if degree_u < 1 || size_u <= degree_u || degree_v < 1 || size_v <= degree_v {
+            return None;
+        }
+
+        let column = |j: usize| -> Vec<VectorN<Scalar, D>> {
+            (0..size_u).map(|i| points[i * size_v + j].clone_owned()).collect()
+        };
+        let row = |i: usize| -> Vec<VectorN<Scalar, D>> {
+            points[i * size_v..(i + 1) * size_v].to_vec()
+        };
+
+        // average the parameters of all the columns and rows
+        let mut params_u = vec![0.; size_u];
+        for j in 0..size_v {
+            for (k, u) in compute_params(&column(j), method).iter().enumerate() {
+                params_u[k] += u / size_v as Scalar;
+            }
+        }
+        let mut params_v = vec![0.; size_v];
+        for i in 0..size_u {
+            for (k, v) in compute_params(&row(i), method).iter().enumerate() {
+                params_v[k] += v / size_u as Scalar;
+            }
+        }
+
+        let knot_vec_u = knotvec_averaging(&params_u, degree_u, false);
+        let knot_vec_v = knotvec_averaging(&params_v, degree_v, false);
+        let a_u = basis_matrix(&params_u, degree_u, size_u, &knot_vec_u);
+        let a_v = basis_matrix(&params_v, degree_v, size_v, &knot_vec_v);
+
+        // interpolate the columns along u, then the rows of the result along v
+        let mut temp = vec![VectorN::<Scalar, D>::zeros(); size_u * size_v];
+        for j in 0..size_v {
+            let pts = solve_points(a_u.clone(), &column(j))?;
+            for i in 0..size_u {
+                temp[i * size_v + j] = pts[i].clone_owned();
+            }
+        }
+        let mut ctrlpts = Vec::with_capacity(size_u * size_v);
+        for i in 0..size_u {
+            let pts = temp[i * size_v..(i + 1) * size_v].to_vec();
+            ctrlpts.extend(solve_points(a_v.clone(), &pts)?);
+        }
+
+        let surf = BSplineSurface::new(
+            ctrlpts, knot_vec_u, knot_vec_v, degree_u, degree_v, size_u, size_v);
+        let mut residuals = Vec::with_capacity(points.len());
+        for i in 0..size_u {
+            for j in 0..size_v {
+                let point = surf.evaluate_single(params_u[i], params_v[j]);
+                residuals.push((point - &points[i * size_v + j]).norm());
+            }
+        }
+
+        return Some((surf, residuals));
+    }
+}
+
+impl NurbsSurface {
+
+    /// Computes the (non-rational) surface passing through a rectangular
+    /// grid of points. See `BSplineSurface::interpolate`.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: grid of data points, the point `(i, j)` at index `i * size_v + j`
+    /// - `size_u`: number of points along u
+    /// - `size_v`: number of points along v
+    /// - `degree_u`: degree along u, smaller than `size_u`
+    /// - `degree_v`: degree along v, smaller than `size_v`
+    /// - `method`: parameterization of the data points
+    pub fn interpolate(points: &Vec<Vector3f>,
+                       size_u: usize,
+                       size_v: usize,
+                       degree_u: usize,
+                       degree_v: usize,
+                       method: Parameterization) -> Option<(Self, Vec<Scalar>)> {
+        let (surf, residuals) = BSplineSurface::interpolate(
+            points, size_u, size_v, degree_u, degree_v, method)?;
+        return Some((Self::from_nonrational(surf), residuals));
+    }
+
+    /// Fits a (non-rational) surface with `size.0 x size.1` control points
+    /// to scattered points in the least-squares sense.
+    ///
+    /// The points are first parameterized by projecting them onto the base
+    /// surface, or onto their best fitting plane if none is given (base
+    /// surface parameterization, see Ma & Kruth 1995). The control points
+    /// are solved for on uniform knot vectors, with a small smoothing term
+    /// that keeps the system regular where the data is sparse. The
+    /// parameters are then improved by projecting the points onto the
+    /// fitted surface, and the surface is fitted again, `criteria.iterations`
+    /// times. Returns the surface and the distance from each point to the
+    /// surface at its final parameters, or `None` if there are fewer points
+    /// than control points, the sizes are too small for the degrees or the
+    /// least-squares system is singular.
+    ///
+    /// # Arguments
+    ///
+    /// - `points`: scattered data points
+    /// - `base`: optional base surface roughly approximating the points
+    /// - `degree`: degrees along u and v
+    /// - `size`: numbers of control points along u and v
+    /// - `criteria`: fitting parameters
+    pub fn approximate(points: &Vec<Vector3f>,
+                       base: Option<&NurbsSurface>,
+                       degree: (usize, usize),
+                       size: (usize, usize),
+                       criteria: &SurfaceFitCriteria) -> Option<(Self, Vec<Scalar>)> {
+        let (p, q) = degree;
+        let (size_u, size_v) = size;
+        if p < 1 || size_u <= p || q < 1 || size_v <= q {
+            return None;
+        }
+        if points.len() < size_u * size_v {
+            return None;
+        }
+
+        let proj_criteria = ProjectionCriteria {
+            samples: criteria.samples,
+            ..ProjectionCriteria::default()
+        };
+        let mut params = match base {
+            Some(base) => points.iter()
+                .map(|point| {
+                    let proj = base.project_point(point, &proj_criteria);
+                    (proj.u, proj.v)
+                })
+                .collect(),
+            None => plane_params(points),
+        };
+
+        let knot_vec_u = knotvec_uniform(p, size_u);
+        let knot_vec_v = knotvec_uniform(q, size_v);
+        let mut surf = Self::fit_ctrlpts(points, &params, &knot_vec_u, &knot_vec_v,
+                                         degree, size, criteria.smoothing)?;
+        for _ in 0..criteria.iterations {
+            params = points.iter().zip(params.iter())
+                .map(|(point, (u, v))| {
+                    let proj = surf.project_point_newton(point, *u, *v, &proj_criteria);
+                    (proj.u, proj.v)
+                })
+                .collect();
+            surf = Self::fit_ctrlpts(points, &params, &knot_vec_u, &knot_vec_v,
+                                     degree, size, criteria.smoothing)?;
+        }
+
+        let residuals = points.iter().zip(params.iter())
+            .map(|(point, (u, v))| (surf.evaluate_single(*u, *v) - point).norm())
+            .collect();
+
+        return Some((surf, residuals));
+    }
+
+    /// Solves the regularized least-squares problem for the control points,
+    /// given the parameters of the data points. Returns `None` if the system
+    /// is singular.
+    fn fit_ctrlpts(points: &Vec<Vector3f>,
+                   params: &Vec<(Scalar, Scalar)>,
+                   knot_vec_u: &Vec<Scalar>,
+                   knot_vec_v: &Vec<Scalar>,
+                   degree: (usize, usize),
+                   size: (usize, usize),
+                   smoothing: Scalar) -> Option<Self> {
+        let (p, q) = degree;
+        let (size_u, size_v) = size;
+        let n = size_u * size_v;
+
+        let mut ntn = DMatrix::<Scalar>::zeros(n, n);
+        let mut ntr = vec![Vector3f::zeros(); n];
+        for (point, (u, v)) in points.iter().zip(params.iter()) {
+            let span_u = find_span(size_u, p, *u, knot_vec_u);
+            let span_v = find_span(size_v, q, *v, knot_vec_v);
+            let basis_u = basis_functions(span_u, *u, p, knot_vec_u);
+            let basis_v = basis_functions(span_v, *v, q, knot_vec_v);
+
+            let mut entries = Vec::with_capacity((p + 1) * (q + 1));
+            for a in 0..=p {
+                for b in 0..=q {
+                    let index = (span_u - p + a) * size_v + span_v - q + b;
+                    entries.push((index, basis_u[a] * basis_v[b]));
+                }
+            }
+            for (i, ni) in &entries {
+                ntr[*i] += *ni * point;
+                for (j, nj) in &entries {
+                    ntn[(*i, *j)] += ni * nj;
+                }
+            }
+        }
+
+        // membrane energy of the control net
+        let lambda = smoothing * points.len() as Scalar;
+        for i in 0..size_u {
+            for j in 0..size_v {
+                let a = i * size_v + j;
+                let mut neighbours = Vec::new();
+                if i + 1 < size_u { neighbours.push(a + size_v); }
+                if j + 1 < size_v { neighbours.push(a + 1); }
+                for b in neighbours {
+                    ntn[(a, a)] += lambda;
+                    ntn[(b, b)] += lambda;
+                    ntn[(a, b)] -= lambda;
+                    ntn[(b, a)] -= lambda;
+                }
+            }
+        }
+
+        let ctrlpts = solve_points(ntn, &ntr)?;
+        Some(NurbsSurface::new(ctrlpts, knot_vec_u.clone(), knot_vec_v.clone(),
+                               p, q, size_u, size_v, vec![1.; n]))
+    }
+
+    fn from_nonrational(surf: BSplineSurface<U3>) -> Self {
+        let weights = vec![1.; surf.ctrlpts.len()];
+        NurbsSurface::new(surf.ctrlpts, surf.knotvec_u, surf.knotvec_v,
+                          surf.degree_u, surf.degree_v, surf.size_u, surf.size_v, weights)
+    }
+}
@@ -1,9 +1,10 @@
 use na::*;
 use na::allocator::Allocator;
-use crate::math::Scalar;
+use crate::math::{Scalar, INFINITY};
 use std::rc::Rc;
 use std::cell::RefCell;
 use kiss3d::resource::Mesh;
+use std::cmp::{min, max};
 
 
 /// Determine the knot span index where u lies
@@ -15,13 +16,17 @@ use kiss3d::resource::Mesh;
 /// * `u` - parameter
 /// * `knot_vec` - knot vector
 pub fn find_span(n: usize, p: usize, u: Scalar, knot_vec: &Vec<Scalar>) -> usize {
-    if (u - knot_vec[n+1]).abs() < 1e-3 {
-        return n;
+    // special cases: the parameter lies on (or beyond) either end of the domain
+    if u >= knot_vec[n] {
+        return n - 1;
+    }
+    if u <= knot_vec[p] {
+        return p;
     }
 
     // Bisection search
     let mut low: usize  = p;
-    let mut high: usize = n+1;
+    let mut high: usize = n;
     let mut mid: usize  =  (low + high) / 2;
     while u < knot_vec[mid] || u >= knot_vec[mid+1] {
         if u < knot_vec[mid] {
@@ -183,4 +188,801 @@ pub fn normalize_knotvec(knotvec: &Vec<Scalar>) -> Vec<Scalar> {
     }
 
     return knotvec_norm;
-}
\ No newline at end of file
+}
+/// Computes the binomial coefficient $\binom{n}{k}$.
+pub fn binomial(n: usize, k: usize) -> Scalar {
+    if k > n {
+        return 0.;
+    }
+    let k = min(k, n - k);
+    let mut res = 1.;
+    for i in 0..k {
+        res = res * (n - i) as Scalar / (i + 1) as Scalar;
+    }
+    return res;
+}
+
+/// Computes the non-zero basis functions and their derivatives.
+///
+/// Implementation of Algorithm A2.3 from The NURBS Book by Piegl & Tiller.
+/// Returns a 2D array `ders` where `ders[k][j]` is the k-th derivative of
+/// the function $N_{i-p+j, p}$, with $0 \leq k \leq n$ and $0 \leq j \leq p$.
+/// Derivatives of order higher than `p` are identically zero.
+///
+/// # Arguments
+///
+/// * `i` - knot span
+/// * `u` - parameter
+/// * `p` - basis function degree
+/// * `n` - order of the derivatives
+/// * `knot_vec` - knot vector
+pub fn basis_function_ders(i: usize, u: Scalar, p: usize, n: usize,
+                           knot_vec: &Vec<Scalar>) -> Vec<Vec<Scalar>> {
+    let mut left  = vec![0.; p+1];
+    let mut right = vec![0.; p+1];
+    // `ndu` stores the basis functions (upper triangle) and knot
+    // differences (lower triangle)
+    let mut ndu   = vec![vec![1.; p+1]; p+1];
+    // `a` stores the two most recently computed rows a_{k, j} and
+    // a_{k-1, j} in an alternating fashion
+    let mut a     = vec![vec![0.; p+1]; 2];
+    let mut ders  = vec![vec![0.; p+1]; n+1];
+
+    for j in 1..=p {
+        left[j]  = u - knot_vec[i+1-j];
+        right[j] = knot_vec[i+j] - u;
+        let mut saved = 0.;
+        for r in 0..j {
+            ndu[j][r] = right[r+1] + left[j-r];
+            let temp = ndu[r][j-1] / ndu[j][r];
+            ndu[r][j] = saved + right[r+1] * temp;
+            saved = left[j-r] * temp;
+        }
+        ndu[j][j] = saved;
+    }
+
+    // load the basis functions
+    for j in 0..=p {
+        ders[0][j] = ndu[j][p];
+    }
+
+    // compute the derivatives (Eq. 2.9), loop over function index
+    let nd = min(n, p);
+    for r in 0..=p {
+        let mut s1 = 0;
+        let mut s2 = 1;
+        a[0][0] = 1.;
+        for k in 1..=nd {
+            let mut d = 0.;
+            let rk = r as isize - k as isize;
+            let pk = p - k;
+            if r >= k {
+                a[s2][0] = a[s1][0] / ndu[pk+1][rk as usize];
+                d = a[s2][0] * ndu[rk as usize][pk];
+            }
+
+            let j1 = if rk >= -1 { 1 } else { (-rk) as usize };
+            let j2 = if r as isize - 1 <= pk as isize { k - 1 } else { p - r };
+            for j in j1..=j2 {
+                let rkj = (rk + j as isize) as usize;
+                a[s2][j] = (a[s1][j] - a[s1][j-1]) / ndu[pk+1][rkj];
+                d += a[s2][j] * ndu[rkj][pk];
+            }
+
+            if r <= pk {
+                a[s2][k] = -a[s1][k-1] / ndu[pk+1][r];
+                d += a[s2][k] * ndu[r][pk];
+            }
+            ders[k][r] = d;
+
+            // switch rows
+            let j = s1; s1 = s2; s2 = j;
+        }
+    }
+
+    // multiply through by the correct factors (Eq. 2.9)
+    let mut r = p as Scalar;
+    for k in 1..=nd {
+        for j in 0..=p {
+            ders[k][j] *= r;
+        }
+        r *= (p - k) as Scalar;
+    }
+
+    return ders;
+}
+
+/// Refines the knot vector of a rational/non-rational spline by inserting
+/// all the knots in `knots` at once.
+///
+/// Implementation of Algorithm A5.4 from The NURBS Book by Piegl & Tiller.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector
+/// - `ctrlpts`: control points
+/// - `degree`: degree
+/// - `knots`: knots to be inserted, in non-decreasing order
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new)`: updated knot vector and control points
+pub fn refine_knotvec<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<VectorN<Scalar, D>>,
+    degree: usize,
+    knots: &Vec<Scalar>) -> (Vec<Scalar>, Vec<VectorN<Scalar, D>>)
+where DefaultAllocator: Allocator<Scalar, D> {
+    if knots.is_empty() {
+        return (knot_vec.clone(), ctrlpts.clone());
+    }
+
+    let p = degree;
+    let n = ctrlpts.len() - 1;
+    let m = n + p + 1;
+    let r = knots.len() - 1;
+
+    let a = find_span(n + 1, p, knots[0], knot_vec);
+    let b = find_span(n + 1, p, knots[r], knot_vec) + 1;
+
+    let mut knot_vec_new = vec![0.; m + r + 2];
+    let mut ctrlpts_new = vec![VectorN::<Scalar, D>::zeros(); n + r + 2];
+
+    for j in 0..=a-p {
+        ctrlpts_new[j] = ctrlpts[j].clone_owned();
+    }
+    for j in b-1..=n {
+        ctrlpts_new[j+r+1] = ctrlpts[j].clone_owned();
+    }
+    for j in 0..=a {
+        knot_vec_new[j] = knot_vec[j];
+    }
+    for j in b+p..=m {
+        knot_vec_new[j+r+1] = knot_vec[j];
+    }
+
+    let mut i = b + p - 1;
+    let mut k = b + p + r;
+    for j in (0..=r).rev() {
+        while knots[j] <= knot_vec[i] && i > a {
+            ctrlpts_new[k-p-1] = ctrlpts[i-p-1].clone_owned();
+            knot_vec_new[k] = knot_vec[i];
+            k -= 1;
+            i -= 1;
+        }
+        ctrlpts_new[k-p-1] = ctrlpts_new[k-p].clone_owned();
+        for l in 1..=p {
+            let ind = k - p + l;
+            let alpha = knot_vec_new[k+l] - knots[j];
+            if alpha.abs() == 0. {
+                ctrlpts_new[ind-1] = ctrlpts_new[ind].clone_owned();
+            } else {
+                let alpha = alpha / (knot_vec_new[k+l] - knot_vec[i-p+l]);
+                ctrlpts_new[ind-1] = alpha * &ctrlpts_new[ind-1]
+                    + (1. - alpha) * &ctrlpts_new[ind];
+            }
+        }
+        knot_vec_new[k] = knots[j];
+        k -= 1;
+    }
+
+    return (knot_vec_new, ctrlpts_new);
+}
+
+/// Tries to remove the knot `u` from a rational/non-rational spline `num`
+/// times, as long as the control polygon moves by less than `tol`.
+///
+/// Implementation of Algorithm A5.8 from The NURBS Book by Piegl & Tiller.
+/// The tolerance is measured on the (homogeneous) control points passed in.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector
+/// - `ctrlpts`: control points
+/// - `degree`: degree
+/// - `u`: knot to be removed
+/// - `num`: maximum number of removals
+/// - `tol`: tolerance
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new, t)`: updated knot vector and control points,
+/// and the number of times the knot was actually removed
+pub fn remove_knot<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<VectorN<Scalar, D>>,
+    degree: usize,
+    u: Scalar,
+    num: usize,
+    tol: Scalar) -> (Vec<Scalar>, Vec<VectorN<Scalar, D>>, usize)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let s = find_multiplicity(knot_vec, u);
+    let p = degree as isize;
+    let n = ctrlpts.len() as isize - 1;
+    let m = n + p + 1;
+    let ord = p + 1;
+    let num = min(num, s) as isize;
+
+    // last index of the knot in the knot vector; the knot must be interior
+    let r = match knot_vec.iter().rposition(|k| (k - u).abs() < 1e-6) {
+        Some(r) => r as isize,
+        None => return (knot_vec.clone(), ctrlpts.clone(), 0),
+    };
+    let s = s as isize;
+    if r - s + 1 <= p || r >= n + 1 {
+        return (knot_vec.clone(), ctrlpts.clone(), 0);
+    }
+
+    let fout = (2 * r - s - p) / 2;
+    let mut first = r - p;
+    let mut last = r - s;
+
+    let mut pts = ctrlpts.clone();
+    let kv = knot_vec;
+    let mut temp = vec![VectorN::<Scalar, D>::zeros(); (2 * p + 1) as usize];
+
+    let mut t = 0;
+    while t < num {
+        let off = first - 1;
+        temp[0] = pts[off as usize].clone_owned();
+        temp[(last + 1 - off) as usize] = pts[(last + 1) as usize].clone_owned();
+        let mut i = first;
+        let mut j = last;
+        let mut ii = 1;
+        let mut jj = last - off;
+
+        while j - i > t {
+            let (iu, ju) = (i as usize, j as usize);
+            let alfi = (u - kv[iu]) / (kv[(i + ord + t) as usize] - kv[iu]);
+            let alfj = (u - kv[(j - t) as usize]) / (kv[(j + ord) as usize] - kv[(j - t) as usize]);
+            temp[ii as usize] = (&pts[iu] - (1. - alfi) * &temp[(ii - 1) as usize]) / alfi;
+            temp[jj as usize] = (&pts[ju] - alfj * &temp[(jj + 1) as usize]) / (1. - alfj);
+            i += 1; ii += 1;
+            j -= 1; jj -= 1;
+        }
+
+        let removable = if j - i < t {
+            (&temp[(ii - 1) as usize] - &temp[(jj + 1) as usize]).norm() <= tol
+        } else {
+            let iu = i as usize;
+            let alfi = (u - kv[iu]) / (kv[(i + ord + t) as usize] - kv[iu]);
+            let pt = alfi * &temp[(ii + t + 1) as usize] + (1. - alfi) * &temp[(ii - 1) as usize];
+            (&pts[iu] - pt).norm() <= tol
+        };
+        if !removable {
+            break;
+        }
+
+        // save new control points
+        let mut i = first;
+        let mut j = last;
+        while j - i > t {
+            pts[i as usize] = temp[(i - off) as usize].clone_owned();
+            pts[j as usize] = temp[(j - off) as usize].clone_owned();
+            i += 1;
+            j -= 1;
+        }
+
+        first -= 1;
+        last += 1;
+        t += 1;
+    }
+
+    if t == 0 {
+        return (knot_vec.clone(), ctrlpts.clone(), 0);
+    }
+
+    // shift knots
+    let mut knot_vec_new = knot_vec.clone();
+    for k in r+1..=m {
+        knot_vec_new[(k - t) as usize] = knot_vec_new[k as usize];
+    }
+    knot_vec_new.truncate((m + 1 - t) as usize);
+
+    // shift control points, `pj` is the first point to be overwritten
+    let mut pj = fout;
+    let mut i = pj;
+    for k in 1..t {
+        if k % 2 == 1 {
+            i += 1;
+        } else {
+            pj -= 1;
+        }
+    }
+    for k in i+1..=n {
+        pts[pj as usize] = pts[k as usize].clone_owned();
+        pj += 1;
+    }
+    pts.truncate((n + 1 - t) as usize);
+
+    return (knot_vec_new, pts, t as usize);
+}
+
+/// Elevates the degree of a rational/non-rational spline `t` times.
+///
+/// Implementation of Algorithm A5.9 from The NURBS Book by Piegl & Tiller.
+/// The knot vector is assumed to be clamped.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector
+/// - `ctrlpts`: control points
+/// - `degree`: degree
+/// - `t`: number of degree elevations
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new)`: updated knot vector and control points
+pub fn elevate_degree<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<VectorN<Scalar, D>>,
+    degree: usize,
+    t: usize) -> (Vec<Scalar>, Vec<VectorN<Scalar, D>>)
+where DefaultAllocator: Allocator<Scalar, D> {
+    if t == 0 {
+        return (knot_vec.clone(), ctrlpts.clone());
+    }
+
+    let zero = VectorN::<Scalar, D>::zeros();
+    let p = degree;
+    let n = ctrlpts.len() - 1;
+    let m = n + p + 1;
+    let ph = p + t;
+    let ph2 = ph / 2;
+
+    // coefficients for degree elevating the Bezier segments
+    let mut bezalfs = vec![vec![0.; p+1]; ph+1];
+    bezalfs[0][0] = 1.;
+    bezalfs[ph][p] = 1.;
+    for i in 1..=ph2 {
+        let inv = 1. / binomial(ph, i);
+        let mpi = min(p, i);
+        for j in i.saturating_sub(t)..=mpi {
+            bezalfs[i][j] = inv * binomial(p, j) * binomial(t, i - j);
+        }
+    }
+    for i in ph2+1..ph {
+        let mpi = min(p, i);
+        for j in i.saturating_sub(t)..=mpi {
+            bezalfs[i][j] = bezalfs[ph-i][p-j];
+        }
+    }
+
+    // upper bounds on the size of the new knot vector and control points
+    let max_pts = (n + 1) * (t + 1) + ph + 1;
+    let mut knot_vec_new = vec![0.; max_pts + ph + 1];
+    let mut ctrlpts_new = vec![zero.clone(); max_pts];
+
+    let mut bpts = vec![zero.clone(); p+1];
+    let mut ebpts = vec![zero.clone(); ph+1];
+    let mut next_bpts = vec![zero.clone(); max(p, 1)];
+    let mut alfs = vec![0.; max(p, 1)];
+
+    let mut mh = ph;
+    let mut kind = ph + 1;
+    let mut r: isize = -1;
+    let mut a = p;
+    let mut b = p + 1;
+    let mut cind = 1;
+    let mut ua = knot_vec[0];
+    ctrlpts_new[0] = ctrlpts[0].clone_owned();
+    for i in 0..=ph {
+        knot_vec_new[i] = ua;
+    }
+    for i in 0..=p {
+        bpts[i] = ctrlpts[i].clone_owned();
+    }
+
+    while b < m {
+        let i = b;
+        while b < m && knot_vec[b] == knot_vec[b+1] {
+            b += 1;
+        }
+        let mul = b - i + 1;
+        mh = mh + mul + t;
+        let ub = knot_vec[b];
+        let oldr = r;
+        r = p as isize - mul as isize;
+
+        // insert knot u(b) r times
+        let lbz = if oldr > 0 { ((oldr + 2) / 2) as usize } else { 1 };
+        let rbz = if r > 0 { ph - ((r + 1) / 2) as usize } else { ph };
+        if r > 0 {
+            let numer = ub - ua;
+            for k in (mul+1..=p).rev() {
+                alfs[k-mul-1] = numer / (knot_vec[a+k] - ua);
+            }
+            for j in 1..=r as usize {
+                let save = r as usize - j;
+                let s = mul + j;
+                for k in (s..=p).rev() {
+                    bpts[k] = alfs[k-s] * &bpts[k] + (1. - alfs[k-s]) * &bpts[k-1];
+                }
+                next_bpts[save] = bpts[p].clone_owned();
+            }
+        }
+
+        // degree elevate the Bezier segment
+        for i in lbz..=ph {
+            ebpts[i] = zero.clone();
+            let mpi = min(p, i);
+            for j in i.saturating_sub(t)..=mpi {
+                ebpts[i] = &ebpts[i] + bezalfs[i][j] * &bpts[j];
+            }
+        }
+
+        // remove knot u = U[a] oldr times
+        if oldr > 1 {
+            let mut first = kind - 2;
+            let mut last = kind;
+            let den = ub - ua;
+            let bet = (ub - knot_vec_new[kind-1]) / den;
+            for tr in 1..oldr as usize {
+                let mut i = first;
+                let mut j = last;
+                let mut kj = j as isize - kind as isize + 1;
+                while j - i > tr {
+                    if i < cind {
+                        let alf = (ub - knot_vec_new[i]) / (ua - knot_vec_new[i]);
+                        ctrlpts_new[i] = alf * &ctrlpts_new[i] + (1. - alf) * &ctrlpts_new[i-1];
+                    }
+                    if j >= lbz {
+                        let k = kj as usize;
+                        if j - tr <= kind - ph + oldr as usize {
+                            let gam = (ub - knot_vec_new[j-tr]) / den;
+                            ebpts[k] = gam * &ebpts[k] + (1. - gam) * &ebpts[k+1];
+                        } else {
+                            ebpts[k] = bet * &ebpts[k] + (1. - bet) * &ebpts[k+1];
+                        }
+                    }
+                    i += 1;
+                    j -= 1;
+                    kj -= 1;
+                }
+                first -= 1;
+                last += 1;
+            }
+        }
+
+        // load the knot ua
+        if a != p {
+            for _ in 0..(ph as isize - oldr) as usize {
+                knot_vec_new[kind] = ua;
+                kind += 1;
+            }
+        }
+
+        // load control points
+        for j in lbz..=rbz {
+            ctrlpts_new[cind] = ebpts[j].clone_owned();
+            cind += 1;
+        }
+
+        if b < m {
+            // set up for next pass through loop
+            let r = r.max(0) as usize;
+            for j in 0..r {
+                bpts[j] = next_bpts[j].clone_owned();
+            }
+            for j in r..=p {
+                bpts[j] = ctrlpts[b-p+j].clone_owned();
+            }
+            a = b;
+            b += 1;
+            ua = ub;
+        } else {
+            // end knot
+            for i in 0..=ph {
+                knot_vec_new[kind+i] = ub;
+            }
+        }
+    }
+
+    let nh = mh - ph - 1;
+    knot_vec_new.truncate(nh + ph + 2);
+    ctrlpts_new.truncate(nh + 1);
+
+    return (knot_vec_new, ctrlpts_new);
+}
+
+/// Decomposes a rational/non-rational spline into its Bezier segments by
+/// raising the multiplicity of every interior knot to the degree.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector, clamped
+/// - `ctrlpts`: control points
+/// - `degree`: degree
+///
+/// # Return
+///
+/// `(breaks, segments)`: distinct knots bounding the segments, and the
+/// `degree + 1` control points of each segment
+pub fn decompose_bezier<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<VectorN<Scalar, D>>,
+    degree: usize) -> (Vec<Scalar>, Vec<Vec<VectorN<Scalar, D>>>)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let p = degree;
+    let m = knot_vec.len() - 1;
+
+    let mut breaks = vec![knot_vec[p]];
+    let mut knots = Vec::new();
+    let mut i = p + 1;
+    while i < m - p {
+        let u = knot_vec[i];
+        let s = find_multiplicity(knot_vec, u);
+        for _ in s..p {
+            knots.push(u);
+        }
+        breaks.push(u);
+        i += s;
+    }
+    breaks.push(knot_vec[m - p]);
+
+    let (_, pts) = refine_knotvec(knot_vec, ctrlpts, p, &knots);
+    let segments = (0..breaks.len() - 1)
+        .map(|k| pts[k*p..=(k+1)*p].to_vec())
+        .collect();
+
+    return (breaks, segments);
+}
+
+/// Reduces the degree of a Bezier segment by one.
+///
+/// Uses the forward and backward recurrences of Eq. 5.41 - 5.46 from The
+/// NURBS Book by Piegl & Tiller, which keep the end points. The error is
+/// bounded by elevating the reduced segment back and comparing the control
+/// points.
+///
+/// # Arguments
+///
+/// - `bpts`: control points of the segment, at least three
+///
+/// # Return
+///
+/// `(rbpts, err)`: control points of the reduced segment, and an upper
+/// bound of the deviation from the input segment
+pub fn reduce_bezier_degree<D: Dim + DimName>(
+    bpts: &Vec<VectorN<Scalar, D>>) -> (Vec<VectorN<Scalar, D>>, Scalar)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let p = bpts.len() - 1;
+    assert!(p > 1, "cannot reduce the degree of a linear segment");
+    let r = (p - 1) / 2;
+    let alf = |i: usize| i as Scalar / p as Scalar;
+
+    let mut rbpts = vec![VectorN::<Scalar, D>::zeros(); p];
+    rbpts[0] = bpts[0].clone_owned();
+    rbpts[p-1] = bpts[p].clone_owned();
+
+    let last_forward = if p % 2 == 0 { r } else { r - 1 };
+    for i in 1..=last_forward {
+        rbpts[i] = (&bpts[i] - alf(i) * &rbpts[i-1]) / (1. - alf(i));
+    }
+    for i in (r+1..=p-2).rev() {
+        rbpts[i] = (&bpts[i+1] - (1. - alf(i+1)) * &rbpts[i+1]) / alf(i+1);
+    }
+    if p % 2 == 1 {
+        // average of the forward and backward recurrences
+        let left = (&bpts[r] - alf(r) * &rbpts[r-1]) / (1. - alf(r));
+        let right = (&bpts[r+1] - (1. - alf(r+1)) * &rbpts[r+1]) / alf(r+1);
+        rbpts[r] = 0.5 * (left + right);
+    }
+
+    let mut err: Scalar = 0.;
+    for i in 1..p {
+        let elevated = alf(i) * &rbpts[i-1] + (1. - alf(i)) * &rbpts[i];
+        err = err.max((&bpts[i] - elevated).norm());
+    }
+
+    return (rbpts, err);
+}
+
+/// Reduces the degree of a set of rational/non-rational splines sharing the
+/// same knot vector by one, as long as they move by less than `tol`.
+///
+/// Follows the idea of Algorithm A5.11 from The NURBS Book by Piegl &
+/// Tiller: the splines are decomposed into Bezier segments, which are
+/// degree reduced, and the interior knots are then removed as far as the
+/// accumulated error of the segments allows. The splines are handled
+/// together so that they keep a common knot vector (e.g. the rows of a
+/// surface). The tolerance is measured on the (homogeneous) control points
+/// passed in.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector, clamped
+/// - `ctrlpts`: control points of each spline
+/// - `degree`: degree, at least two
+/// - `tol`: tolerance
+///
+/// # Return
+///
+/// `Some((knot_vec_new, ctrlpts_new, err))`: updated knot vector, control
+/// points and an upper bound of the deviation, or `None` if the degree
+/// cannot be reduced within the tolerance
+pub fn reduce_degree<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<Vec<VectorN<Scalar, D>>>,
+    degree: usize,
+    tol: Scalar) -> Option<(Vec<Scalar>, Vec<Vec<VectorN<Scalar, D>>>, Scalar)>
+where DefaultAllocator: Allocator<Scalar, D> {
+    let p = degree;
+    if p < 2 {
+        return None;
+    }
+
+    // degree reduce the Bezier segments of each spline
+    let mut breaks = Vec::new();
+    let mut errs: Vec<Scalar> = Vec::new();
+    let mut pts_new = Vec::new();
+    for pts in ctrlpts {
+        let (brks, segments) = decompose_bezier(knot_vec, pts, p);
+        errs.resize(segments.len(), 0.);
+        let mut curve: Vec<VectorN<Scalar, D>> = Vec::new();
+        for (k, segment) in segments.iter().enumerate() {
+            let (rbpts, err) = reduce_bezier_degree(segment);
+            errs[k] = errs[k].max(err);
+            let skip = if k == 0 { 0 } else { 1 };
+            curve.extend(rbpts.into_iter().skip(skip));
+        }
+        breaks = brks;
+        pts_new.push(curve);
+    }
+    if errs.iter().any(|e| *e > tol) {
+        return None;
+    }
+
+    // knot vector of the reduced splines, with C0 interior knots
+    let q = p - 1;
+    let mut knot_vec_new = vec![breaks[0]; q + 1];
+    for u in &breaks[1..breaks.len()-1] {
+        knot_vec_new.extend(vec![*u; q]);
+    }
+    knot_vec_new.extend(vec![breaks[breaks.len()-1]; q + 1]);
+
+    // remove the interior knots within the remaining tolerance
+    let (knot_vec_new, pts_new, _) = remove_knots_bounded(
+        &knot_vec_new, &pts_new, q, &breaks, &mut errs, tol);
+
+    let err = errs.iter().cloned().fold(0., Scalar::max);
+    return Some((knot_vec_new, pts_new, err));
+}
+
+/// Removes the interior knots of a set of rational/non-rational splines
+/// sharing the same knot vector, as long as they move by less than `tol`.
+///
+/// The accumulated deviation is tracked for each span `[breaks[k],
+/// breaks[k+1]]` in `errs`, and a knot is only removed if the deviation of
+/// all the spans it affects stays within the tolerance.
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new, removed)`: updated knot vector and control
+/// points, and the total number of knot removals
+fn remove_knots_bounded<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<Vec<VectorN<Scalar, D>>>,
+    degree: usize,
+    breaks: &Vec<Scalar>,
+    errs: &mut Vec<Scalar>,
+    tol: Scalar) -> (Vec<Scalar>, Vec<Vec<VectorN<Scalar, D>>>, usize)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let q = degree;
+    let mut knot_vec_new = knot_vec.clone();
+    let mut pts_new = ctrlpts.clone();
+    let mut removed = 0;
+
+    for u in breaks[1..breaks.len()-1].to_vec() {
+        let r = knot_vec_new.iter().rposition(|k| *k == u).unwrap();
+        let s = find_multiplicity(&knot_vec_new, u);
+        let lo = knot_vec_new[r.saturating_sub(q + s)];
+        let hi = knot_vec_new[min(r + q + 1, knot_vec_new.len() - 1)];
+        let affected: Vec<usize> = (0..errs.len())
+            .filter(|k| breaks[*k] < hi && breaks[*k+1] > lo)
+            .collect();
+        let tol_rem = affected.iter().map(|k| tol - errs[*k]).fold(INFINITY, Scalar::min);
+        if tol_rem <= 0. {
+            continue;
+        }
+
+        let (kv, pts_rem, num) = remove_knot_common(&knot_vec_new, &pts_new, q, u, s, tol_rem);
+        if num == 0 {
+            continue;
+        }
+
+        // the deviation caused by the removal is bounded by the distance
+        // between the control points before the removal and after inserting
+        // the knot back
+        let mut err: Scalar = 0.;
+        for (pts, pts_rem) in pts_new.iter().zip(pts_rem.iter()) {
+            let (_, pts_back) = refine_knotvec(&kv, pts_rem, q, &vec![u; num]);
+            for (a, b) in pts.iter().zip(pts_back.iter()) {
+                err = err.max((a - b).norm());
+            }
+        }
+        knot_vec_new = kv;
+        pts_new = pts_rem;
+        removed += num;
+        for k in affected {
+            errs[k] += err;
+        }
+    }
+
+    return (knot_vec_new, pts_new, removed);
+}
+
+/// Removes as many interior knots as possible from a set of
+/// rational/non-rational splines sharing the same knot vector, as long as
+/// they move by less than `tol` (data reduction).
+///
+/// The deviation is accumulated over the removals, so that the resulting
+/// splines stay within `tol` of the input ones. The tolerance is measured
+/// on the (homogeneous) control points passed in.
+///
+/// # Arguments
+///
+/// - `knot_vec`: knot vector, clamped
+/// - `ctrlpts`: control points of each spline
+/// - `degree`: degree
+/// - `tol`: tolerance
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new, removed, err)`: updated knot vector and
+/// control points, the total number of knot removals and an upper bound of
+/// the deviation
+pub fn remove_knots<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<Vec<VectorN<Scalar, D>>>,
+    degree: usize,
+    tol: Scalar) -> (Vec<Scalar>, Vec<Vec<VectorN<Scalar, D>>>, usize, Scalar)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let p = degree;
+    let m = knot_vec.len() - 1;
+    let mut breaks: Vec<Scalar> = knot_vec[p..=m-p].to_vec();
+    breaks.dedup();
+    let mut errs = vec![0.; breaks.len() - 1];
+
+    let (knot_vec_new, pts_new, removed) = remove_knots_bounded(
+        knot_vec, ctrlpts, p, &breaks, &mut errs, tol);
+
+    let err = errs.iter().cloned().fold(0., Scalar::max);
+    return (knot_vec_new, pts_new, removed, err);
+}
+
+/// Tries to remove the knot `u` `num` times from a set of
+/// rational/non-rational splines sharing the same knot vector, as long as
+/// each of them moves by less than `tol`. All the splines undergo the same
+/// number of removals.
+///
+/// # Return
+///
+/// `(knot_vec_new, ctrlpts_new, t)`: updated knot vector and control points,
+/// and the number of times the knot was actually removed
+pub fn remove_knot_common<D: Dim + DimName>(
+    knot_vec: &Vec<Scalar>,
+    ctrlpts: &Vec<Vec<VectorN<Scalar, D>>>,
+    degree: usize,
+    u: Scalar,
+    num: usize,
+    tol: Scalar) -> (Vec<Scalar>, Vec<Vec<VectorN<Scalar, D>>>, usize)
+where DefaultAllocator: Allocator<Scalar, D> {
+    let t = ctrlpts.iter()
+        .map(|pts| remove_knot(knot_vec, pts, degree, u, num, tol).2)
+        .min()
+        .unwrap_or(0);
+    if t == 0 {
+        return (knot_vec.clone(), ctrlpts.clone(), 0);
+    }
+
+    let mut knot_vec_new = knot_vec.clone();
+    let mut pts_new = Vec::new();
+    for pts in ctrlpts {
+        let (kv, pts_rem, _) = remove_knot(knot_vec, pts, degree, u, t, tol);
+        knot_vec_new = kv;
+        pts_new.push(pts_rem);
+    }
+
+    return (knot_vec_new, pts_new, t);
+}
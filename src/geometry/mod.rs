@@ -4,6 +4,12 @@ pub mod dcel;
 pub mod list;
 pub mod volint;
 pub mod nurbs;
+pub mod projection;
+pub mod tessellation;
+pub mod fitting;
+pub mod construction;
+pub mod primitives;
+pub mod trimmed;
 mod helper;
 
 pub use self::bezier::*;
@@ -12,3 +18,9 @@ pub use self::dcel::*;
 pub use self::list::*;
 pub use self::volint::*;
 pub use self::nurbs::*;
+pub use self::projection::*;
+pub use self::tessellation::*;
+pub use self::fitting::*;
+pub use self::construction::*;
+pub use self::primitives::*;
+pub use self::trimmed::*;
@@ -1,9 +1,9 @@
 use crate::math::*;
-use na::{Point3};
+use na::{Point3, Vector3};
 use std::cell::RefCell;
 use std::rc::Rc;
 use kiss3d::resource::Mesh;
-use crate::geometry::{BSplineSurface, homo2cart};
+use crate::geometry::{BSplineCurve, BSplineSurface, homo2cart};
 use crate::geometry::helper;
 use crate::robotics::{tform2rotm, tform2tvec};
 use crate::simulation::sim_model::SimScene;
@@ -87,8 +87,111 @@ impl NurbsSurface {
         self.sample_size = size_v * size_u;
     }
 
+    /// Evaluates the surface at the input (u, v) parameter pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - the first parameter
+    /// * `v` - the second parameter
+    pub fn evaluate_single(&self, u: Scalar, v: Scalar) -> Vector3f {
+        let point = self.bspline.evaluate_single(u, v);
+        return Vector3f::new(point[0], point[1], point[2]) / point[3];
+    }
+
+    /// Computes the rational surface partial derivatives up to order `d` at
+    /// the input (u, v) parameter pair.
+    /// Algorithm A4.4: RatSurfaceDerivs
+    ///
+    /// Returns a 2D array `skl` where `skl[k][l]` is the derivative of the
+    /// surface `k` times with respect to u and `l` times with respect to v,
+    /// for $0 \leq k + l \leq d$. The remaining entries are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - the first parameter
+    /// * `v` - the second parameter
+    /// * `d` - order of the derivatives
+    pub fn derivatives(&self, u: Scalar, v: Scalar, d: usize) -> Vec<Vec<Vector3f>> {
+        let ders_w = self.bspline.derivatives(u, v, d);
+        let mut skl = vec![vec![Vector3f::zeros(); d+1]; d+1];
+
+        for k in 0..=d {
+            for l in 0..=d-k {
+                let a = &ders_w[k][l];
+                let mut val = Vector3f::new(a[0], a[1], a[2]);
+                for j in 1..=l {
+                    val -= helper::binomial(l, j) * ders_w[0][j][3] * skl[k][l-j];
+                }
+                for i in 1..=k {
+                    val -= helper::binomial(k, i) * ders_w[i][0][3] * skl[k-i][l];
+                    let mut val2 = Vector3f::zeros();
+                    for j in 1..=l {
+                        val2 += helper::binomial(l, j) * ders_w[i][j][3] * skl[k-i][l-j];
+                    }
+                    val -= helper::binomial(k, i) * val2;
+                }
+                skl[k][l] = val / ders_w[0][0][3];
+            }
+        }
+
+        return skl;
+    }
+
+    /// Parameter domain `((u_min, u_max), (v_min, v_max))` of the surface.
+    pub fn domain(&self) -> ((Scalar, Scalar), (Scalar, Scalar)) {
+        let nu = self.knotvec_u.len();
+        let nv = self.knotvec_v.len();
+        return ((self.knotvec_u[self.degree_u], self.knotvec_u[nu - self.degree_u - 1]),
+                (self.knotvec_v[self.degree_v], self.knotvec_v[nv - self.degree_v - 1]));
+    }
+
+    /// Computes the unit normal vector $S_u \times S_v / |S_u \times S_v|$
+    /// at the input (u, v) parameter pair.
+    ///
+    /// At degenerate points (e.g. the poles of a sphere) the normal is taken
+    /// from a nearby parameter pair towards the middle of the domain. If the
+    /// surface is degenerate there as well, the zero vector is returned.
+    pub fn normal(&self, u: Scalar, v: Scalar) -> Vector3f {
+        let ders = self.derivatives(u, v, 1);
+        if let Some(normal) = ders[1][0].cross(&ders[0][1]).try_normalize(1e-12) {
+            return normal;
+        }
+
+        let ((u_min, u_max), (v_min, v_max)) = self.domain();
+        let delta = 1e-6;
+        let u = u + (0.5 * (u_min + u_max) - u).signum() * delta * (u_max - u_min);
+        let v = v + (0.5 * (v_min + v_max) - v).signum() * delta * (v_max - v_min);
+        let ders = self.derivatives(u, v, 1);
+        return ders[1][0].cross(&ders[0][1]).try_normalize(1e-12).unwrap_or(Vector3f::zeros());
+    }
+
+    /// Computes the tangent plane at the input (u, v) parameter pair, returned
+    /// as `(point, normal)` where `point` lies on the surface and `normal` is
+    /// the unit surface normal.
+    pub fn tangent_plane(&self, u: Scalar, v: Scalar) -> (Vector3f, Vector3f) {
+        (self.evaluate_single(u, v), self.normal(u, v))
+    }
+
+    /// Computes the coefficients `(E, F, G)` of the first fundamental form,
+    /// where $E = S_u \cdot S_u$, $F = S_u \cdot S_v$ and $G = S_v \cdot S_v$.
+    pub fn first_fundamental_form(&self, u: Scalar, v: Scalar) -> (Scalar, Scalar, Scalar) {
+        let ders = self.derivatives(u, v, 1);
+        let su = &ders[1][0];
+        let sv = &ders[0][1];
+        (su.dot(su), su.dot(sv), sv.dot(sv))
+    }
+
+    /// Computes the coefficients `(L, M, N)` of the second fundamental form,
+    /// where $L = S_{uu} \cdot n$, $M = S_{uv} \cdot n$ and $N = S_{vv} \cdot n$.
+    pub fn second_fundamental_form(&self, u: Scalar, v: Scalar) -> (Scalar, Scalar, Scalar) {
+        let ders = self.derivatives(u, v, 2);
+        let normal = self.normal(u, v);
+        (ders[2][0].dot(&normal), ders[1][1].dot(&normal), ders[0][2].dot(&normal))
+    }
+
     pub fn get_mesh(&self) -> Rc<RefCell<Mesh>> {
         let mut vertices = Vec::new();
+        let mut normals = Vec::new();
         let mut indices = Vec::new();
         let step_u = self.sample_size_u;
         let step_v = self.sample_size_v;
@@ -97,12 +200,10 @@ impl NurbsSurface {
             for j in 0u16..step_v {
                 let u = i as Scalar / step_u as Scalar;
                 let v = j as Scalar / step_v as Scalar;
-                let coord = self.bspline.evaluate_single(u, v);
-                let w = coord[3] as f32;
-                let x = coord[0] as f32 / w;
-                let y = coord[1] as f32 / w;
-                let z = coord[2] as f32 / w;
-                vertices.push(Point3::new(x, y, z));
+                let coord = self.evaluate_single(u, v);
+                let normal = self.normal(u, v);
+                vertices.push(Point3::new(coord[0] as f32, coord[1] as f32, coord[2] as f32));
+                normals.push(Vector3::new(normal[0] as f32, normal[1] as f32, normal[2] as f32));
 
                 if i > 0 && j > 0 {
                     let idx_1 = i * step_v + j;
@@ -111,7 +212,7 @@ impl NurbsSurface {
                     indices.push(Point3::new(idx_1, idx_2, idx_3))
                 }
 
-                if i < 99 && j < 99 {
+                if i + 1 < step_u && j + 1 < step_v {
                     let idx_1 = i * step_v + j;
                     let idx_2 = (i + 1) * step_v + j;
                     let idx_3 = i * step_v + (j + 1);
@@ -121,7 +222,7 @@ impl NurbsSurface {
         }
 
         let mesh = Rc::new(RefCell::new(Mesh::new(
-            vertices, indices, None, None, false,
+            vertices, indices, Some(normals), None, false,
         )));
 
         return mesh;
@@ -301,6 +402,100 @@ impl NurbsSurface {
         return ret_val
     }
 
+    /// Inserts the knot `uv.0` `num.0` times in the u-direction and the knot
+    /// `uv.1` `num.1` times in the v-direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `uv`: knots in the u- and v-directions, if any
+    /// - `num`: number of knot insertions in each direction
+    pub fn insert_knot(&mut self, uv: (Option<Scalar>, Option<Scalar>), num: (usize, usize)) {
+        self.bspline.insert_knot(uv, num);
+        self.update_from_bspline();
+    }
+
+    /// Updates the cartesian control points, weights, knot vectors and
+    /// degrees after the underlying b-spline has been modified.
+    pub(crate) fn update_from_bspline(&mut self) {
+        self.degree_u = self.bspline.degree_u;
+        self.degree_v = self.bspline.degree_v;
+        self.order_u = self.degree_u + 1;
+        self.order_v = self.degree_v + 1;
+        self.knotvec_u = self.bspline.knotvec_u.clone();
+        self.knotvec_v = self.bspline.knotvec_v.clone();
+        self.ctrlpts_size_u = self.bspline.size_u;
+        self.ctrlpts_size_v = self.bspline.size_v;
+        self.ctrlpts_w = self.bspline.ctrlpts.clone();
+        self.ctrlpts.clear();
+        self.weights.clear();
+        for point in &self.bspline.ctrlpts {
+            self.ctrlpts.push(Vector3f::new(
+                point[0] / point[3], point[1] / point[3], point[2] / point[3]));
+            self.weights.push(point[3]);
+        }
+    }
+
+    /// Removes the knot `uv.0` up to `num.0` times in the u-direction and
+    /// the knot `uv.1` up to `num.1` times in the v-direction, as long as the
+    /// surface moves by less than `tol` in each direction. Returns the number
+    /// of actual removals in each direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `uv`: knots to be removed
+    /// - `num`: maximum number of knot removals
+    /// - `tol`: tolerance
+    pub fn remove_knot(&mut self, uv: (Option<Scalar>, Option<Scalar>),
+                       num: (usize, usize), tol: Scalar) -> (usize, usize) {
+        let removed = self.bspline.remove_knot(uv, num, weighted_tolerance(&self.ctrlpts, &self.weights, tol));
+        self.update_from_bspline();
+        return removed;
+    }
+
+    /// Removes as many interior knots as possible in both directions, as
+    /// long as the surface moves by less than `tol` in total. Returns the
+    /// number of removals in each direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn remove_knots(&mut self, tol: Scalar) -> (usize, usize) {
+        let removed = self.bspline.remove_knots(weighted_tolerance(&self.ctrlpts, &self.weights, tol));
+        self.update_from_bspline();
+        return removed;
+    }
+
+    /// Elevates the degree of the surface `t.0` times in the u-direction
+    /// and `t.1` times in the v-direction.
+    ///
+    /// # Arguments
+    ///
+    /// - `t`: number of degree elevations in each direction
+    pub fn elevate_degree(&mut self, t: (usize, usize)) {
+        self.bspline.elevate_degree(t);
+        self.update_from_bspline();
+    }
+
+    /// Reduces the degree of the surface by one in the u-direction (if
+    /// `uv.0`) and/or the v-direction (if `uv.1`), as long as it moves by
+    /// less than `tol`. Returns whether the degree was reduced; the surface
+    /// is left untouched otherwise.
+    ///
+    /// The geometric tolerance is converted to a tolerance on the weighted
+    /// control points (Eq. 5.30 in The NURBS Book).
+    ///
+    /// # Arguments
+    ///
+    /// - `uv`: directions to be reduced
+    /// - `tol`: tolerance
+    pub fn reduce_degree(&mut self, uv: (bool, bool), tol: Scalar) -> bool {
+        if !self.bspline.reduce_degree(uv, weighted_tolerance(&self.ctrlpts, &self.weights, tol)) {
+            return false;
+        }
+        self.update_from_bspline();
+        return true;
+    }
+
     pub fn transform(&mut self, trans: &Matrix4f) {
         let rotm = tform2rotm(trans.clone_owned());
         let tvec = tform2tvec(trans.clone_owned());
@@ -312,4 +507,217 @@ impl NurbsSurface {
             self.bspline.ctrlpts[i] = trans * self.bspline.ctrlpts[i];
         }
     }
-}
\ No newline at end of file
+}
+
+/// Non-uniform rational b-spline curve in 3D, stored as a 4-dimensional
+/// b-spline curve with weighted control points. The cartesian control points,
+/// weights and knot vector are kept in sync with the underlying b-spline.
+#[derive(Debug, Clone)]
+pub struct NurbsCurve {
+    pub degree:         usize,
+    pub knotvec:        Vec<Scalar>,
+    pub ctrlpts:        Vec<Vector3f>,      // control points in (x, y, z) format
+    pub weights:        Vec<Scalar>,        // control point weights
+    pub bspline:        BSplineCurve<U4>,   // 4-dimensional b-spline curve
+}
+
+impl NurbsCurve {
+
+    /// Create a NURBS curve
+    ///
+    /// # Arguments
+    ///
+    /// * `control_points` - control points
+    /// * `knot_vector` - knot vector, normalized to [0, 1]
+    /// * `degree` - degree
+    /// * `weight` - control point weights
+    pub fn new(control_points: Vec<Vector3f>,
+               knot_vector: Vec<Scalar>,
+               degree: usize,
+               weight: Vec<Scalar>) -> Self {
+
+        assert_eq!(control_points.len(), weight.len());
+        assert_eq!(control_points.len() + degree + 1, knot_vector.len());
+
+        let mut ctrlpts_w = Vec::<Vector4f>::new();
+        for (point, w) in control_points.iter().zip(weight.clone()) {
+            ctrlpts_w.push(Vector4f::new(point[0] * w, point[1] * w, point[2] * w, w))
+        }
+
+        let knot_vector = helper::normalize_knotvec(&knot_vector);
+
+        NurbsCurve {
+            degree,
+            knotvec: knot_vector.clone(),
+            ctrlpts: control_points,
+            weights: weight,
+            bspline: BSplineCurve::<U4>::new(degree, ctrlpts_w, knot_vector),
+        }
+    }
+
+    /// Create a NURBS curve from a 4-dimensional b-spline curve with
+    /// weighted control points in (x*w, y*w, z*w, w) format.
+    pub fn from_bspline(bspline: BSplineCurve<U4>) -> Self {
+        let mut curve = NurbsCurve {
+            degree: bspline.degree,
+            knotvec: vec![],
+            ctrlpts: vec![],
+            weights: vec![],
+            bspline,
+        };
+        curve.update_from_bspline();
+        return curve;
+    }
+
+    /// Number of control points
+    pub fn size(&self) -> usize {
+        self.bspline.size
+    }
+
+    /// Updates the cartesian control points, weights and knot vector after
+    /// the underlying b-spline has been modified.
+    fn update_from_bspline(&mut self) {
+        self.degree = self.bspline.degree;
+        self.knotvec = self.bspline.knotvec.clone();
+        self.ctrlpts.clear();
+        self.weights.clear();
+        for point in &self.bspline.ctrlpts {
+            self.ctrlpts.push(Vector3f::new(
+                point[0] / point[3], point[1] / point[3], point[2] / point[3]));
+            self.weights.push(point[3]);
+        }
+    }
+
+    /// Compute curve point
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - parameter
+    pub fn curve_point(&self, u: Scalar) -> Vector3f {
+        let point = self.bspline.curve_point(u);
+        return Vector3f::new(point[0], point[1], point[2]) / point[3];
+    }
+
+    /// Computes the curve derivatives up to order `d` at parameter `u`.
+    /// Algorithm A4.2: RatCurveDerivs
+    ///
+    /// Returns a vector `ders` where `ders[k]` is the k-th derivative,
+    /// `ders[0]` being the curve point itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - parameter
+    /// * `d` - order of the derivatives
+    pub fn derivatives(&self, u: Scalar, d: usize) -> Vec<Vector3f> {
+        let ders_w = self.bspline.derivatives(u, d);
+        let mut ders: Vec<Vector3f> = Vec::with_capacity(d+1);
+        for k in 0..=d {
+            let mut v = Vector3f::new(ders_w[k][0], ders_w[k][1], ders_w[k][2]);
+            for i in 1..=k {
+                v -= helper::binomial(k, i) * ders_w[i][3] * ders[k-i];
+            }
+            ders.push(v / ders_w[0][3]);
+        }
+        return ders;
+    }
+
+    /// Parameter domain `[u_min, u_max]` of the curve.
+    pub fn domain(&self) -> (Scalar, Scalar) {
+        let n = self.knotvec.len();
+        return (self.knotvec[self.degree], self.knotvec[n - self.degree - 1]);
+    }
+
+    /// Compute `m + 1` points on the curve, evenly spaced over its domain.
+    pub fn get_curve(&self, m: usize) -> Vec<Vector3f> {
+        let (u_min, u_max) = self.domain();
+        let mut curve = Vec::new();
+        for i in 0..=m {
+            let u = u_min + (u_max - u_min) * i as Scalar / m as Scalar;
+            curve.push(self.curve_point(u));
+        }
+        return curve;
+    }
+
+    /// Inserts knot `u` `r` times.
+    ///
+    /// # Arguments
+    ///
+    /// - `u`: knot
+    /// - `r`: number of knot insertions
+    pub fn insert_knot(&mut self, u: Scalar, r: usize) {
+        self.bspline.insert_knot(u, r);
+        self.update_from_bspline();
+    }
+
+    /// Inserts all the given knots at once.
+    ///
+    /// # Arguments
+    ///
+    /// - `knots`: knots to be inserted, in non-decreasing order
+    pub fn refine_knotvec(&mut self, knots: &Vec<Scalar>) {
+        self.bspline.refine_knotvec(knots);
+        self.update_from_bspline();
+    }
+
+    /// Removes the knot `u` up to `num` times, as long as the curve moves by
+    /// less than `tol`. Returns the number of actual removals.
+    ///
+    /// The geometric tolerance is converted to a tolerance on the weighted
+    /// control points (Eq. 5.30 in The NURBS Book).
+    ///
+    /// # Arguments
+    ///
+    /// - `u`: knot
+    /// - `num`: maximum number of knot removals
+    /// - `tol`: tolerance
+    pub fn remove_knot(&mut self, u: Scalar, num: usize, tol: Scalar) -> usize {
+        let t = self.bspline.remove_knot(u, num, weighted_tolerance(&self.ctrlpts, &self.weights, tol));
+        self.update_from_bspline();
+        return t;
+    }
+
+    /// Removes as many interior knots as possible, as long as the curve
+    /// moves by less than `tol` in total. Returns the number of removals.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn remove_knots(&mut self, tol: Scalar) -> usize {
+        let removed = self.bspline.remove_knots(weighted_tolerance(&self.ctrlpts, &self.weights, tol));
+        self.update_from_bspline();
+        return removed;
+    }
+
+    /// Elevates the degree of the curve `t` times.
+    ///
+    /// # Arguments
+    ///
+    /// - `t`: number of degree elevations
+    pub fn elevate_degree(&mut self, t: usize) {
+        self.bspline.elevate_degree(t);
+        self.update_from_bspline();
+    }
+
+    /// Reduces the degree of the curve by one, as long as it moves by less
+    /// than `tol`. Returns whether the degree was reduced; the curve is left
+    /// untouched otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// - `tol`: tolerance
+    pub fn reduce_degree(&mut self, tol: Scalar) -> bool {
+        if !self.bspline.reduce_degree(weighted_tolerance(&self.ctrlpts, &self.weights, tol)) {
+            return false;
+        }
+        self.update_from_bspline();
+        return true;
+    }
+}
+
+/// Converts a geometric tolerance to a tolerance on the weighted control
+/// points (Eq. 5.30 in The NURBS Book).
+fn weighted_tolerance(ctrlpts: &[Vector3f], weights: &[Scalar], tol: Scalar) -> Scalar {
+    let w_min = weights.iter().cloned().fold(INFINITY, Scalar::min);
+    let p_max = ctrlpts.iter().map(|p| p.norm()).fold(0., Scalar::max);
+    return tol * w_min / (1. + p_max);
+}
@@ -0,0 +1,213 @@
+use crate::math::*;
+use crate::geometry::{NurbsCurve, NurbsSurface};
+use std::f64::consts::PI;
+
+/// Returns a unit vector perpendicular to the given axis.
+fn perpendicular(axis: &Vector3f) -> Vector3f {
+    let axis = axis.normalize();
+    let other = if axis[0].abs() < 0.9 {
+        Vector3f::new(1., 0., 0.)
+    } else {
+        Vector3f::new(0., 1., 0.)
+    };
+    return axis.cross(&other).normalize();
+}
+
+impl NurbsCurve {
+
+    /// Creates an elliptical arc as an exact rational quadratic curve.
+    /// Algorithm A7.1: MakeNurbsCircle
+    ///
+    /// The arc is $C(t) = O + r_x \cos t X + r_y \sin t Y$ for `t` from
+    /// `start` to `end`, split into segments of at most 90 degrees.
+    ///
+    /// # Arguments
+    ///
+    /// - `center`: center of the ellipse
+    /// - `x_axis`: direction of the first semi-axis
+    /// - `y_axis`: direction of the second semi-axis, orthogonal to `x_axis`
+    /// - `radius_x`: length of the first semi-axis
+    /// - `radius_y`: length of the second semi-axis
+    /// - `start`: start angle in radians
+    /// - `end`: end angle in radians, with `0 < end - start <= 2 pi`
+    pub fn ellipse_arc(center: &Vector3f, x_axis: &Vector3f, y_axis: &Vector3f,
+                       radius_x: Scalar, radius_y: Scalar,
+                       start: Scalar, end: Scalar) -> Self {
+        let angle = end - start;
+        assert!(angle > 0. && angle <= 2. * PI + 1e-12, "invalid arc angle: {}", angle);
+
+        let narcs = if angle <= PI / 2. + 1e-12 {
+            1
+        } else if angle <= PI + 1e-12 {
+            2
+        } else if angle <= 1.5 * PI + 1e-12 {
+            3
+        } else {
+            4
+        };
+        let dtheta = angle / narcs as Scalar;
+        let w1 = (dtheta / 2.).cos();
+        let x = x_axis.normalize() * radius_x;
+        let y = y_axis.normalize() * radius_y;
+
+        // the ellipse is the affine image of the unit circle, so are the
+        // control points of its arcs
+        let point = |theta: Scalar| center + theta.cos() * x + theta.sin() * y;
+        let mut ctrlpts = vec![point(start)];
+        let mut weights = vec![1.];
+        let mut theta = start;
+        for _ in 0..narcs {
+            let mid = theta + dtheta / 2.;
+            ctrlpts.push(center + (mid.cos() * x + mid.sin() * y) / w1);
+            weights.push(w1);
+            theta += dtheta;
+            ctrlpts.push(point(theta));
+            weights.push(1.);
+        }
+
+        let mut knot_vec = vec![0.; 3];
+        for i in 1..narcs {
+            let u = i as Scalar / narcs as Scalar;
+            knot_vec.push(u);
+            knot_vec.push(u);
+        }
+        knot_vec.extend(vec![1.; 3]);
+
+        return NurbsCurve::new(ctrlpts, knot_vec, 2, weights);
+    }
+
+    /// Creates a circular arc as an exact rational quadratic curve.
+    ///
+    /// # Arguments
+    ///
+    /// - `center`: center of the circle
+    /// - `x_axis`: direction of the angle origin
+    /// - `y_axis`: direction of the angle 90 degrees, orthogonal to `x_axis`
+    /// - `radius`: radius of the circle
+    /// - `start`: start angle in radians
+    /// - `end`: end angle in radians, with `0 < end - start <= 2 pi`
+    pub fn arc(center: &Vector3f, x_axis: &Vector3f, y_axis: &Vector3f,
+               radius: Scalar, start: Scalar, end: Scalar) -> Self {
+        return Self::ellipse_arc(center, x_axis, y_axis, radius, radius, start, end);
+    }
+
+    /// Creates a full circle in the plane spanned by `x_axis` and `y_axis`,
+    /// starting at `center + radius * x_axis`.
+    pub fn circle(center: &Vector3f, x_axis: &Vector3f, y_axis: &Vector3f, radius: Scalar) -> Self {
+        return Self::arc(center, x_axis, y_axis, radius, 0., 2. * PI);
+    }
+
+    /// Creates a full ellipse with the semi-axes `radius_x * x_axis` and
+    /// `radius_y * y_axis`.
+    pub fn ellipse(center: &Vector3f, x_axis: &Vector3f, y_axis: &Vector3f,
+                   radius_x: Scalar, radius_y: Scalar) -> Self {
+        return Self::ellipse_arc(center, x_axis, y_axis, radius_x, radius_y, 0., 2. * PI);
+    }
+}
+
+impl NurbsSurface {
+
+    /// Creates a bilinear planar patch, the rectangle of corners
+    /// `origin`, `origin + u_side`, `origin + v_side` and
+    /// `origin + u_side + v_side`. Its normal is along `u_side x v_side`.
+    pub fn rectangle(origin: &Vector3f, u_side: &Vector3f, v_side: &Vector3f) -> Self {
+        let ctrlpts = vec![
+            *origin,
+            origin + v_side,
+            origin + u_side,
+            origin + u_side + v_side,
+        ];
+        NurbsSurface::new(
+            ctrlpts,
+            vec![0., 0., 1., 1.],
+            vec![0., 0., 1., 1.],
+            1, 1, 2, 2,
+            vec![1.; 4],
+        )
+    }
+
+    /// Creates a flat disk, with the normal of the surface along `normal`.
+    /// The v-direction runs from the center to the rim.
+    pub fn disk(center: &Vector3f, normal: &Vector3f, radius: Scalar) -> Self {
+        let x = perpendicular(normal);
+        let line = NurbsCurve::new(vec![*center, center + radius * x],
+                                   vec![0., 0., 1., 1.], 1, vec![1., 1.]);
+        return Self::revolve(&line, center, &-normal, 2. * PI);
+    }
+
+    /// Creates the lateral surface of a cylinder, without caps. The
+    /// v-direction runs along the axis and the normal points outwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `base`: center of the base circle
+    /// - `axis`: direction of the axis
+    /// - `radius`: radius of the cylinder
+    /// - `height`: height of the cylinder along the axis
+    pub fn cylinder(base: &Vector3f, axis: &Vector3f, radius: Scalar, height: Scalar) -> Self {
+        return Self::cone(base, axis, radius, radius, height);
+    }
+
+    /// Creates the lateral surface of a truncated cone, without caps. A
+    /// zero radius gives a cone with its apex on the axis. The v-direction
+    /// runs along the axis and the normal points outwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `base`: center of the base circle
+    /// - `axis`: direction of the axis
+    /// - `radius_base`: radius of the base circle
+    /// - `radius_top`: radius of the top circle
+    /// - `height`: height of the cone along the axis
+    pub fn cone(base: &Vector3f, axis: &Vector3f,
+                radius_base: Scalar, radius_top: Scalar, height: Scalar) -> Self {
+        let axis = axis.normalize();
+        let x = perpendicular(&axis);
+        let line = NurbsCurve::new(
+            vec![base + radius_base * x, base + height * axis + radius_top * x],
+            vec![0., 0., 1., 1.], 1, vec![1., 1.]);
+        return Self::revolve(&line, base, &axis, 2. * PI);
+    }
+
+    /// Creates a sphere, revolving a half circle about the axis. The
+    /// v-direction runs from the pole at `-axis` to the pole at `axis`, and
+    /// the normal points outwards.
+    pub fn sphere(center: &Vector3f, axis: &Vector3f, radius: Scalar) -> Self {
+        return Self::spherical_zone(center, axis, radius, -PI / 2., PI / 2.);
+    }
+
+    /// Creates the part of a sphere between two latitudes, e.g. a
+    /// hemisphere between `0` and `pi / 2`.
+    ///
+    /// # Arguments
+    ///
+    /// - `center`: center of the sphere
+    /// - `axis`: direction of the north pole
+    /// - `radius`: radius of the sphere
+    /// - `start`: start latitude in radians, in `[-pi / 2, pi / 2)`
+    /// - `end`: end latitude in radians, in `(start, pi / 2]`
+    pub fn spherical_zone(center: &Vector3f, axis: &Vector3f, radius: Scalar,
+                          start: Scalar, end: Scalar) -> Self {
+        let axis = axis.normalize();
+        let x = perpendicular(&axis);
+        let arc = NurbsCurve::arc(center, &x, &axis, radius, start, end);
+        return Self::revolve(&arc, center, &axis, 2. * PI);
+    }
+
+    /// Creates a torus. The u-direction runs about the axis, the v-direction
+    /// about the tube, and the normal points outwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `center`: center of the torus
+    /// - `axis`: direction of the axis of revolution
+    /// - `major_radius`: distance from the center to the center of the tube
+    /// - `minor_radius`: radius of the tube
+    pub fn torus(center: &Vector3f, axis: &Vector3f,
+                 major_radius: Scalar, minor_radius: Scalar) -> Self {
+        let axis = axis.normalize();
+        let x = perpendicular(&axis);
+        let circle = NurbsCurve::circle(&(center + major_radius * x), &x, &axis, minor_radius);
+        return Self::revolve(&circle, center, &axis, 2. * PI);
+    }
+}
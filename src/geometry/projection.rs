@@ -0,0 +1,288 @@
+use crate::math::*;
+use crate::geometry::{NurbsCurve, NurbsSurface};
+use na::{Matrix2, Vector2};
+
+/// Stopping criteria for point inversion and closest-point projection.
+pub struct ProjectionCriteria {
+    pub max_iterations:   usize,
+    pub samples:          usize,   // number of seed samples per parametric direction
+    pub point_tolerance:  Scalar,  // point coincidence tolerance
+    pub cosine_tolerance: Scalar,  // zero cosine tolerance
+}
+
+impl ProjectionCriteria {
+
+    pub fn default() -> Self {
+        ProjectionCriteria {
+            max_iterations:   50,
+            samples:          50,
+            point_tolerance:  1e-9,
+            cosine_tolerance: 1e-9,
+        }
+    }
+}
+
+/// Result of projecting a point onto a curve.
+#[derive(Debug, Clone)]
+pub struct CurveProjection {
+    pub u:        Scalar,     // parameter of the foot point
+    pub point:    Vector3f,   // foot point on the curve
+    pub distance: Scalar,     // distance between the point and the foot point
+}
+
+/// Result of projecting a point onto a surface.
+#[derive(Debug, Clone)]
+pub struct SurfaceProjection {
+    pub u:        Scalar,     // first parameter of the foot point
+    pub v:        Scalar,     // second parameter of the foot point
+    pub point:    Vector3f,   // foot point on the surface
+    pub distance: Scalar,     // distance between the point and the foot point
+}
+
+/// Brings the parameter back into `[a, b]`, either by clamping (open
+/// domains) or by wrapping around (closed domains).
+fn clamp_param(u: Scalar, a: Scalar, b: Scalar, closed: bool) -> Scalar {
+    if closed {
+        let range = b - a;
+        let mut u = (u - a) % range;
+        if u < 0. {
+            u += range;
+        }
+        a + u
+    } else {
+        u.max(a).min(b)
+    }
+}
+
+/// Returns the indices of the `count` smallest values, skipping non-finite ones.
+fn best_seeds(dists: &Vec<Scalar>, count: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..dists.len()).filter(|i| dists[*i].is_finite()).collect();
+    indices.sort_by(|a, b| dists[*a].total_cmp(&dists[*b]));
+    indices.truncate(count);
+    return indices;
+}
+
+const NUM_SEEDS: usize = 3;
+
+impl NurbsCurve {
+
+    /// Whether the curve is closed, i.e. its end points coincide.
+    pub fn is_closed(&self) -> bool {
+        let first = &self.ctrlpts[0];
+        let last = &self.ctrlpts[self.ctrlpts.len() - 1];
+        return (first - last).norm() < 1e-9;
+    }
+
+    /// Finds the parameter of the point on the curve closest to `point`.
+    ///
+    /// The curve is sampled uniformly to find the seed parameters, which are
+    /// then refined by Newton iteration (The NURBS Book, Section 6.1). The
+    /// parameter is clamped to the domain for open curves and wrapped around
+    /// for closed ones.
+    ///
+    /// # Arguments
+    ///
+    /// - `point`: point to be projected
+    /// - `criteria`: stopping criteria
+    pub fn project_point(&self, point: &Vector3f,
+                         criteria: &ProjectionCriteria) -> CurveProjection {
+        let a = self.knotvec[0];
+        let b = self.knotvec[self.knotvec.len() - 1];
+        let closed = self.is_closed();
+
+        // coarse seeding
+        let n = criteria.samples.max(2);
+        let params: Vec<Scalar> = (0..=n)
+            .map(|i| a + (b - a) * i as Scalar / n as Scalar)
+            .collect();
+        let dists: Vec<Scalar> = params.iter()
+            .map(|u| (self.curve_point(*u) - point).norm())
+            .collect();
+
+        let mut best = CurveProjection {
+            u: 0.,
+            point: Vector3f::zeros(),
+            distance: INFINITY,
+        };
+        for i in best_seeds(&dists, NUM_SEEDS) {
+            let res = self.project_point_newton(point, params[i], a, b, closed, criteria);
+            if res.distance < best.distance {
+                best = res;
+            }
+        }
+
+        return best;
+    }
+
+    fn project_point_newton(&self, point: &Vector3f, u0: Scalar,
+                            a: Scalar, b: Scalar, closed: bool,
+                            criteria: &ProjectionCriteria) -> CurveProjection {
+        let mut u = u0;
+        for _ in 0..criteria.max_iterations {
+            let ders = self.derivatives(u, 2);
+            let diff = ders[0] - point;
+            let dist = diff.norm();
+
+            // point coincidence and zero cosine checks
+            if dist <= criteria.point_tolerance {
+                break;
+            }
+            let cosine = ders[1].dot(&diff).abs() / (ders[1].norm() * dist);
+            if cosine <= criteria.cosine_tolerance {
+                break;
+            }
+
+            let f = ders[1].dot(&diff);
+            let df = ders[2].dot(&diff) + ders[1].norm_squared();
+            if df.abs() < 1e-300 {
+                break;
+            }
+            let u_new = clamp_param(u - f / df, a, b, closed);
+
+            // parameter does not change significantly
+            if ((u_new - u) * ders[1]).norm() <= criteria.point_tolerance {
+                u = u_new;
+                break;
+            }
+            u = u_new;
+        }
+
+        let foot = self.curve_point(u);
+        CurveProjection {
+            u,
+            point: foot,
+            distance: (foot - point).norm(),
+        }
+    }
+}
+
+impl NurbsSurface {
+
+    /// Whether the surface is closed in the u-direction, i.e. the first and
+    /// the last rows of control points coincide.
+    pub fn is_closed_u(&self) -> bool {
+        let n = self.ctrlpts_size_u;
+        let m = self.ctrlpts_size_v;
+        let pts = &self.bspline.ctrlpts;
+        return (0..m).all(|j| (pts[j] - pts[(n - 1) * m + j]).norm() < 1e-9);
+    }
+
+    /// Whether the surface is closed in the v-direction, i.e. the first and
+    /// the last columns of control points coincide.
+    pub fn is_closed_v(&self) -> bool {
+        let n = self.ctrlpts_size_u;
+        let m = self.ctrlpts_size_v;
+        let pts = &self.bspline.ctrlpts;
+        return (0..n).all(|i| (pts[i * m] - pts[i * m + m - 1]).norm() < 1e-9);
+    }
+
+    /// Finds the parameters of the point on the surface closest to `point`.
+    ///
+    /// The surface is sampled on a uniform grid to find the seed parameters,
+    /// which are then refined by Newton iteration (The NURBS Book, Section
+    /// 6.1). Each parameter is clamped to the domain in the open directions
+    /// and wrapped around in the closed ones.
+    ///
+    /// # Arguments
+    ///
+    /// - `point`: point to be projected
+    /// - `criteria`: stopping criteria
+    pub fn project_point(&self, point: &Vector3f,
+                         criteria: &ProjectionCriteria) -> SurfaceProjection {
+        let n = criteria.samples.max(2);
+        let mut params = Vec::new();
+        let mut dists = Vec::new();
+        for i in 0..=n {
+            for j in 0..=n {
+                let u = self.knotvec_u[0] + self.domain_u() * i as Scalar / n as Scalar;
+                let v = self.knotvec_v[0] + self.domain_v() * j as Scalar / n as Scalar;
+                params.push((u, v));
+                dists.push((self.evaluate_single(u, v) - point).norm());
+            }
+        }
+
+        let mut best = SurfaceProjection {
+            u: 0.,
+            v: 0.,
+            point: Vector3f::zeros(),
+            distance: INFINITY,
+        };
+        for i in best_seeds(&dists, NUM_SEEDS) {
+            let (u, v) = params[i];
+            let res = self.project_point_newton(point, u, v, criteria);
+            if res.distance < best.distance {
+                best = res;
+            }
+        }
+
+        return best;
+    }
+
+    /// Refines a projection seed `(u0, v0)` by Newton iteration.
+    pub fn project_point_newton(&self, point: &Vector3f, u0: Scalar, v0: Scalar,
+                                criteria: &ProjectionCriteria) -> SurfaceProjection {
+        let (ua, ub) = (self.knotvec_u[0], self.knotvec_u[self.knotvec_u.len() - 1]);
+        let (va, vb) = (self.knotvec_v[0], self.knotvec_v[self.knotvec_v.len() - 1]);
+        let closed_u = self.is_closed_u();
+        let closed_v = self.is_closed_v();
+
+        let mut u = u0;
+        let mut v = v0;
+        for _ in 0..criteria.max_iterations {
+            let skl = self.derivatives(u, v, 2);
+            let r = skl[0][0] - point;
+            let dist = r.norm();
+            let su = &skl[1][0];
+            let sv = &skl[0][1];
+
+            // point coincidence and zero cosine checks
+            if dist <= criteria.point_tolerance {
+                break;
+            }
+            let f = su.dot(&r);
+            let g = sv.dot(&r);
+            if f.abs() / (su.norm() * dist) <= criteria.cosine_tolerance
+                && g.abs() / (sv.norm() * dist) <= criteria.cosine_tolerance {
+                break;
+            }
+
+            let j = Matrix2::new(
+                su.norm_squared() + r.dot(&skl[2][0]), su.dot(sv) + r.dot(&skl[1][1]),
+                su.dot(sv) + r.dot(&skl[1][1]), sv.norm_squared() + r.dot(&skl[0][2]),
+            );
+            let delta = match j.try_inverse() {
+                Some(j_inv) => j_inv * Vector2::new(-f, -g),
+                None => break,
+            };
+
+            let u_new = clamp_param(u + delta[0], ua, ub, closed_u);
+            let v_new = clamp_param(v + delta[1], va, vb, closed_v);
+
+            // parameters do not change significantly
+            let step = (u_new - u) * su + (v_new - v) * sv;
+            u = u_new;
+            v = v_new;
+            if step.norm() <= criteria.point_tolerance {
+                break;
+            }
+        }
+
+        let foot = self.evaluate_single(u, v);
+        SurfaceProjection {
+            u,
+            v,
+            point: foot,
+            distance: (foot - point).norm(),
+        }
+    }
+
+    /// Length of the parametric domain in the u-direction.
+    pub fn domain_u(&self) -> Scalar {
+        self.knotvec_u[self.knotvec_u.len() - 1] - self.knotvec_u[0]
+    }
+
+    /// Length of the parametric domain in the v-direction.
+    pub fn domain_v(&self) -> Scalar {
+        self.knotvec_v[self.knotvec_v.len() - 1] - self.knotvec_v[0]
+    }
+}
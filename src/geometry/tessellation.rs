@@ -0,0 +1,382 @@
+use crate::math::*;
+use crate::geometry::NurbsSurface;
+use crate::geometry::trimmed::{Containment, classify_rect, point_in_loops, nearest_on_loops};
+use crate::utils::{IndexedMesh, IndexedTriangle};
+use na::{Point3, Vector3};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+use kiss3d::resource::Mesh;
+
+/// Refinement criteria for adaptive tessellation.
+pub struct TessellationCriteria {
+    pub chord_tolerance: Scalar,   // maximum chordal deviation
+    pub angle_tolerance: Scalar,   // maximum angle between normals within a cell (radians)
+    pub min_level:       usize,    // minimum subdivision level
+    pub max_level:       usize,    // maximum subdivision level
+}
+
+impl TessellationCriteria {
+
+    pub fn default() -> Self {
+        TessellationCriteria {
+            chord_tolerance: 1e-3,
+            angle_tolerance: 0.2,
+            min_level:       2,
+            max_level:       7,
+        }
+    }
+}
+
+/// Triangle mesh of a surface, together with the surface parameters and
+/// normals at each vertex.
+#[derive(Debug, Clone)]
+pub struct Tessellation {
+    pub vertices:  Vec<Vector3f>,
+    pub normals:   Vec<Vector3f>,
+    pub params:    Vec<(Scalar, Scalar)>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl Tessellation {
+
+    /// Converts the tessellation to a kiss3d mesh with per-vertex normals.
+    ///
+    /// kiss3d indexes vertices with `u16`: returns `None` if the tessellation
+    /// has more than 65536 vertices.
+    pub fn to_kiss3d_mesh(&self) -> Option<Rc<RefCell<Mesh>>> {
+        if self.vertices.len() > u16::max_value() as usize + 1 {
+            return None;
+        }
+
+        let coords = self.vertices.iter()
+            .map(|p| Point3::new(p[0] as f32, p[1] as f32, p[2] as f32))
+            .collect();
+        let normals = self.normals.iter()
+            .map(|n| Vector3::new(n[0] as f32, n[1] as f32, n[2] as f32))
+            .collect();
+        let faces = self.triangles.iter()
+            .map(|t| Point3::new(t[0] as u16, t[1] as u16, t[2] as u16))
+            .collect();
+
+        let mesh = Rc::new(RefCell::new(Mesh::new(
+            coords, faces, Some(normals), None, false,
+        )));
+
+        return Some(mesh);
+    }
+
+    /// Converts the tessellation to an indexed mesh with per-face normals.
+    pub fn to_indexed_mesh(&self) -> IndexedMesh {
+        let vertices = self.vertices.iter()
+            .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+            .collect();
+        let faces = self.triangles.iter()
+            .map(|t| {
+                let a = &self.vertices[t[0]];
+                let normal = (self.vertices[t[1]] - a)
+                    .cross(&(self.vertices[t[2]] - a))
+                    .try_normalize(0.)
+                    .unwrap_or(Vector3f::zeros());
+                IndexedTriangle {
+                    normal: [normal[0] as f32, normal[1] as f32, normal[2] as f32],
+                    vertices: *t,
+                }
+            })
+            .collect();
+
+        IndexedMesh { vertices, faces }
+    }
+}
+
+/// Square cell of the parametric domain, in units of the finest level.
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    i0: u32,
+    j0: u32,
+    size: u32,
+    level: usize,
+}
+
+/// Adaptive quadtree tessellator of the parametric domain of a surface.
+struct Tessellator<'a> {
+    surf: &'a NurbsSurface,
+    criteria: &'a TessellationCriteria,
+    units: u32,
+    domain: (Scalar, Scalar, Scalar, Scalar),
+    samples: HashMap<(u32, u32), (Vector3f, Vector3f)>,
+    loops: &'a Vec<Vec<(Scalar, Scalar)>>,     // trimming polygons, none if untrimmed
+    bounded: bool,                              // whether the polygons include an outer loop
+}
+
+impl<'a> Tessellator<'a> {
+
+    fn new(surf: &'a NurbsSurface, criteria: &'a TessellationCriteria,
+           loops: &'a Vec<Vec<(Scalar, Scalar)>>, bounded: bool) -> Self {
+        Tessellator {
+            surf,
+            criteria,
+            units: 1 << criteria.max_level,
+            domain: (surf.knotvec_u[0], surf.domain_u(), surf.knotvec_v[0], surf.domain_v()),
+            samples: HashMap::new(),
+            loops,
+            bounded,
+        }
+    }
+
+    fn param(&self, key: (u32, u32)) -> (Scalar, Scalar) {
+        let (u0, du, v0, dv) = self.domain;
+        (u0 + du * key.0 as Scalar / self.units as Scalar,
+         v0 + dv * key.1 as Scalar / self.units as Scalar)
+    }
+
+    /// Returns the (cached) surface point and normal at the given key.
+    fn sample(&mut self, key: (u32, u32)) -> (Vector3f, Vector3f) {
+        if let Some(sample) = self.samples.get(&key) {
+            return *sample;
+        }
+        let (u, v) = self.param(key);
+        let sample = (self.surf.evaluate_single(u, v), self.surf.normal(u, v));
+        self.samples.insert(key, sample);
+        return sample;
+    }
+
+    /// Whether the cell approximates the surface well enough.
+    fn is_flat(&mut self, cell: &Cell) -> bool {
+        let h = cell.size / 2;
+        let (i0, j0, i1, j1) = (cell.i0, cell.j0, cell.i0 + cell.size, cell.j0 + cell.size);
+        let corners = [
+            self.sample((i0, j0)), self.sample((i1, j0)),
+            self.sample((i1, j1)), self.sample((i0, j1)),
+        ];
+
+        // chordal deviation at the center and at the edge midpoints
+        let center = self.sample((i0 + h, j0 + h));
+        let bilinear = (corners[0].0 + corners[1].0 + corners[2].0 + corners[3].0) / 4.;
+        if (center.0 - bilinear).norm() > self.criteria.chord_tolerance {
+            return false;
+        }
+        let mids = [(i0 + h, j0), (i1, j0 + h), (i0 + h, j1), (i0, j0 + h)];
+        for k in 0..4 {
+            let mid = self.sample(mids[k]);
+            let chord = (corners[k].0 + corners[(k + 1) % 4].0) / 2.;
+            if (mid.0 - chord).norm() > self.criteria.chord_tolerance {
+                return false;
+            }
+        }
+
+        // angle between the normals
+        for corner in &corners {
+            if corner.1.angle(&center.1) > self.criteria.angle_tolerance {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    /// Classifies the cell against the trimming polygons.
+    fn containment(&self, cell: &Cell) -> Containment {
+        if self.loops.is_empty() {
+            return Containment::Inside;
+        }
+        let (u0, v0) = self.param((cell.i0, cell.j0));
+        let (u1, v1) = self.param((cell.i0 + cell.size, cell.j0 + cell.size));
+        return classify_rect(self.loops, self.bounded, (u0, u1, v0, v1));
+    }
+
+    fn subdivide(&mut self, cell: Cell, leaves: &mut Vec<Cell>) {
+        // trimmed away cells are not refined, and the cells crossed by the
+        // trims are refined to the finest level
+        let split = cell.size > 1 && match self.containment(&cell) {
+            Containment::Outside => false,
+            Containment::Crossing => true,
+            Containment::Inside => cell.level < self.criteria.min_level || !self.is_flat(&cell),
+        };
+        if !split {
+            leaves.push(cell);
+            return;
+        }
+
+        let h = cell.size / 2;
+        for &(di, dj) in &[(0, 0), (h, 0), (0, h), (h, h)] {
+            self.subdivide(Cell {
+                i0: cell.i0 + di,
+                j0: cell.j0 + dj,
+                size: h,
+                level: cell.level + 1,
+            }, leaves);
+        }
+    }
+
+    /// Returns the leaf cells of the adaptive quadtree.
+    fn leaves(&mut self) -> Vec<Cell> {
+        let mut leaves = Vec::new();
+        let root = Cell { i0: 0, j0: 0, size: self.units, level: 0 };
+        self.subdivide(root, &mut leaves);
+        return leaves;
+    }
+}
+
+/// Lists the keys on the boundary of each leaf cell in counter-clockwise
+/// order, including the corners of finer neighbours lying on its edges.
+pub(crate) fn cell_boundaries(cells: &Vec<(u32, u32, u32)>) -> Vec<Vec<(u32, u32)>> {
+    // corners on each horizontal (fixed j) and vertical (fixed i) line
+    let mut rows: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    let mut cols: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    for &(i0, j0, size) in cells {
+        for &(i, j) in &[(i0, j0), (i0 + size, j0), (i0 + size, j0 + size), (i0, j0 + size)] {
+            rows.entry(j).or_insert_with(BTreeSet::new).insert(i);
+            cols.entry(i).or_insert_with(BTreeSet::new).insert(j);
+        }
+    }
+
+    let mut boundaries = Vec::new();
+    for &(i0, j0, size) in cells {
+        let (i1, j1) = (i0 + size, j0 + size);
+        let mut keys = Vec::new();
+        keys.extend(rows[&j0].range(i0..i1).map(|i| (*i, j0)));
+        keys.extend(cols[&i1].range(j0..j1).map(|j| (i1, *j)));
+        keys.extend(rows[&j1].range(i0+1..=i1).rev().map(|i| (*i, j1)));
+        keys.extend(cols[&i0].range(j0+1..=j1).rev().map(|j| (i0, *j)));
+        boundaries.push(keys);
+    }
+
+    return boundaries;
+}
+
+impl NurbsSurface {
+
+    /// Tessellates the surface adaptively.
+    ///
+    /// The parametric domain is subdivided as a quadtree until each cell
+    /// deviates from the surface by less than the chordal tolerance and the
+    /// normals within the cell differ by less than the angle tolerance.
+    /// Cells with finer neighbours are triangulated as fans that include the
+    /// neighbours' vertices, so the resulting mesh has no cracks. The seam
+    /// of closed surfaces is welded.
+    ///
+    /// # Arguments
+    ///
+    /// - `criteria`: refinement criteria
+    pub fn tessellate(&self, criteria: &TessellationCriteria) -> Tessellation {
+        return self.tessellate_trimmed(criteria, &Vec::new(), false);
+    }
+
+    /// Tessellates the part of the surface inside the closed polygons of
+    /// the parametric domain (even-odd rule, see `point_in_loops`), or the
+    /// whole surface if there are none. See `TrimmedNurbsSurface::tessellate`.
+    pub(crate) fn tessellate_trimmed(&self, criteria: &TessellationCriteria,
+                                     loops: &Vec<Vec<(Scalar, Scalar)>>, bounded: bool) -> Tessellation {
+        let mut tess = Tessellator::new(self, criteria, loops, bounded);
+        let leaves = tess.leaves();
+        let cells = leaves.iter().map(|c| (c.i0, c.j0, c.size)).collect();
+        let boundaries = cell_boundaries(&cells);
+
+        let units = tess.units;
+        let closed_u = self.is_closed_u();
+        let closed_v = self.is_closed_v();
+
+        let mut mesh = Tessellation {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            params: Vec::new(),
+            triangles: Vec::new(),
+        };
+        let mut indices: HashMap<(u32, u32), usize> = HashMap::new();
+        let mut vertex = |key: (u32, u32), tess: &mut Tessellator, mesh: &mut Tessellation| {
+            // weld the seam of closed surfaces
+            let key = (if closed_u && key.0 == units { 0 } else { key.0 },
+                       if closed_v && key.1 == units { 0 } else { key.1 });
+            *indices.entry(key).or_insert_with(|| {
+                let (point, normal) = tess.sample(key);
+                mesh.vertices.push(point);
+                mesh.normals.push(normal);
+                mesh.params.push(tess.param(key));
+                mesh.vertices.len() - 1
+            })
+        };
+
+        for (cell, keys) in leaves.iter().zip(boundaries) {
+            let ids: Vec<usize> = keys.iter().map(|k| vertex(*k, &mut tess, &mut mesh)).collect();
+            if ids.len() == 4 {
+                mesh.triangles.push([ids[0], ids[1], ids[2]]);
+                mesh.triangles.push([ids[0], ids[2], ids[3]]);
+            } else {
+                let h = cell.size / 2;
+                let center = vertex((cell.i0 + h, cell.j0 + h), &mut tess, &mut mesh);
+                for k in 0..ids.len() {
+                    mesh.triangles.push([center, ids[k], ids[(k + 1) % ids.len()]]);
+                }
+            }
+        }
+
+        // drop triangles collapsed by welding the seam
+        mesh.triangles.retain(|t| t[0] != t[1] && t[1] != t[2] && t[2] != t[0]);
+
+        if !loops.is_empty() {
+            mesh = self.trim_tessellation(mesh, loops, bounded);
+        }
+
+        return mesh;
+    }
+
+    /// Drops the triangles of the tessellation with neither a vertex nor
+    /// their center inside of the polygons, and moves the outer vertices of
+    /// the remaining ones onto the polygons.
+    fn trim_tessellation(&self, mesh: Tessellation, loops: &Vec<Vec<(Scalar, Scalar)>>,
+                         bounded: bool) -> Tessellation {
+        let area = |t: &[usize; 3], params: &Vec<(Scalar, Scalar)>| {
+            let (a, b, c) = (params[t[0]], params[t[1]], params[t[2]]);
+            (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+        };
+
+        let inside: Vec<bool> = mesh.params.iter()
+            .map(|p| point_in_loops(loops, bounded, p.0, p.1))
+            .collect();
+        let triangles: Vec<[usize; 3]> = mesh.triangles.iter()
+            .filter(|t| {
+                let (a, b, c) = (mesh.params[t[0]], mesh.params[t[1]], mesh.params[t[2]]);
+                t.iter().any(|i| inside[*i]) ||
+                    point_in_loops(loops, bounded, (a.0 + b.0 + c.0) / 3., (a.1 + b.1 + c.1) / 3.)
+            })
+            .cloned()
+            .collect();
+
+        let mut trimmed = Tessellation {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            params: Vec::new(),
+            triangles: Vec::new(),
+        };
+        let mut indices: HashMap<usize, usize> = HashMap::new();
+        for t in &triangles {
+            let mut ids = [0; 3];
+            for k in 0..3 {
+                let i = t[k];
+                ids[k] = *indices.entry(i).or_insert_with(|| {
+                    if inside[i] {
+                        trimmed.vertices.push(mesh.vertices[i]);
+                        trimmed.normals.push(mesh.normals[i]);
+                        trimmed.params.push(mesh.params[i]);
+                    } else {
+                        let (u, v) = nearest_on_loops(loops, mesh.params[i].0, mesh.params[i].1);
+                        trimmed.vertices.push(self.evaluate_single(u, v));
+                        trimmed.normals.push(self.normal(u, v));
+                        trimmed.params.push((u, v));
+                    }
+                    trimmed.vertices.len() - 1
+                });
+            }
+
+            // drop the triangles collapsed or flipped by moving the vertices
+            let before = area(t, &mesh.params);
+            let after = area(&ids, &trimmed.params);
+            if after * before.signum() > 1e-12 * before.abs() {
+                trimmed.triangles.push(ids);
+            }
+        }
+
+        return trimmed;
+    }
+}
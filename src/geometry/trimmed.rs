@@ -0,0 +1,262 @@
+use crate::math::*;
+use crate::geometry::{NurbsCurve, NurbsSurface, Tessellation, TessellationCriteria};
+
+/// Closed loop of the parametric domain of a surface, made of consecutive
+/// curves whose `(x, y)` coordinates are the `(u, v)` parameters (the
+/// z-coordinate is ignored). The end of each curve is the start of the next
+/// one, and the end of the last curve is the start of the first one.
+#[derive(Debug, Clone)]
+pub struct TrimLoop {
+    pub curves: Vec<NurbsCurve>,
+}
+
+impl TrimLoop {
+
+    pub fn new(curves: Vec<NurbsCurve>) -> Self {
+        assert!(!curves.is_empty(), "empty trimming loop");
+        TrimLoop { curves }
+    }
+
+    /// Creates a loop from the vertices of a polygon of the parametric
+    /// domain, as a single closed curve of degree 1.
+    pub fn from_polygon(points: &Vec<(Scalar, Scalar)>) -> Self {
+        assert!(points.len() >= 3, "a polygon needs at least three vertices");
+        let n = points.len() + 1;
+        let mut ctrlpts: Vec<Vector3f> = points.iter().map(|p| Vector3f::new(p.0, p.1, 0.)).collect();
+        ctrlpts.push(ctrlpts[0]);
+
+        let mut knot_vec = vec![0.];
+        knot_vec.extend((0..n).map(|i| i as Scalar / (n - 1) as Scalar));
+        knot_vec.push(1.);
+
+        TrimLoop::new(vec![NurbsCurve::new(ctrlpts, knot_vec, 1, vec![1.; n])])
+    }
+
+    /// Approximates the loop by a polygon, without repeating its first
+    /// vertex at the end.
+    ///
+    /// # Arguments
+    ///
+    /// - `samples`: number of segments per knot span of the curves
+    pub fn polygon(&self, samples: usize) -> Vec<(Scalar, Scalar)> {
+        let mut points = Vec::new();
+        for curve in &self.curves {
+            let mut knots = curve.knotvec.clone();
+            knots.dedup_by(|a, b| (*a - *b).abs() < 1e-12);
+            let segments = if curve.degree == 1 { 1 } else { samples };
+            for span in knots.windows(2) {
+                for k in 0..segments {
+                    let u = span[0] + (span[1] - span[0]) * k as Scalar / segments as Scalar;
+                    let p = curve.curve_point(u);
+                    points.push((p[0], p[1]));
+                }
+            }
+        }
+        return points;
+    }
+}
+
+/// Position of a region of the parametric domain relative to the trimmed
+/// domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    Inside,     // fully inside the trimmed domain
+    Outside,    // fully trimmed away
+    Crossing,   // crossed by a trimming loop
+}
+
+/// NURBS surface restricted to the part of its parametric domain inside
+/// the outer trimming loop and outside of the inner ones.
+///
+/// The loops are defined in the normalized parametric domain `[0, 1]^2` of
+/// the surface. Inner loops must lie inside the outer one and must not
+/// intersect each other.
+#[derive(Debug, Clone)]
+pub struct TrimmedNurbsSurface {
+    pub surface: NurbsSurface,
+    pub outer:   Option<TrimLoop>,              // outer loop, the domain boundary if none
+    pub inner:   Vec<TrimLoop>,                 // inner loops (holes)
+    polygons:    Vec<Vec<(Scalar, Scalar)>>,    // polygons of the outer and inner loops
+    bounded:     bool,                          // whether the polygons include an outer loop
+}
+
+impl TrimmedNurbsSurface {
+
+    /// Creates a trimmed surface, with 16 polygon segments per knot span of
+    /// the trimming curves.
+    ///
+    /// # Arguments
+    ///
+    /// - `surface`: underlying surface
+    /// - `outer`: outer loop, `None` for the boundary of the domain
+    /// - `inner`: inner loops
+    pub fn new(surface: NurbsSurface, outer: Option<TrimLoop>, inner: Vec<TrimLoop>) -> Self {
+        let mut trimmed = TrimmedNurbsSurface {
+            surface,
+            outer,
+            inner,
+            polygons: Vec::new(),
+            bounded: false,
+        };
+        trimmed.set_sample_size(16);
+        return trimmed;
+    }
+
+    /// Sets the number of polygon segments per knot span used to
+    /// approximate the trimming curves.
+    pub fn set_sample_size(&mut self, samples: usize) {
+        self.polygons.clear();
+        self.bounded = self.outer.is_some();
+        if let Some(outer) = &self.outer {
+            self.polygons.push(outer.polygon(samples));
+        }
+        for inner in &self.inner {
+            self.polygons.push(inner.polygon(samples));
+        }
+    }
+
+    /// Returns the polygons approximating the loops, the outer one first if
+    /// any.
+    pub fn polygons(&self) -> &Vec<Vec<(Scalar, Scalar)>> {
+        &self.polygons
+    }
+
+    /// Whether the parameters lie in the trimmed domain.
+    pub fn is_inside(&self, u: Scalar, v: Scalar) -> bool {
+        return point_in_loops(&self.polygons, self.bounded, u, v);
+    }
+
+    /// Classifies the rectangle `(u_min, u_max, v_min, v_max)` of the
+    /// parametric domain against the trimmed domain.
+    pub fn classify(&self, domain: (Scalar, Scalar, Scalar, Scalar)) -> Containment {
+        return classify_rect(&self.polygons, self.bounded, domain);
+    }
+
+    /// Evaluates the surface at the given parameters, ignoring the trims.
+    pub fn evaluate_single(&self, u: Scalar, v: Scalar) -> Vector3f {
+        self.surface.evaluate_single(u, v)
+    }
+
+    /// Tessellates the trimmed surface adaptively.
+    ///
+    /// The cells crossed by the trimming loops are refined to the maximum
+    /// level. Triangles with neither a vertex nor their center inside of the
+    /// trimmed domain are dropped, and the outer vertices of the remaining
+    /// ones are moved onto the nearest trimming loop.
+    ///
+    /// # Arguments
+    ///
+    /// - `criteria`: refinement criteria
+    pub fn tessellate(&self, criteria: &TessellationCriteria) -> Tessellation {
+        return self.surface.tessellate_trimmed(criteria, &self.polygons, self.bounded);
+    }
+}
+
+/// Even-odd test of the parameters against the closed polygons, the
+/// polygons themselves included. Without outer loop (`bounded` false), the
+/// whole domain is inside except for the holes.
+pub(crate) fn point_in_loops(loops: &Vec<Vec<(Scalar, Scalar)>>, bounded: bool,
+                             u: Scalar, v: Scalar) -> bool {
+    let mut inside = !bounded;
+    for polygon in loops {
+        let n = polygon.len();
+        for i in 0..n {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            if on_segment(a, b, (u, v)) {
+                return true;
+            }
+            if (a.1 > v) != (b.1 > v) {
+                let x = a.0 + (v - a.1) / (b.1 - a.1) * (b.0 - a.0);
+                if u < x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    return inside;
+}
+
+/// Whether the point lies on the segment `ab`, up to rounding errors.
+fn on_segment(a: (Scalar, Scalar), b: (Scalar, Scalar), p: (Scalar, Scalar)) -> bool {
+    let (d0, d1) = (b.0 - a.0, b.1 - a.1);
+    let (e0, e1) = (p.0 - a.0, p.1 - a.1);
+    let len2 = d0 * d0 + d1 * d1;
+    if len2 == 0. {
+        return e0 == 0. && e1 == 0.;
+    }
+    let dot = d0 * e0 + d1 * e1;
+    return (d0 * e1 - d1 * e0).abs() <= 1e-12 * len2.sqrt() && dot >= 0. && dot <= len2;
+}
+
+/// Whether the segment `ab` meets the rectangle (Liang-Barsky clipping).
+fn segment_meets_rect(a: (Scalar, Scalar), b: (Scalar, Scalar),
+                      rect: (Scalar, Scalar, Scalar, Scalar)) -> bool {
+    let (d0, d1) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1): (Scalar, Scalar) = (0., 1.);
+    for &(p, q) in &[(-d0, a.0 - rect.0), (d0, rect.1 - a.0),
+                     (-d1, a.1 - rect.2), (d1, rect.3 - a.1)] {
+        if p == 0. {
+            if q < 0. {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0. {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+/// Classifies the rectangle `(u_min, u_max, v_min, v_max)` against the
+/// region bounded by the closed polygons, as `point_in_loops`.
+pub(crate) fn classify_rect(loops: &Vec<Vec<(Scalar, Scalar)>>, bounded: bool,
+                            rect: (Scalar, Scalar, Scalar, Scalar)) -> Containment {
+    for polygon in loops {
+        let n = polygon.len();
+        for i in 0..n {
+            if segment_meets_rect(polygon[i], polygon[(i + 1) % n], rect) {
+                return Containment::Crossing;
+            }
+        }
+    }
+
+    if point_in_loops(loops, bounded, 0.5 * (rect.0 + rect.1), 0.5 * (rect.2 + rect.3)) {
+        Containment::Inside
+    } else {
+        Containment::Outside
+    }
+}
+
+/// Returns the point of the closed polygons nearest to the parameters.
+pub(crate) fn nearest_on_loops(loops: &Vec<Vec<(Scalar, Scalar)>>,
+                               u: Scalar, v: Scalar) -> (Scalar, Scalar) {
+    let mut best = (u, v);
+    let mut best_dist = INFINITY;
+    for polygon in loops {
+        let n = polygon.len();
+        for i in 0..n {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            let (d0, d1) = (b.0 - a.0, b.1 - a.1);
+            let len2 = d0 * d0 + d1 * d1;
+            let t = if len2 > 0. {
+                (((u - a.0) * d0 + (v - a.1) * d1) / len2).max(0.).min(1.)
+            } else {
+                0.
+            };
+            let p = (a.0 + t * d0, a.1 + t * d1);
+            let dist = (p.0 - u).powi(2) + (p.1 - v).powi(2);
+            if dist < best_dist {
+                best_dist = dist;
+                best = p;
+            }
+        }
+    }
+    return best;
+}
@@ -1,21 +1,259 @@
-/// Fast and Accurate Computation of Polyhedral Mass Propertie
-///
-/// https://people.eecs.berkeley.edu/~jfc/mirtich/massProps.html
-///
-/// The location of a body's center of mass, and its moments and products
-/// of inertia about various axes are important physical quantities needed
-/// for any type of dynamic simulation or physical based modeling. We
-/// present an algorithm for automatically computing these quantities for
-/// a general class of rigid bodies: those composed of uniform density polyhedra.
-///
-/// Our algorithm is based on a three step reduction of the volume integrals
-/// to successively simpler integrals. The algorithm is designed to minimize
-/// the numerical errors that can result from poorly conditioned alignment of
-/// polyhedral faces. It is also designed for efficiency. All required volume
-/// integrals of a polyhedron are computed together during a single walk over
-/// the boundary of the polyhedron; exploiting common subexpressions reduces
-/// floating point operations.
-pub fn comp_projection_integrals() {
-    unimplemented!()
+//! Fast and Accurate Computation of Polyhedral Mass Propertie
+//!
+//! https://people.eecs.berkeley.edu/~jfc/mirtich/massProps.html
+//!
+//! The location of a body's center of mass, and its moments and products
+//! of inertia about various axes are important physical quantities needed
+//! for any type of dynamic simulation or physical based modeling. We
+//! present an algorithm for automatically computing these quantities for
+//! a general class of rigid bodies: those composed of uniform density polyhedra.
+//!
+//! Our algorithm is based on a three step reduction of the volume integrals
+//! to successively simpler integrals. The algorithm is designed to minimize
+//! the numerical errors that can result from poorly conditioned alignment of
+//! polyhedral faces. It is also designed for efficiency. All required volume
+//! integrals of a polyhedron are computed together during a single walk over
+//! the boundary of the polyhedron; exploiting common subexpressions reduces
+//! floating point operations.
+
+use crate::math::*;
+use crate::utils::IndexedMesh;
+
+/// Mass properties of a solid of uniform density.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+    pub volume:         Scalar,
+    pub mass:           Scalar,
+    pub center_of_mass: Vector3f,
+    pub inertia:        Matrix3f,   // inertia tensor about the center of mass, aligned with the frame
+}
+
+impl MassProperties {
+
+    /// Creates the mass properties of a solid from its volume, density,
+    /// center of mass, and inertia tensor per unit of mass about the center
+    /// of mass.
+    pub fn new(volume: Scalar, density: Scalar, center_of_mass: Vector3f, unit_inertia: Matrix3f) -> Self {
+        let mass = density * volume;
+        MassProperties { volume, mass, center_of_mass, inertia: mass * unit_inertia }
+    }
+
+    /// Returns the mass properties of the solid moved by the rigid transform.
+    pub fn transform(&self, tform: &Isometry3f) -> Self {
+        let rotm = tform.rotation.to_rotation_matrix();
+        let rotm = rotm.matrix();
+        MassProperties {
+            volume: self.volume,
+            mass: self.mass,
+            center_of_mass: tform.transform_point(&Point3f::from(self.center_of_mass)).coords,
+            inertia: rotm * self.inertia * rotm.transpose(),
+        }
+    }
+
+    /// Returns the mass properties of the union of two disjoint solids.
+    pub fn combine(&self, other: &MassProperties) -> Self {
+        let mass = self.mass + other.mass;
+        if mass <= 0. {
+            return MassProperties {
+                volume: self.volume + other.volume,
+                mass,
+                center_of_mass: (self.center_of_mass + other.center_of_mass) / 2.,
+                inertia: Matrix3f::zeros(),
+            };
+        }
+        let center_of_mass = (self.mass * self.center_of_mass + other.mass * other.center_of_mass) / mass;
+
+        // parallel axis theorem: I + m (|d|^2 E - d d^T)
+        let shift = |props: &MassProperties| {
+            let d = props.center_of_mass - center_of_mass;
+            props.inertia + props.mass * (d.norm_squared() * Matrix3f::identity() - d * d.transpose())
+        };
+        MassProperties {
+            volume: self.volume + other.volume,
+            mass,
+            center_of_mass,
+            inertia: shift(self) + shift(other),
+        }
+    }
+}
+
+/// Integrals over the projection of a face on the plane of its `a` and `b`
+/// axes: $\int 1$, $\int a$, $\int a^2$, ... in the order
+/// `[P1, Pa, Pb, Paa, Pab, Pbb, Paaa, Paab, Pabb, Pbbb]`.
+fn comp_projection_integrals(points: &[Vector3f; 3], a: usize, b: usize) -> [Scalar; 10] {
+    let (mut p1, mut pa, mut pb, mut paa, mut pab, mut pbb) = (0., 0., 0., 0., 0., 0.);
+    let (mut paaa, mut paab, mut pabb, mut pbbb) = (0., 0., 0., 0.);
+
+    for i in 0..3 {
+        let (a0, b0) = (points[i][a], points[i][b]);
+        let (a1, b1) = (points[(i + 1) % 3][a], points[(i + 1) % 3][b]);
+        let (da, db) = (a1 - a0, b1 - b0);
+        let (a0_2, b0_2, a1_2, b1_2) = (a0 * a0, b0 * b0, a1 * a1, b1 * b1);
+        let (a0_3, b0_3, a1_3, b1_3) = (a0_2 * a0, b0_2 * b0, a1_2 * a1, b1_2 * b1);
+        let (a0_4, b0_4) = (a0_3 * a0, b0_3 * b0);
+
+        let c1 = a1 + a0;
+        let ca = a1 * c1 + a0_2;
+        let caa = a1 * ca + a0_3;
+        let caaa = a1 * caa + a0_4;
+        let cb = b1 * (b1 + b0) + b0_2;
+        let cbb = b1 * cb + b0_3;
+        let cbbb = b1 * cbb + b0_4;
+        let cab = 3. * a1_2 + 2. * a1 * a0 + a0_2;
+        let kab = a1_2 + 2. * a1 * a0 + 3. * a0_2;
+        let caab = a0 * cab + 4. * a1_3;
+        let kaab = a1 * kab + 4. * a0_3;
+        let cabb = 4. * b1_3 + 3. * b1_2 * b0 + 2. * b1 * b0_2 + b0_3;
+        let kabb = b1_3 + 2. * b1_2 * b0 + 3. * b1 * b0_2 + 4. * b0_3;
+
+        p1 += db * c1;
+        pa += db * ca;
+        paa += db * caa;
+        paaa += db * caaa;
+        pb += da * cb;
+        pbb += da * cbb;
+        pbbb += da * cbbb;
+        pab += db * (b1 * cab + b0 * kab);
+        paab += db * (b1 * caab + b0 * kaab);
+        pabb += da * (a1 * cabb + a0 * kabb);
+    }
+
+    return [p1 / 2., pa / 6., pb / -6., paa / 12., pab / 24., pbb / -12.,
+            paaa / 20., paab / 60., pabb / -60., pbbb / -20.];
+}
+
+/// Integrals over a face of unit normal `n` lying in the plane
+/// $n \cdot x + w = 0$, in the order
+/// `[Fa, Fb, Fc, Faa, Fbb, Fcc, Faaa, Fbbb, Fccc, Faab, Fbbc, Fcca]`,
+/// where `c` is the axis along which the normal is the largest.
+fn comp_face_integrals(points: &[Vector3f; 3], n: &Vector3f, w: Scalar,
+                       a: usize, b: usize, c: usize) -> [Scalar; 12] {
+    let [p1, pa, pb, paa, pab, pbb, paaa, paab, pabb, pbbb] = comp_projection_integrals(points, a, b);
+
+    let k1 = 1. / n[c];
+    let k2 = k1 * k1;
+    let k3 = k2 * k1;
+    let k4 = k3 * k1;
+    let (na, nb) = (n[a], n[b]);
+
+    let fa = k1 * pa;
+    let fb = k1 * pb;
+    let fc = -k2 * (na * pa + nb * pb + w * p1);
+
+    let faa = k1 * paa;
+    let fbb = k1 * pbb;
+    let fcc = k3 * (na * na * paa + 2. * na * nb * pab + nb * nb * pbb
+        + w * (2. * (na * pa + nb * pb) + w * p1));
+
+    let faaa = k1 * paaa;
+    let fbbb = k1 * pbbb;
+    let fccc = -k4 * (na * na * na * paaa + 3. * na * na * nb * paab
+        + 3. * na * nb * nb * pabb + nb * nb * nb * pbbb
+        + 3. * w * (na * na * paa + 2. * na * nb * pab + nb * nb * pbb)
+        + w * w * (3. * (na * pa + nb * pb) + w * p1));
+
+    let faab = k1 * paab;
+    let fbbc = -k2 * (na * pabb + nb * pbbb + w * pbb);
+    let fcca = k3 * (na * na * paaa + 2. * na * nb * paab + nb * nb * pabb
+        + w * (2. * (na * paa + nb * pab) + w * pa));
+
+    return [fa, fb, fc, faa, fbb, fcc, faaa, fbbb, fccc, faab, fbbc, fcca];
+}
+
+/// Volume integrals over the solid bounded by the triangles: $\int 1$,
+/// $\int x$, $\int x^2$ per axis, and $\int xy$, $\int yz$, $\int zx$.
+fn comp_volume_integrals(mesh: &IndexedMesh) -> (Scalar, Vector3f, Vector3f, Vector3f) {
+    let mut t0 = 0.;
+    let mut t1 = Vector3f::zeros();
+    let mut t2 = Vector3f::zeros();
+    let mut tp = Vector3f::zeros();
+
+    for face in &mesh.faces {
+        let mut points = [Vector3f::zeros(); 3];
+        for k in 0..3 {
+            let v = &mesh.vertices[face.vertices[k]];
+            points[k] = Vector3f::new(v[0] as Scalar, v[1] as Scalar, v[2] as Scalar);
+        }
+        let n = match (points[1] - points[0]).cross(&(points[2] - points[0])).try_normalize(0.) {
+            Some(n) => n,
+            None => continue,       // degenerate faces do not contribute
+        };
+        let w = -n.dot(&points[0]);
+
+        // project on the plane maximizing the projected area
+        let nabs = n.abs();
+        let c = if nabs[0] > nabs[1] && nabs[0] > nabs[2] {
+            0
+        } else if nabs[1] > nabs[2] {
+            1
+        } else {
+            2
+        };
+        let a = (c + 1) % 3;
+        let b = (a + 1) % 3;
+
+        let [fa, fb, fc, faa, fbb, fcc, faaa, fbbb, fccc, faab, fbbc, fcca] =
+            comp_face_integrals(&points, &n, w, a, b, c);
+
+        t0 += n[0] * if a == 0 { fa } else if b == 0 { fb } else { fc };
+        t1[a] += n[a] * faa;
+        t1[b] += n[b] * fbb;
+        t1[c] += n[c] * fcc;
+        t2[a] += n[a] * faaa;
+        t2[b] += n[b] * fbbb;
+        t2[c] += n[c] * fccc;
+        tp[a] += n[a] * faab;
+        tp[b] += n[b] * fbbc;
+        tp[c] += n[c] * fcca;
+    }
+
+    return (t0, t1 / 2., t2 / 3., tp / 2.);
 }
 
+impl IndexedMesh {
+
+    /// Computes the volume enclosed by the mesh.
+    pub fn volume(&self) -> Scalar {
+        let (t0, _, _, _) = comp_volume_integrals(self);
+        return t0.abs();
+    }
+
+    /// Computes the mass properties of the solid bounded by the mesh, with
+    /// uniform density, using Mirtich's volume integration.
+    ///
+    /// The mesh must be closed and consistently oriented. Faces oriented
+    /// inwards give the same result as faces oriented outwards.
+    ///
+    /// # Arguments
+    ///
+    /// - `density`: mass per unit of volume
+    pub fn mass_properties(&self, density: Scalar) -> MassProperties {
+        let (mut t0, mut t1, mut t2, mut tp) = comp_volume_integrals(self);
+        if t0 < 0. {
+            t0 = -t0;
+            t1 = -t1;
+            t2 = -t2;
+            tp = -tp;
+        }
+
+        let mass = density * t0;
+        let r = if t0 > 0. { t1 / t0 } else { Vector3f::zeros() };
+
+        // inertia tensor about the origin
+        let mut j = Matrix3f::new(
+            t2[1] + t2[2], -tp[0], -tp[2],
+            -tp[0], t2[2] + t2[0], -tp[1],
+            -tp[2], -tp[1], t2[0] + t2[1],
+        ) * density;
+
+        // translate the inertia tensor to the center of mass
+        j -= mass * (r.norm_squared() * Matrix3f::identity() - r * r.transpose());
+
+        return MassProperties {
+            volume: t0,
+            mass,
+            center_of_mass: r,
+            inertia: j,
+        };
+    }
+}
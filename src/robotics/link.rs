@@ -7,6 +7,11 @@ use kiss3d::resource::Mesh;
 use std::cell::RefCell;
 use std::rc::Rc;
 use crate::utils::Color;
+use crate::geometry::{MassProperties, NurbsSurface};
+use crate::robotics::inertia_com2body;
+use crate::utils::load_indexed_mesh;
+use crate::ccd::{self, CCDObject, ConvexHull, Transformed};
+use std::f64::consts::PI;
 
 /// abstract geom
 pub struct Geom {
@@ -46,6 +51,34 @@ impl Default for Link {
     }
 }
 
+impl Link {
+
+    /// Computes the inertial properties of the link from its collision
+    /// geometries, assumed to be disjoint solids of uniform density. The
+    /// center of mass frame is aligned with the link frame.
+    ///
+    /// # Arguments
+    ///
+    /// - `density`: mass per unit of volume
+    pub fn inertial_from_collisions(&self, density: Scalar) -> std::io::Result<Inertial> {
+        let mut props: Option<MassProperties> = None;
+        for collision in &self.collisions {
+            let part = collision.geometry.mass_properties(density)?.transform(collision.origin());
+            props = Some(match props {
+                Some(props) => props.combine(&part),
+                None => part,
+            });
+        }
+        match props {
+            Some(props) => Ok(Inertial::from_mass_properties(&props)),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("link '{}' has no collision geometry", self.name),
+            )),
+        }
+    }
+}
+
 impl fmt::Display for Link {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.name)
@@ -61,6 +94,150 @@ pub enum Geometry {
     Mesh     { filename: String, scale: Vector3f32, mesh: Rc<RefCell<Mesh>> },
 }
 
+impl Geometry {
+
+    /// Converts the primitive into exact NURBS surfaces, expressed in the
+    /// frame of the geometry (the origin of the visual or collision is not
+    /// applied). Cylinders and capsules are aligned with the z-axis and
+    /// centered at the origin, as in URDF. Returns `None` for meshes.
+    pub fn to_nurbs(&self) -> Option<Vec<NurbsSurface>> {
+        let zvec = Vector3f::new(0., 0., 1.);
+        match self {
+            Geometry::Box { depth, width, height } => {
+                let half = Vector3f::new(*depth, *width, *height) / 2.;
+                let mut faces = Vec::with_capacity(6);
+                for a in 0..3 {
+                    let (b, c) = ((a + 1) % 3, (a + 2) % 3);
+                    let mut side_b = Vector3f::zeros();
+                    let mut side_c = Vector3f::zeros();
+                    side_b[b] = 2. * half[b];
+                    side_c[c] = 2. * half[c];
+                    for sign in &[1., -1.] {
+                        let mut origin = -half;
+                        origin[a] = sign * half[a];
+                        faces.push(if *sign > 0. {
+                            NurbsSurface::rectangle(&origin, &side_b, &side_c)
+                        } else {
+                            NurbsSurface::rectangle(&origin, &side_c, &side_b)
+                        });
+                    }
+                }
+                Some(faces)
+            },
+            Geometry::Cylinder { radius, length } => {
+                let base = Vector3f::new(0., 0., -length / 2.);
+                let top = Vector3f::new(0., 0., length / 2.);
+                Some(vec![
+                    NurbsSurface::cylinder(&base, &zvec, *radius, *length),
+                    NurbsSurface::disk(&base, &-zvec, *radius),
+                    NurbsSurface::disk(&top, &zvec, *radius),
+                ])
+            },
+            Geometry::Capsule { radius, length } => {
+                let base = Vector3f::new(0., 0., -length / 2.);
+                let top = Vector3f::new(0., 0., length / 2.);
+                Some(vec![
+                    NurbsSurface::cylinder(&base, &zvec, *radius, *length),
+                    NurbsSurface::spherical_zone(&base, &zvec, *radius, -PI / 2., 0.),
+                    NurbsSurface::spherical_zone(&top, &zvec, *radius, 0., PI / 2.),
+                ])
+            },
+            Geometry::Sphere { radius } => {
+                Some(vec![NurbsSurface::sphere(&Vector3f::zeros(), &zvec, *radius)])
+            },
+            Geometry::Mesh { .. } => None,
+        }
+    }
+
+    /// Computes the mass properties of the solid with uniform density, in
+    /// the frame of the geometry. Meshes are read again from their files and
+    /// scaled, and must be closed.
+    ///
+    /// # Arguments
+    ///
+    /// - `density`: mass per unit of volume
+    pub fn mass_properties(&self, density: Scalar) -> std::io::Result<MassProperties> {
+        let center = Vector3f::zeros();
+        Ok(match self {
+            Geometry::Box { depth, width, height } => {
+                let (x2, y2, z2) = (depth * depth, width * width, height * height);
+                let unit_inertia = Matrix3f::from_diagonal(
+                    &Vector3f::new(y2 + z2, x2 + z2, x2 + y2)) / 12.;
+                MassProperties::new(depth * width * height, density, center, unit_inertia)
+            },
+            Geometry::Cylinder { radius, length } => {
+                let (r2, l2) = (radius * radius, length * length);
+                let unit_inertia = Matrix3f::from_diagonal(
+                    &Vector3f::new((3. * r2 + l2) / 12., (3. * r2 + l2) / 12., r2 / 2.));
+                MassProperties::new(PI * r2 * length, density, center, unit_inertia)
+            },
+            Geometry::Capsule { radius, length } => {
+                // cylinder and two hemispheres, the latter about the center
+                let (r, l) = (*radius, *length);
+                let cylinder = density * PI * r * r * l;
+                let hemisphere = density * 2. / 3. * PI * r * r * r;
+                let izz = cylinder * r * r / 2. + 2. * hemisphere * 2. / 5. * r * r;
+                let ixx = cylinder * (3. * r * r + l * l) / 12.
+                    + 2. * hemisphere * (2. / 5. * r * r + l * l / 4. + 3. / 8. * l * r);
+                let volume = PI * r * r * l + 4. / 3. * PI * r * r * r;
+                MassProperties {
+                    volume,
+                    mass: density * volume,
+                    center_of_mass: center,
+                    inertia: Matrix3f::from_diagonal(&Vector3f::new(ixx, ixx, izz)),
+                }
+            },
+            Geometry::Sphere { radius } => {
+                let r2 = radius * radius;
+                MassProperties::new(4. / 3. * PI * r2 * radius, density, center,
+                                    Matrix3f::identity() * 2. / 5. * r2)
+            },
+            Geometry::Mesh { filename, scale, .. } => {
+                let mut mesh = load_indexed_mesh(filename)?;
+                for v in mesh.vertices.iter_mut() {
+                    for k in 0..3 {
+                        v[k] *= scale[k];
+                    }
+                }
+                mesh.mass_properties(density)
+            },
+        })
+    }
+
+    /// Returns the collision object of the geometry, in the frame of the
+    /// geometry. Meshes are read again from their files, scaled, and
+    /// replaced by their convex hull.
+    pub fn to_ccd_object(&self) -> std::io::Result<std::boxed::Box<dyn CCDObject>> {
+        let pos = Vector3f::zeros();
+        let rotm = Matrix3f::identity();
+        Ok(match self {
+            Geometry::Box { depth, width, height } => {
+                std::boxed::Box::new(ccd::Box { pos, rotm, dim: Vector3f::new(*depth, *width, *height) })
+            },
+            Geometry::Cylinder { radius, length } => {
+                std::boxed::Box::new(ccd::Cylinder { pos, rotm, radius: *radius, height: *length })
+            },
+            Geometry::Capsule { radius, length } => {
+                std::boxed::Box::new(ccd::Capsule { pos, rotm, radius: *radius, height: *length })
+            },
+            Geometry::Sphere { radius } => {
+                std::boxed::Box::new(ccd::Sphere { pos, rotm, radius: *radius })
+            },
+            Geometry::Mesh { filename, scale, .. } => {
+                let mut mesh = load_indexed_mesh(filename)?;
+                for v in mesh.vertices.iter_mut() {
+                    for k in 0..3 {
+                        v[k] *= scale[k];
+                    }
+                }
+                let hull = ConvexHull::from_indexed_mesh(&mesh).ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::InvalidData, format!("flat mesh: {}", filename)))?;
+                std::boxed::Box::new(hull)
+            },
+        })
+    }
+}
+
 impl Debug for Geometry {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{}", &self.to_string())
@@ -151,6 +328,14 @@ impl Inertial {
             spatial_inertia: spatial_inertia(mass, bvec_com, inertia_body),
         }
     }
+
+    /// Creates the inertial properties of a solid, with the center of mass
+    /// frame aligned with the body frame.
+    pub fn from_mass_properties(props: &MassProperties) -> Self {
+        let inertia_body = inertia_com2body(props.mass, props.center_of_mass, props.inertia);
+        Inertial::new(props.center_of_mass, props.mass, props.inertia, inertia_body)
+    }
+
     pub fn set_com(&mut self, origin: Vector3f) {
         self.bvec_com = origin;
     }
@@ -209,4 +394,10 @@ impl Collision {
     pub fn origin(&self) -> &Isometry3f {
         &self.origin
     }
+
+    /// Returns the collision object of the geometry, placed at the origin
+    /// of the collision in the link frame.
+    pub fn to_ccd_object(&self) -> std::io::Result<Transformed<std::boxed::Box<dyn CCDObject>>> {
+        Ok(Transformed { tform: self.origin, obj: self.geometry.to_ccd_object()? })
+    }
 }
\ No newline at end of file
@@ -18,33 +18,52 @@ impl From<urdf_rs::Robot> for RigidBodyTree {
 impl<'a> From<&'a urdf_rs::Robot> for RigidBodyTree {
 
     fn from(robot: &urdf_rs::Robot) -> Self {
-        let mut model = RigidBodyTree::new(&robot.name);
+        let links = robot.links.iter()
+            .map(|link| Link::from(link.clone()))
+            .collect();
+        let joints = robot.joints.iter()
+            .map(|joint| (Joint::from(joint), joint.parent.link.clone(), joint.child.link.clone()))
+            .collect();
+        match RigidBodyTree::from_links_and_joints(&robot.name, links, joints) {
+            Ok(tree) => return tree,
+            Err(msg) => {
+                error!("{}", msg);
+                std::process::exit(utils::ERROR_CODE_URDF_PARSING);
+            },
+        }
+    }
+}
 
-        for link in &robot.links {
-            model.body_name2ptr.insert(link.name.clone(), Rc::new(RefCell::new(
-                RigidBody::from_link(Link::from(link.clone()), true))));
+impl RigidBodyTree {
+
+    /// Builds the rigid body tree from its links, and its joints given as
+    /// `(joint, parent link name, child link name)`. The children of each
+    /// link are visited in the order of the joints. Returns an error if a
+    /// joint refers to a missing link or if there are several base links.
+    pub(crate) fn from_links_and_joints(name: &String, links: Vec<Link>,
+                                        joints: Vec<(Joint, String, String)>) -> Result<Self, String> {
+        let mut model = RigidBodyTree::new(name);
+
+        for link in links {
             model.children.insert(link.name.clone(), Vec::new());
+            model.body_name2ptr.insert(link.name.clone(), Rc::new(RefCell::new(
+                RigidBody::from_link(link, true))));
         }
 
-        for joint in &robot.joints {
-            match model.body_name2ptr.get_mut(&joint.child.link) {
-                None => {
-                    error!("joint {}'s child link not found.", joint.name);
-                    std::process::exit(utils::ERROR_CODE_URDF_PARSING);
-                },
+        for (joint, parent, child) in joints {
+            let joint_name = joint.name.clone();
+            match model.body_name2ptr.get_mut(&child) {
+                None => return Err(format!("joint {}'s child link not found", joint_name)),
                 Some(body) => {
-                    body.borrow_mut().joint = Joint::from(joint);
-                    model.joint.insert(joint.name.clone(), Rc::clone(body));
+                    body.borrow_mut().joint = joint;
+                    model.joint.insert(joint_name.clone(), Rc::clone(body));
                 },
             }
-            model.parent.insert(joint.child.link.clone(), joint.parent.link.clone());
-            match model.children.get_mut(&joint.parent.link) {
-                None => {
-                    error!("joint {}'s parent link not found.", joint.name);
-                    std::process::exit(utils::ERROR_CODE_URDF_PARSING);
-                },
+            model.parent.insert(child.clone(), parent.clone());
+            match model.children.get_mut(&parent) {
+                None => return Err(format!("joint {}'s parent link not found", joint_name)),
                 Some(children) => {
-                    children.push(joint.child.link.clone());
+                    children.push(child);
                 }
             }
         }
@@ -60,8 +79,7 @@ impl<'a> From<&'a urdf_rs::Robot> for RigidBodyTree {
             if model.parent.get(&body.link.name).is_none() {
                 // if more than one body are base candidates
                 if model.base.is_some() {
-                    error!("multiple base link detected.");
-                    std::process::exit(utils::ERROR_CODE_URDF_PARSING);
+                    return Err("multiple base link detected".to_owned());
                 }
                 model.base = Some(Rc::clone(body_ptr));
             }
@@ -132,6 +150,6 @@ impl<'a> From<&'a urdf_rs::Robot> for RigidBodyTree {
             }
         }
 
-        return model;
+        return Ok(model);
     }
 }
@@ -10,6 +10,8 @@ mod display;
 mod property;
 mod kinematics;
 mod dynamics;
+#[cfg(feature = "serde")]
+mod serialization;
 
 type RigidBodyPtr = Rc<RefCell<RigidBody>>;
 
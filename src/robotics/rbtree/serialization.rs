@@ -0,0 +1,104 @@
+use crate::robotics::*;
+use crate::utils::{vec3, serialize_versioned, deserialize_versioned};
+use crate::math::{Scalar, Vector3f};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error as DeError;
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize)]
+struct BodyData {
+    link:   Link,
+    joint:  Option<Joint>,      // joint to the parent, none for the base
+    parent: Option<String>,     // name of the parent link, none for the base
+}
+
+/// Schema of a rigid body tree. The base comes first, then the children of
+/// each body grouped by parent in the tree order, so that the indices of the
+/// bodies are preserved.
+#[derive(Serialize, Deserialize)]
+struct RigidBodyTreeData {
+    name:    String,
+    gravity: [Scalar; 3],
+    bodies:  Vec<BodyData>,
+}
+
+impl<'a> From<&'a RigidBodyTree> for RigidBodyTreeData {
+    fn from(tree: &'a RigidBodyTree) -> Self {
+        let mut bodies = Vec::new();
+        if let Some(base) = &tree.base {
+            let mut order = vec![std::rc::Rc::clone(base)];
+            order.extend(tree.bodies.iter().cloned());
+
+            bodies.push(BodyData { link: base.borrow().link.clone(), joint: None, parent: None });
+            for parent in order.iter() {
+                let parent_name = &parent.borrow().link.name;
+                for child_name in &tree.children[parent_name] {
+                    let child = tree.body_name2ptr[child_name].borrow();
+                    bodies.push(BodyData {
+                        link: child.link.clone(),
+                        joint: Some(child.joint.clone()),
+                        parent: Some(parent_name.clone()),
+                    });
+                }
+            }
+        }
+
+        RigidBodyTreeData {
+            name: tree.name.clone(),
+            gravity: vec3(&tree.gravity),
+            bodies,
+        }
+    }
+}
+
+impl RigidBodyTreeData {
+
+    /// Checks that the bodies form a tree before building it.
+    fn into_tree(self) -> Result<RigidBodyTree, String> {
+        let mut names = HashSet::new();
+        for body in &self.bodies {
+            if !names.insert(body.link.name.clone()) {
+                return Err(format!("duplicate link '{}'", body.link.name));
+            }
+        }
+
+        let mut links = Vec::new();
+        let mut joints = Vec::new();
+        let mut num_base = 0;
+        for body in self.bodies {
+            match (body.joint, body.parent) {
+                (Some(joint), Some(parent)) => {
+                    if !names.contains(&parent) {
+                        return Err(format!("parent link '{}' of '{}' not found", parent, body.link.name));
+                    }
+                    joints.push((joint, parent, body.link.name.clone()));
+                },
+                (None, None) => num_base += 1,
+                _ => return Err(format!("link '{}' needs both a joint and a parent", body.link.name)),
+            }
+            links.push(body.link);
+        }
+        if num_base != 1 {
+            return Err(format!("expected a single base link, found {}", num_base));
+        }
+
+        let mut tree = RigidBodyTree::from_links_and_joints(&self.name, links, joints)?;
+        if tree.bodies.len() + 1 != names.len() {
+            return Err("the links are not connected to the base".to_owned());
+        }
+        tree.set_gravity(Vector3f::from_column_slice(&self.gravity));
+        Ok(tree)
+    }
+}
+
+impl Serialize for RigidBodyTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_versioned(RigidBodyTreeData::from(self), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RigidBodyTree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_versioned::<RigidBodyTreeData, D>(deserializer)?.into_tree().map_err(D::Error::custom)
+    }
+}
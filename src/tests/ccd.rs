@@ -1,5 +1,5 @@
 use crate::ccd::*;
-use crate::math::{Vector3f, Matrix3f, Scalar};
+use crate::math::{Vector3f, Matrix3f, Scalar, Isometry3f};
 use crate::utils::rotm2quat;
 use crate::robotics::axang2rotm;
 use std::f64::consts::{FRAC_PI_3, FRAC_PI_4, PI};
@@ -458,4 +458,504 @@ fn test_box_box_aligned_z() {
 
         box_1.pos[2] += 0.1;
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_obb_separation() {
+    let a = OBB {
+        pos: Vector3f::zeros(),
+        r: Vector3f::new(1., 1., 1.),
+        axis: Matrix3f::identity(),
+        scene_node: None,
+    };
+    let mut b = OBB {
+        pos: Vector3f::new(5., 0., 0.),
+        r: Vector3f::new(1., 2., 3.),
+        axis: Matrix3f::identity(),
+        scene_node: None,
+    };
+    assert_relative_eq!(a.separation(&b), 3., epsilon = 1e-12);
+
+    // rotated by 45 degrees about z: corner points towards a
+    b.axis = axang2rotm(Vector3f::new(0., 0., 1.), FRAC_PI_4);
+    b.r = Vector3f::new(1., 1., 1.);
+    assert_relative_eq!(a.separation(&b), 4. - 2_f64.sqrt(), epsilon = 1e-12);
+
+    b.pos = Vector3f::new(1.5, 0.5, 0.);
+    assert!(a.intersects(&b, 0.));
+    assert_eq!(a.separation(&b), 0.);
+}
+
+#[test]
+fn test_gjk_distance() {
+    let ccd = CCDCriteria::default();
+    let mut res = CCDDistance::new();
+
+    // spheres along a diagonal
+    let sphere_1 = Sphere {
+        pos: Vector3f::new(1., 1., 1.),
+        rotm: axang2rotm(Vector3f::new(1., 2., 3.), 0.7),
+        radius: 0.5,
+    };
+    let sphere_2 = Sphere {
+        pos: Vector3f::new(1., 1., 1.) + Vector3f::new(1., -2., 2.) / 1.5,
+        rotm: Matrix3f::identity(),
+        radius: 0.3,
+    };
+    assert!(gjk_distance(&sphere_1, &sphere_2, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 1.2, epsilon = 1e-6);
+    let dir = Vector3f::new(1., -2., 2.) / 3.;
+    assert_relative_eq!(res.point1, sphere_1.pos + 0.5 * dir, epsilon = 1e-3);
+    assert_relative_eq!(res.point2, sphere_2.pos - 0.3 * dir, epsilon = 1e-3);
+
+    // box with a corner towards a face, as for the OBB separation
+    let box_1 = Box {
+        pos: Vector3f::zeros(),
+        rotm: Matrix3f::identity(),
+        dim: Vector3f::new(2., 2., 2.),
+    };
+    let box_2 = Box {
+        pos: Vector3f::new(5., 0.3, 0.),
+        rotm: axang2rotm(Vector3f::new(0., 0., 1.), FRAC_PI_4),
+        dim: Vector3f::new(2., 2., 2.),
+    };
+    assert!(gjk_distance(&box_1, &box_2, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 4. - 2_f64.sqrt(), epsilon = 1e-6);
+    assert_relative_eq!(res.point2, Vector3f::new(5. - 2_f64.sqrt(), 0.3, res.point2[2]), epsilon = 1e-9);
+    assert_relative_eq!(res.point1[0], 1., epsilon = 1e-9);
+    assert_relative_eq!((res.point1 - res.point2).norm(), res.distance, epsilon = 1e-12);
+    assert!(!gjk_intersect(&box_1, &box_2, &ccd));
+
+    // cylinder above a box
+    let cylinder = Cylinder {
+        pos: Vector3f::new(0.2, -0.1, 2.),
+        rotm: Matrix3f::identity(),
+        radius: 0.4,
+        height: 0.7,
+    };
+    assert!(gjk_distance(&box_1, &cylinder, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 2. - 1. - 0.35, epsilon = 1e-6);
+
+    // intersecting objects
+    let sphere_3 = Sphere { pos: Vector3f::new(0.9, 0., 0.), rotm: Matrix3f::identity(), radius: 0.5 };
+    assert!(!gjk_distance(&box_1, &sphere_3, &ccd, &mut res));
+    assert_eq!(res.distance, 0.);
+    assert!(gjk_intersect(&box_1, &sphere_3, &ccd));
+}
+
+#[test]
+fn test_gjk_intersect_sphere_sweep() {
+    let mut sphere_1 = Sphere { pos: Vector3f::new(-5., 0., 0.), rotm: Matrix3f::identity(), radius: 0.35 };
+    let sphere_2 = Sphere { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.5 };
+    let ccd = CCDCriteria::default();
+    for i in 0..100 {
+        assert_eq!(gjk_intersect(&sphere_1, &sphere_2, &ccd), !(i < 42 || i > 58));
+        sphere_1.pos[0] += 0.1;
+    }
+}
+
+/// Checks that translating obj2 by the penetration separates the objects.
+fn assert_penetration_resolved(obj1: &dyn CCDObject, obj2: &mut Cylinder, res: &CCDResult) {
+    let ccd = CCDCriteria::default();
+    let pos = obj2.pos;
+    obj2.pos = pos + res.dir * (res.depth - 1e-3);
+    assert!(gjk_intersect(obj1, obj2, &ccd));
+    obj2.pos = pos + res.dir * (res.depth + 1e-3);
+    assert!(!gjk_intersect(obj1, obj2, &ccd));
+    obj2.pos = pos;
+}
+
+#[test]
+fn test_gjk_penetration() {
+    let ccd = CCDCriteria::default();
+    let mut res = CCDResult::new();
+
+    // spheres
+    let sphere_1 = Sphere { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.5 };
+    let mut sphere_2 = Sphere { pos: Vector3f::new(0.6, 0., 0.), rotm: Matrix3f::identity(), radius: 0.3 };
+    assert!(gjk_penetration(&sphere_1, &sphere_2, &ccd, &mut res));
+    assert_relative_eq!(res.depth, 0.2, epsilon = 1e-3);
+    assert_relative_eq!(res.dir, Vector3f::new(1., 0., 0.), epsilon = 1e-2);
+    sphere_2.pos[0] = 0.81;
+    assert!(!gjk_penetration(&sphere_1, &sphere_2, &ccd, &mut res));
+
+    // boxes overlapping along x
+    let box_1 = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 2.) };
+    let box_2 = Box { pos: Vector3f::new(1.5, 0.2, -0.1), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 2.) };
+    assert!(gjk_penetration(&box_1, &box_2, &ccd, &mut res));
+    assert_relative_eq!(res.depth, 0.5, epsilon = 1e-6);
+    assert_relative_eq!(res.dir, Vector3f::new(1., 0., 0.), epsilon = 1e-6);
+    assert_relative_eq!(res.pos[0], 0.75, epsilon = 1e-6);
+
+    // same configurations as the MPR box-cylinder test
+    let mut obj_box = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(0.5, 1., 1.5) };
+    let mut obj_cylinder = Cylinder { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.4, height: 0.7 };
+    let configs = vec![
+        (Vector3f::new(0.1, 0., 0.), Vector3f::new(0., 1., 0.), 0.),
+        (Vector3f::new(0.6, 0., 0.), Vector3f::new(0., 1., 0.), 0.),
+        (Vector3f::new(0.6, 0.6, 0.), Vector3f::new(0., 1., 0.), 0.),
+        (Vector3f::new(0.6, 0.6, 0.5), Vector3f::new(0., 1., 0.), 0.),
+        (Vector3f::new(0.6, 0., 0.5), Vector3f::new(0., 1., 0.), FRAC_PI_3),
+        (Vector3f::new(0.6, 0., 0.5), Vector3f::new(0.67, 1.1, 0.12), FRAC_PI_4),
+    ];
+    for (pos, axis, angle) in configs {
+        obj_cylinder.pos = pos;
+        obj_cylinder.rotm = axang2rotm(axis, angle);
+        assert!(gjk_penetration(&obj_box, &obj_cylinder, &ccd, &mut res));
+        assert!(res.depth > 0.);
+        assert_penetration_resolved(&obj_box, &mut obj_cylinder, &res);
+    }
+    obj_box.pos = Vector3f::new(0.9, 0.8, 0.5);
+    obj_box.rotm = axang2rotm(Vector3f::new(1., 1., 0.), -FRAC_PI_4);
+    obj_cylinder.rotm = axang2rotm(Vector3f::new(-0.1, 2.2, -1.), PI as Scalar / 5.);
+    assert!(gjk_penetration(&obj_box, &obj_cylinder, &ccd, &mut res));
+    assert_penetration_resolved(&obj_box, &mut obj_cylinder, &res);
+
+    // EPA finds a penetration no deeper than MPR
+    let mut mpr = CCDResult::new();
+    assert!(mpr_penetration(&obj_box, &obj_cylinder, &ccd, &mut mpr));
+    assert!(res.depth <= mpr.depth + ccd.epa_tolerance);
+
+    // polytope too flat for EPA: same penetration as MPR
+    let plate_1 = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 1e-8) };
+    let plate_2 = Box { pos: Vector3f::new(1.5, 0.2, 0.), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 1e-8) };
+    assert!(gjk_penetration(&plate_1, &plate_2, &ccd, &mut res));
+    assert!(mpr_penetration(&plate_1, &plate_2, &ccd, &mut mpr));
+    assert!(res.depth > 0.);
+    assert_eq!(res.depth, mpr.depth);
+    assert_eq!(res.dir, mpr.dir);
+}
+
+/// Directions spread over the unit sphere (golden spiral).
+fn sphere_directions(n: usize) -> Vec<Vector3f> {
+    let golden = PI * (3. - 5_f64.sqrt());
+    (0..n).map(|i| {
+        let z = 1. - 2. * (i as Scalar + 0.5) / n as Scalar;
+        let r = (1. - z * z).sqrt();
+        let theta = golden * i as Scalar;
+        Vector3f::new(r * theta.cos(), r * theta.sin(), z)
+    }).collect()
+}
+
+#[test]
+fn test_convex_hull_points() {
+    // cube corners, with points inside and on the faces
+    let mut points = Vec::new();
+    for i in 0..5 {
+        for j in 0..5 {
+            for k in 0..5 {
+                points.push(Vector3f::new(i as Scalar, j as Scalar, k as Scalar) * 0.25 - Vector3f::repeat(0.5));
+            }
+        }
+    }
+    let mut hull = ConvexHull::new(&points).unwrap();
+    assert_eq!(hull.vertices.len(), 8);
+    assert_eq!(hull.faces.len(), 12);
+    for v in &hull.vertices {
+        assert_relative_eq!(v.abs(), Vector3f::repeat(0.5));
+    }
+    let mut mesh = hull.to_indexed_mesh();
+    assert!(mesh.repair(&crate::utils::RepairCriteria::default()).watertight);
+    assert_eq!(mesh.orient_faces(), 0);
+    assert_relative_eq!(mesh.volume(), 1., epsilon = 1e-6);
+
+    // the support matches the box one for any pose
+    hull.pos = Vector3f::new(0.3, -1., 2.);
+    hull.rotm = axang2rotm(Vector3f::new(1., -2., 0.5), 0.8);
+    let obj_box = Box { pos: hull.pos, rotm: hull.rotm, dim: Vector3f::repeat(1.) };
+    for dir in sphere_directions(200) {
+        assert_relative_eq!(hull.support(&dir).dot(&dir), obj_box.support(&dir).dot(&dir), epsilon = 1e-12);
+    }
+    assert_relative_eq!(hull.center(), hull.pos, epsilon = 1e-12);
+
+    // coplanar points have no hull
+    let flat: Vec<Vector3f> = points.iter().map(|p| Vector3f::new(p[0], p[1], 0.)).collect();
+    assert!(ConvexHull::new(&flat).is_none());
+    assert!(ConvexHull::new(&points[..3].to_vec()).is_none());
+
+    // nor do points with a non-finite coordinate
+    let mut invalid = points.clone();
+    invalid[2][1] = Scalar::NAN;
+    assert!(ConvexHull::new(&invalid).is_none());
+    invalid[2][1] = Scalar::INFINITY;
+    assert!(ConvexHull::new(&invalid).is_none());
+}
+
+#[test]
+fn test_convex_hull_sphere() {
+    // points on a sphere are all hull vertices
+    let points: Vec<Vector3f> = sphere_directions(300).iter().map(|d| d * 2.).collect();
+    let hull = ConvexHull::new(&points).unwrap();
+    assert_eq!(hull.vertices.len(), 300);
+    assert_eq!(hull.faces.len(), 2 * 300 - 4);
+    for dir in sphere_directions(101) {
+        let expected = points.iter().map(|p| p.dot(&dir)).fold(Scalar::NEG_INFINITY, Scalar::max);
+        assert_relative_eq!(hull.support(&dir).dot(&dir), expected, epsilon = 1e-12);
+    }
+
+    // GJK against a sphere, from outside and inside
+    let ccd = CCDCriteria::default();
+    let mut res = CCDDistance::new();
+    let sphere = Sphere { pos: Vector3f::new(0., 0., 3.), rotm: Matrix3f::identity(), radius: 0.5 };
+    assert!(gjk_distance(&hull, &sphere, &ccd, &mut res));
+    assert!(res.distance > 0.5 - 1e-6 && res.distance < 0.5 + 0.05);
+    let sphere = Sphere { pos: Vector3f::new(0., 0., 1.), rotm: Matrix3f::identity(), radius: 0.5 };
+    assert!(gjk_intersect(&hull, &sphere, &ccd));
+    assert!(mpr_intersect(&hull, &sphere, &ccd));
+}
+
+#[test]
+fn test_convex_hull_mesh() {
+    let filename = "resource/mesh/universal/forearm.stl";
+    let mesh = crate::utils::load_indexed_mesh(filename).unwrap();
+    let hull = ConvexHull::from_indexed_mesh(&mesh).unwrap();
+    assert!(hull.vertices.len() <= mesh.vertices.len());
+
+    // the hull has the support of the mesh vertices
+    let points: Vec<Vector3f> = mesh.vertices.iter()
+        .map(|v| Vector3f::new(v[0] as Scalar, v[1] as Scalar, v[2] as Scalar))
+        .collect();
+    for dir in sphere_directions(50) {
+        let expected = points.iter().map(|p| p.dot(&dir)).fold(Scalar::NEG_INFINITY, Scalar::max);
+        assert_relative_eq!(hull.support(&dir).dot(&dir), expected, epsilon = 1e-9);
+    }
+    for v in &hull.vertices {
+        assert!(points.contains(v));
+    }
+    let hull_mesh = hull.to_indexed_mesh();
+    assert!(hull_mesh.volume() >= mesh.volume() / 2.);
+
+    // same hull from the rendering mesh
+    let render = crate::utils::load_mesh(filename).unwrap();
+    let hull2 = ConvexHull::from_mesh(&render.borrow()).unwrap();
+    assert_eq!(hull2.vertices.len(), hull.vertices.len());
+    for dir in sphere_directions(50) {
+        assert_relative_eq!(hull.support(&dir), hull2.support(&dir), epsilon = 1e-6);
+    }
+}
+
+#[test]
+fn test_ccd_primitives_support() {
+    let rotm = axang2rotm(Vector3f::new(0.3, -1., 0.4), 1.1);
+    let pos = Vector3f::new(0.5, 1., -2.);
+    let dirs = sphere_directions(100);
+
+    // capsule as a segment rounded by a sphere
+    let capsule = Capsule { pos, rotm, radius: 0.3, height: 1.2 };
+    let axis = rotm * Vector3f::new(0., 0., 0.6);
+    let swept = MinkowskiSum {
+        obj1: Segment { points: [pos - axis, pos + axis] },
+        obj2: Sphere { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.3 },
+    };
+    for dir in &dirs {
+        assert_relative_eq!(capsule.support(dir).dot(dir), swept.support(dir).dot(dir), epsilon = 1e-12);
+    }
+    assert_relative_eq!(swept.center(), pos, epsilon = 1e-12);
+
+    // cone against the hull of its apex and base circle
+    let cone = Cone { pos, rotm, radius: 0.4, height: 1. };
+    let mut points: Vec<Vector3f> = (0..720).map(|i| {
+        let t = i as Scalar * PI / 360.;
+        Vector3f::new(0.4 * t.cos(), 0.4 * t.sin(), -0.5)
+    }).collect();
+    points.push(Vector3f::new(0., 0., 0.5));
+    let polytope = Polytope { pos, rotm, vertices: points };
+    for dir in &dirs {
+        let d = cone.support(dir).dot(dir);
+        assert!(d >= polytope.support(dir).dot(dir) - 1e-12);
+        assert!(d <= polytope.support(dir).dot(dir) + 1e-5);
+    }
+    assert_relative_eq!(cone.center(), pos + rotm * Vector3f::new(0., 0., -0.25), epsilon = 1e-12);
+
+    // ellipsoid support on the surface, with the normal along the direction
+    let radii = Vector3f::new(0.5, 1., 2.);
+    let ellipsoid = Ellipsoid { pos, rotm, radii };
+    for dir in &dirs {
+        let p = rotm.transpose() * (ellipsoid.support(dir) - pos);
+        let q = p.component_div(&radii);
+        assert_relative_eq!(q.norm(), 1., epsilon = 1e-12);
+        let normal = rotm * q.component_div(&radii).normalize();
+        assert_relative_eq!(normal, *dir, epsilon = 1e-12);
+    }
+
+    // polytope and convex hull of the same vertices
+    let vertices = vec![
+        Vector3f::new(0., 0., 0.), Vector3f::new(1., 0., 0.), Vector3f::new(0., 2., 0.),
+        Vector3f::new(0., 0., 3.), Vector3f::new(0.2, 0.2, 0.2),
+    ];
+    let polytope = Polytope { pos, rotm, vertices: vertices.clone() };
+    let mut hull = ConvexHull::new(&vertices).unwrap();
+    hull.pos = pos;
+    hull.rotm = rotm;
+    for dir in &dirs {
+        assert_relative_eq!(polytope.support(dir), hull.support(dir), epsilon = 1e-12);
+    }
+
+    // transformed wrapper and posed box
+    let tform = Isometry3f::new(pos, Vector3f::new(0.3, -0.2, 0.9));
+    let moved = Transformed {
+        tform,
+        obj: Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(1., 2., 3.) },
+    };
+    let posed = Box { pos, rotm: *tform.rotation.to_rotation_matrix().matrix(), dim: Vector3f::new(1., 2., 3.) };
+    for dir in &dirs {
+        assert_relative_eq!(moved.support(dir), posed.support(dir), epsilon = 1e-12);
+    }
+    assert_relative_eq!(moved.center(), pos, epsilon = 1e-12);
+}
+
+#[test]
+fn test_ccd_primitives_distance() {
+    let ccd = CCDCriteria::default();
+    let mut res = CCDDistance::new();
+    let point = |p: Vector3f| Sphere { pos: p, rotm: Matrix3f::identity(), radius: 0. };
+
+    // point above a triangle, and beside a segment
+    let triangle = Triangle { vertices: [Vector3f::zeros(), Vector3f::x(), Vector3f::y()] };
+    assert!(gjk_distance(&triangle, &point(Vector3f::new(0.2, 0.3, 0.5)), &ccd, &mut res));
+    assert_relative_eq!(res.distance, 0.5, epsilon = 1e-9);
+    assert_relative_eq!(res.point1, Vector3f::new(0.2, 0.3, 0.), epsilon = 1e-9);
+    let segment = Segment { points: [Vector3f::new(0., 0., -1.), Vector3f::new(0., 0., 1.)] };
+    assert!(gjk_distance(&segment, &point(Vector3f::new(2., 0., 3.)), &ccd, &mut res));
+    assert_relative_eq!(res.distance, 2_f64.sqrt() * 2., epsilon = 1e-9);
+
+    // capsules side by side, and cone apex under a box
+    let capsule_1 = Capsule { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.2, height: 1. };
+    let capsule_2 = Capsule {
+        pos: Vector3f::new(1., 0., 0.),
+        rotm: axang2rotm(Vector3f::x(), FRAC_PI_4),
+        radius: 0.3,
+        height: 1.,
+    };
+    assert!(gjk_distance(&capsule_1, &capsule_2, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 0.5, epsilon = 1e-6);
+    let cone = Cone { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 1., height: 2. };
+    let obj_box = Box { pos: Vector3f::new(0.1, 0.2, 1.5), rotm: Matrix3f::identity(), dim: Vector3f::repeat(0.6) };
+    assert!(gjk_distance(&cone, &obj_box, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 0.2, epsilon = 1e-6);
+    assert_relative_eq!(res.point1, Vector3f::new(0., 0., 1.), epsilon = 1e-6);
+}
+
+#[test]
+fn test_ccd_geometry_objects() {
+    use crate::robotics::{Collision, Geometry};
+
+    let ccd = CCDCriteria::default();
+    let mut res = CCDDistance::new();
+    let capsule = Collision::new(
+        "capsule".to_string(), Isometry3f::translation(0., 0., 2.),
+        Geometry::Capsule { radius: 0.5, length: 1. }).to_ccd_object().unwrap();
+    let cube = Collision::new(
+        "box".to_string(), Isometry3f::identity(),
+        Geometry::Box { depth: 1., width: 1., height: 1. }).to_ccd_object().unwrap();
+    assert!(gjk_distance(&cube, &capsule, &ccd, &mut res));
+    assert_relative_eq!(res.distance, 0.5, epsilon = 1e-6);
+    assert_relative_eq!(res.point2, Vector3f::new(res.point2[0], res.point2[1], 1.), epsilon = 1e-6);
+
+    let mesh = Geometry::Mesh {
+        filename: "resource/mesh/universal/base.stl".to_string(),
+        scale: crate::math::Vector3f32::new(2., 2., 2.),
+        mesh: crate::utils::load_mesh("resource/mesh/universal/base.stl").unwrap(),
+    }.to_ccd_object().unwrap();
+    let indexed = crate::utils::load_indexed_mesh("resource/mesh/universal/base.stl").unwrap();
+    let top = indexed.vertices.iter().map(|v| v[2] as Scalar).fold(Scalar::NEG_INFINITY, Scalar::max);
+    assert_relative_eq!(mesh.support(&Vector3f::z())[2], 2. * top, epsilon = 1e-6);
+}
+
+#[test]
+fn test_time_of_impact_linear() {
+    let ccd = CCDCriteria::default();
+    let mut impact = CCDImpact::new();
+
+    // fast small sphere tunneling through a thin plate in one step
+    let ball = Sphere { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.05 };
+    let plate = Box { pos: Vector3f::new(0.5, 0., 0.), rotm: Matrix3f::identity(), dim: Vector3f::new(0.002, 1., 1.) };
+    let motion = CCDMotion::new(Isometry3f::identity(), Vector3f::new(100., 0., 0.), Vector3f::zeros());
+    let fixed = CCDMotion::fixed(Isometry3f::identity());
+    let end = Transformed { tform: motion.pose(0.01), obj: &ball };
+    assert!(!gjk_intersect(&ball, &plate, &ccd));
+    assert!(!gjk_intersect(&end, &plate, &ccd));
+    assert!(time_of_impact(&ball, &motion, &plate, &fixed, 0.01, &ccd, &mut impact));
+    assert_relative_eq!(impact.time, 0.449 / 100., epsilon = 1e-5);
+    assert_relative_eq!(impact.normal, Vector3f::x(), epsilon = 1e-6);
+    assert_relative_eq!(impact.point[0], 0.499, epsilon = 1e-4);
+
+    // too short, and moving away or sideways
+    assert!(!time_of_impact(&ball, &motion, &plate, &fixed, 0.004, &ccd, &mut impact));
+    let away = CCDMotion::new(Isometry3f::identity(), Vector3f::new(-100., 0., 0.), Vector3f::zeros());
+    assert!(!time_of_impact(&ball, &away, &plate, &fixed, 1., &ccd, &mut impact));
+    let sideways = CCDMotion::new(Isometry3f::identity(), Vector3f::new(0., 0., 10.), Vector3f::zeros());
+    assert!(!time_of_impact(&ball, &sideways, &plate, &fixed, 1., &ccd, &mut impact));
+
+    // both moving, and overlapping at the start
+    let plate_motion = CCDMotion::new(Isometry3f::identity(), Vector3f::new(-100., 0., 0.), Vector3f::zeros());
+    assert!(time_of_impact(&ball, &motion, &plate, &plate_motion, 0.01, &ccd, &mut impact));
+    assert_relative_eq!(impact.time, 0.449 / 200., epsilon = 1e-5);
+    let start = CCDMotion::fixed(Isometry3f::translation(0.47, 0., 0.));
+    assert!(time_of_impact(&ball, &start, &plate, &fixed, 0.01, &ccd, &mut impact));
+    assert_eq!(impact.time, 0.);
+    assert_relative_eq!(impact.normal, Vector3f::x(), epsilon = 1e-6);
+}
+
+#[test]
+fn test_time_of_impact_rotation() {
+    let ccd = CCDCriteria::default();
+    let mut impact = CCDImpact::new();
+
+    // rod along x spinning about z towards a ball on the y axis: contact
+    // when the side of the rod is at the radius of the ball
+    let rod = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 0.02, 0.02) };
+    let ball = Sphere { pos: Vector3f::new(0., 0.8, 0.), rotm: Matrix3f::identity(), radius: 0.1 };
+    let spin = CCDMotion::new(Isometry3f::identity(), Vector3f::zeros(), Vector3f::new(0., 0., 2.));
+    let fixed = CCDMotion::fixed(Isometry3f::identity());
+    assert!(time_of_impact(&rod, &spin, &ball, &fixed, 1., &ccd, &mut impact));
+    let angle = (0.11 as Scalar / 0.8).acos();
+    assert_relative_eq!(impact.time, angle / 2., epsilon = 1e-3);
+    assert_relative_eq!(impact.normal, Vector3f::new(-angle.sin(), angle.cos(), 0.), epsilon = 1e-3);
+
+    // the same motion given by the start and end poses of a step
+    let step = CCDMotion::from_poses(&Isometry3f::identity(), &spin.pose(0.5), 0.5).unwrap();
+    assert_relative_eq!(step.angvel, spin.angvel, epsilon = 1e-12);
+    assert!(step.pose(0.25).translation.vector.norm() < 1e-12);
+    assert!(!time_of_impact(&rod, &step, &ball, &fixed, 0.5, &ccd, &mut impact));
+    assert!(CCDMotion::from_poses(&Isometry3f::identity(), &spin.pose(0.5), 0.).is_none());
+
+    // contact not reached within the iterations: conservative impact, with
+    // the objects still apart
+    let few = CCDCriteria { max_iterations: 3, ..CCDCriteria::default() };
+    assert!(time_of_impact(&rod, &spin, &ball, &fixed, 1., &few, &mut impact));
+    assert!(impact.time > 0. && impact.time < angle / 2.);
+    assert_relative_eq!(impact.normal.norm(), 1., epsilon = 1e-12);
+    let moved = Transformed { tform: spin.pose(impact.time), obj: &rod };
+    assert!(!gjk_intersect(&moved, &ball, &ccd));
+}
+
+#[test]
+fn test_time_of_impact_degenerate() {
+    let ccd = CCDCriteria::default();
+    let mut impact = CCDImpact::new();
+    let fixed = CCDMotion::fixed(Isometry3f::identity());
+
+    // thin plates overlapping at time 0, along the offset of their centers
+    let plate_1 = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 1e-8) };
+    let plate_2 = Box { pos: Vector3f::new(1.5, 0.2, 0.), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 1e-8) };
+    assert!(time_of_impact(&plate_1, &fixed, &plate_2, &fixed, 1., &ccd, &mut impact));
+    assert_eq!(impact.time, 0.);
+    assert_relative_eq!(impact.normal.norm(), 1., epsilon = 1e-12);
+
+    // flat plates: no penetration direction, the centers give the normal
+    let flat_1 = Box { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 0.) };
+    let flat_2 = Box { pos: Vector3f::new(1.5, 0., 0.), rotm: Matrix3f::identity(), dim: Vector3f::new(2., 2., 0.) };
+    assert!(time_of_impact(&flat_1, &fixed, &flat_2, &fixed, 1., &ccd, &mut impact));
+    assert_eq!(impact.time, 0.);
+    assert_relative_eq!(impact.normal, Vector3f::x(), epsilon = 1e-12);
+
+    // touching objects, with coinciding closest points
+    let ball_1 = Sphere { pos: Vector3f::zeros(), rotm: Matrix3f::identity(), radius: 0.5 };
+    let ball_2 = Sphere { pos: Vector3f::new(0., 1., 0.), rotm: Matrix3f::identity(), radius: 0.5 };
+    let motion = CCDMotion::new(Isometry3f::identity(), Vector3f::new(0., 1., 0.), Vector3f::zeros());
+    assert!(time_of_impact(&ball_1, &motion, &ball_2, &fixed, 1., &ccd, &mut impact));
+    assert_eq!(impact.time, 0.);
+    assert!(impact.normal.iter().all(|x| x.is_finite()));
+    assert_relative_eq!(impact.normal.norm(), 1., epsilon = 1e-6);
+}
@@ -1,5 +1,8 @@
 use crate::geometry::DoubleEdgeList;
 use crate::math::Vector2f;
+use crate::utils::*;
+use crate::tests::cube;
+use std::rc::Rc;
 
 #[test]
 fn test_dcel_init() {
@@ -22,4 +25,125 @@ fn test_dcel_init() {
     dcel.initialize(&vertices, &edges, &face);
 
     print!("Hello");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_dcel_from_faces() {
+    // tetrahedron
+    let vertices = vec![0, 1, 2, 3];
+    let faces = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![2, 0, 3]];
+    let dcel = DoubleEdgeList::from_faces(&vertices, &faces, &(), &vec!['a', 'b', 'c', 'd']).unwrap();
+    assert_eq!(dcel.edges().len(), 12);
+    assert!(dcel.is_closed());
+    for edge in dcel.edges() {
+        assert!(edge.twin().twin() == *edge);
+        assert!(edge.twin().origin() == edge.get_destination());
+        assert!(edge.get_previous().next() == *edge);
+    }
+    for vertex in dcel.vertices() {
+        assert_eq!(vertex.leaving_edges().count(), 3);
+        assert_eq!(vertex.faces().count(), 3);
+        assert!(!vertex.is_boundary());
+        let mut ring: Vec<usize> = vertex.one_ring().map(|v| *v.element()).collect();
+        ring.sort();
+        let expected: Vec<usize> = (0..4).filter(|i| i != vertex.element()).collect();
+        assert_eq!(ring, expected);
+    }
+    let face = dcel.faces()[1];
+    assert_eq!(*face.element(), 'b');
+    assert_eq!(face.get_edge_count(), 3);
+    assert_eq!(face.vertices().map(|v| *v.element()).collect::<Vec<_>>(), vec![0, 1, 3]);
+    assert_eq!(face.neighbors().count(), 3);
+
+    // inconsistent orientation, or an edge shared by three faces
+    let flipped = vec![vec![0, 1, 2], vec![0, 1, 3], vec![1, 2, 3], vec![2, 0, 3]];
+    assert!(DoubleEdgeList::from_faces(&vertices, &flipped, &(), &vec![(); 4]).is_none());
+    let fin = vec![vec![0, 1, 2], vec![1, 0, 3], vec![1, 0, 4]];
+    assert!(DoubleEdgeList::from_faces(&vec![0; 5], &fin, &(), &vec![(); 3]).is_none());
+    assert!(DoubleEdgeList::from_faces(&vertices, &vec![vec![0, 1]], &(), &vec![()]).is_none());
+}
+
+#[test]
+fn test_dcel_boundary() {
+    // square of two triangles, with a polygon on the side
+    let vertices = vec![0, 1, 2, 3, 4, 5];
+    let faces = vec![vec![0, 1, 2], vec![0, 2, 3], vec![1, 4, 5, 2]];
+    let dcel = DoubleEdgeList::from_faces(&vertices, &faces, &0., &vec![0, 1, 2]).unwrap();
+    assert!(!dcel.is_closed());
+    assert_eq!(dcel.edges().len(), 2 * 8);
+
+    // a single hole along the six outer edges
+    let boundary = dcel.boundary_edges();
+    assert_eq!(boundary.len(), 6);
+    let hole: Vec<usize> = boundary[0].loop_edges().map(|e| *e.origin().element()).collect();
+    assert_eq!(hole.len(), 6);
+    for edge in &boundary {
+        assert!(edge.next().is_boundary());
+        assert!(edge.twin().face().element() < &3);
+    }
+
+    // the fans around the boundary vertices are complete
+    let counts: Vec<usize> = dcel.vertices().iter().map(|v| v.leaving_edges().count()).collect();
+    assert_eq!(counts, vec![3, 3, 4, 2, 2, 2]);
+    let faces: Vec<usize> = dcel.vertices().iter().map(|v| v.faces().count()).collect();
+    assert_eq!(faces, vec![2, 2, 3, 1, 1, 1]);
+    assert!(dcel.vertices().iter().all(|v| v.is_boundary()));
+    assert!(dcel.vertices()[0].get_edge_to(dcel.vertices()[2]).face().element() < &2);
+}
+
+#[test]
+fn test_dcel_from_mesh() {
+    // unit cube as a triangle soup
+    let cube = cube();
+    let mut triangles: Vec<Triangle> = cube.faces.iter().map(|face| Triangle {
+        normal: face.normal,
+        vertices: [
+            cube.vertices[face.vertices[0]],
+            cube.vertices[face.vertices[1]],
+            cube.vertices[face.vertices[2]],
+        ],
+    }).collect();
+    let dcel = DoubleEdgeList::from_triangles(&triangles, 1e-6).unwrap();
+    assert_eq!(dcel.vertices().len(), 8);
+    assert_eq!(dcel.faces().len(), 12);
+    assert!(dcel.is_closed());
+    // Euler characteristic of a sphere
+    assert_eq!(dcel.vertices().len() + dcel.faces().len() - dcel.edges().len() / 2, 2);
+
+    // sliver collapsed by the welding
+    let sliver = [cube.vertices[0], [1e-7, 0., 0.], cube.vertices[1]];
+    triangles.insert(3, Triangle { normal: [0., 0., 1.], vertices: sliver });
+    let dcel = DoubleEdgeList::from_triangles(&triangles, 1e-6).unwrap();
+    assert_eq!(dcel.faces().len(), 12);
+    assert!(dcel.is_closed());
+    let elements: Vec<usize> = dcel.faces().iter().map(|face| *face.element()).collect();
+    assert_eq!(elements, (0..13).filter(|&i| i != 3).collect::<Vec<usize>>());
+
+    let mut mesh = load_indexed_mesh("resource/mesh/universal/base.stl").unwrap();
+    mesh.repair(&RepairCriteria::default());
+    let dcel = DoubleEdgeList::from_indexed_mesh(&mesh).unwrap();
+    assert!(dcel.is_closed());
+    for (f, face) in dcel.faces().iter().enumerate() {
+        assert_eq!(*face.element(), f);
+        let vertices: Vec<Vertex> = face.vertices().map(|v| *v.element()).collect();
+        let expected: Vec<Vertex> = mesh.faces[f].vertices.iter().map(|&i| mesh.vertices[i]).collect();
+        assert_eq!(vertices, expected);
+    }
+}
+
+#[test]
+fn test_dcel_drop() {
+    // the elements are dropped with the list, or with a partial build
+    let element = Rc::new(0);
+    let vertices = vec![Rc::clone(&element); 4];
+    let faces = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![2, 0, 3]];
+    let face_elements = vec![Rc::clone(&element); 4];
+    let dcel = DoubleEdgeList::from_faces(&vertices, &faces, &element, &face_elements).unwrap();
+    assert_eq!(Rc::strong_count(&element), 1 + 4 + 4 + (4 + 12 + 4));
+    drop(dcel);
+    assert_eq!(Rc::strong_count(&element), 1 + 4 + 4);
+
+    let flipped = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 3, 2]];
+    assert!(DoubleEdgeList::from_faces(&vertices, &flipped, &element, &face_elements[..3].to_vec()).is_none());
+    assert_eq!(Rc::strong_count(&element), 1 + 4 + 4);
+}
@@ -0,0 +1,165 @@
+use crate::ccd::*;
+use crate::math::{Vector3f, Matrix3f, Scalar, Isometry3f};
+use crate::utils::*;
+
+/// L-shaped prism: a 2x2 square with the upper right 1x1 square removed,
+/// extruded along z by 1, with outward faces.
+fn l_shape() -> IndexedMesh {
+    let outline: [[f32; 2]; 6] = [[0., 0.], [2., 0.], [2., 1.], [1., 1.], [1., 2.], [0., 2.]];
+    let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    for &z in [0., 1.].iter() {
+        for p in outline.iter() {
+            mesh.vertices.push([p[0], p[1], z]);
+        }
+    }
+    let push = |mesh: &mut IndexedMesh, a: usize, b: usize, c: usize| {
+        let normal = triangle_normal(&mesh.vertices[a], &mesh.vertices[b], &mesh.vertices[c]);
+        mesh.faces.push(IndexedTriangle { normal, vertices: [a, b, c] });
+    };
+    // caps, split into the two rectangles 0-1-2-3 + 0-3-4-5
+    for tri in [[0, 1, 2], [0, 2, 3], [0, 3, 4], [0, 4, 5]].iter() {
+        push(&mut mesh, tri[0], tri[2], tri[1]);
+        push(&mut mesh, tri[0] + 6, tri[1] + 6, tri[2] + 6);
+    }
+    for i in 0..6 {
+        let j = (i + 1) % 6;
+        push(&mut mesh, i, j, j + 6);
+        push(&mut mesh, i, j + 6, i + 6);
+    }
+    return mesh;
+}
+
+#[test]
+fn test_convex_decomposition() {
+    let mesh = l_shape();
+    let criteria = DecompositionCriteria::default();
+    let hulls = convex_decomposition(&mesh, &criteria);
+    assert!(hulls.len() >= 2 && hulls.len() <= criteria.max_hulls);
+
+    // the hulls cover the shape without filling the notch
+    let volume: Scalar = hulls.iter().map(|h| h.volume()).sum();
+    assert!((volume - 3.).abs() < 0.3, "volume {}", volume);
+    let notch = Vector3f::new(1.6, 1.6, 0.5);
+    for hull in &hulls {
+        assert!(!gjk_intersect(hull, &Sphere { pos: notch, rotm: Matrix3f::identity(), radius: 0.1 }, &CCDCriteria::default()));
+    }
+
+    // a convex mesh is a single hull
+    let mut cube = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    let tris = [[0, 2, 1], [0, 3, 2], [4, 5, 6], [4, 6, 7], [0, 1, 5], [0, 5, 4],
+                [2, 3, 7], [2, 7, 6], [1, 2, 6], [1, 6, 5], [0, 4, 7], [0, 7, 3]];
+    cube.vertices = vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.],
+                         [0., 0., 1.], [1., 0., 1.], [1., 1., 1.], [0., 1., 1.]];
+    for t in tris.iter() {
+        let normal = triangle_normal(&cube.vertices[t[0]], &cube.vertices[t[1]], &cube.vertices[t[2]]);
+        cube.faces.push(IndexedTriangle { normal, vertices: *t });
+    }
+    let hulls = convex_decomposition(&cube, &criteria);
+    assert_eq!(hulls.len(), 1);
+    assert!((hulls[0].volume() - 1.).abs() < 1e-6);
+
+    assert!(convex_decomposition(&IndexedMesh { vertices: Vec::new(), faces: Vec::new() }, &criteria).is_empty());
+    cube.vertices[6][2] = std::f32::NAN;
+    assert!(convex_decomposition(&cube, &criteria).is_empty());
+
+    // the maximum number of hulls holds for concave parts
+    let criteria = DecompositionCriteria { max_hulls: 1, ..criteria };
+    assert_eq!(convex_decomposition(&mesh, &criteria).len(), 1);
+}
+
+#[test]
+fn test_convex_decomposition_disjoint() {
+    // five disjoint L-shapes along x, more parts than hulls
+    let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    for i in 0..5 {
+        let part = l_shape();
+        let offset = mesh.vertices.len();
+        mesh.vertices.extend(part.vertices.iter().map(|v| [v[0] + 3. * i as f32, v[1], v[2]]));
+        mesh.faces.extend(part.faces.iter().map(|face| IndexedTriangle {
+            normal: face.normal,
+            vertices: [face.vertices[0] + offset, face.vertices[1] + offset, face.vertices[2] + offset],
+        }));
+    }
+
+    let criteria = DecompositionCriteria { resolution: 48, max_hulls: 3, ..DecompositionCriteria::default() };
+    let hulls = convex_decomposition(&mesh, &criteria);
+    assert!(!hulls.is_empty() && hulls.len() <= 3, "{} hulls", hulls.len());
+    let volume: Scalar = hulls.iter().map(|h| h.volume()).sum();
+    assert!(volume >= 15. - 1.5, "volume {}", volume);
+
+    let criteria = DecompositionCriteria { max_hulls: 10, ..criteria };
+    let hulls = convex_decomposition(&mesh, &criteria);
+    assert_eq!(hulls.len(), 10);
+}
+
+#[test]
+fn test_convex_hulls_cache() {
+    let dir = std::env::temp_dir();
+    let mesh_path = dir.join("crobot_decomposition_l.stl");
+    let cache_path = dir.join("crobot_decomposition_l.obj");
+    let _ = std::fs::remove_file(&cache_path);
+    save_indexed_mesh(&mesh_path, &l_shape()).unwrap();
+
+    let criteria = DecompositionCriteria { resolution: 16, ..DecompositionCriteria::default() };
+    let compound = Compound::from_mesh_file(&mesh_path, &cache_path, &criteria).unwrap();
+    assert!(cache_path.exists());
+
+    // the second call loads the cache
+    let cached = Compound::from_mesh_file(&mesh_path, &cache_path, &criteria).unwrap();
+    assert_eq!(cached.parts.len(), compound.parts.len());
+    for (a, b) in compound.parts.iter().zip(cached.parts.iter()) {
+        assert!((a.volume() - b.volume()).abs() < 1e-5);
+    }
+
+    // other criteria invalidate the cache
+    let mesh = load_indexed_mesh(&mesh_path).unwrap();
+    let coarse = DecompositionCriteria { resolution: 8, ..criteria };
+    let err = load_convex_hulls(&cache_path, &mesh, &coarse).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Compound::from_mesh_file(&mesh_path, &cache_path, &coarse).unwrap();
+    assert!(load_convex_hulls(&cache_path, &mesh, &coarse).is_ok());
+    assert!(load_convex_hulls(&cache_path, &mesh, &criteria).is_err());
+
+    // so does another mesh, even if the cache is more recent
+    let mut moved = mesh.clone();
+    for v in moved.vertices.iter_mut() {
+        v[2] += 1.;
+    }
+    assert!(load_convex_hulls(&cache_path, &moved, &coarse).is_err());
+    save_indexed_mesh(&mesh_path, &moved).unwrap();
+    let compound = Compound::from_mesh_file(&mesh_path, &cache_path, &coarse).unwrap();
+    assert!(compound.parts.iter().all(|part| part.center()[2] > 1.));
+    assert!(load_convex_hulls(&cache_path, &moved, &coarse).is_ok());
+
+    assert!(load_convex_hulls(dir.join("crobot_decomposition_missing.obj"), &mesh, &criteria).is_err());
+}
+
+#[test]
+fn test_compound_queries() {
+    let ccd = CCDCriteria::default();
+    let criteria = DecompositionCriteria { resolution: 16, ..DecompositionCriteria::default() };
+    let mut compound = Compound::new(convex_decomposition(&l_shape(), &criteria));
+
+    // the notch is free, although inside the convex hull of the shape, and
+    // its closest features are the edges x = 1 and y = 1
+    let mut sphere = Sphere { pos: Vector3f::new(1.6, 1.6, 0.5), rotm: Matrix3f::identity(), radius: 0.2 };
+    assert!(!compound.intersect(&sphere, &ccd));
+    let mut dist = CCDDistance::new();
+    assert!(compound.distance(&sphere, &ccd, &mut dist));
+    assert!((dist.distance - 0.4).abs() < 0.05, "distance {}", dist.distance);
+
+    sphere.pos = Vector3f::new(0.5, 0.5, 0.5);
+    assert!(compound.intersect(&sphere, &ccd));
+    assert!(!compound.distance(&sphere, &ccd, &mut dist));
+
+    // two copies of the shape, the second one moved above the first
+    let mut other = Compound::new(convex_decomposition(&l_shape(), &criteria));
+    other.tform = Isometry3f::translation(0., 0., 1.5);
+    assert!(!compound.intersect_compound(&other, &ccd));
+    assert!(compound.distance_compound(&other, &ccd, &mut dist));
+    assert!((dist.distance - 0.5).abs() < 0.05, "distance {}", dist.distance);
+
+    compound.tform = Isometry3f::translation(0., 0., 0.8);
+    assert!(compound.intersect_compound(&other, &ccd));
+    assert!(!compound.distance_compound(&other, &ccd, &mut dist));
+}
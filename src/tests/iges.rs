@@ -0,0 +1,144 @@
+use crate::geometry::*;
+use crate::math::{Vector3f, Scalar};
+use crate::utils::{IgesModel, load_iges, read_iges, write_iges};
+
+fn setup() -> IgesModel {
+    let file = "resource/iges/fixture.igs";
+    load_iges(file).expect("iges file not found.")
+}
+
+fn roundtrip(model: &IgesModel) -> IgesModel {
+    let mut buffer = Vec::new();
+    write_iges(&mut buffer, model).unwrap();
+    for line in String::from_utf8(buffer.clone()).unwrap().lines() {
+        assert_eq!(line.len(), 80);
+    }
+    read_iges(&mut buffer.as_slice()).unwrap()
+}
+
+#[test]
+fn test_iges_read() {
+    let model = setup();
+    assert_eq!(model.curves.len(), 1);
+    assert_eq!(model.surfaces.len(), 1);
+    assert_eq!(model.trimmed_surfaces.len(), 1);
+
+    // quarter circle of radius 2, rotated about z and moved to (1, 2, 3)
+    let arc = &model.curves[0];
+    assert_eq!(arc.degree, 2);
+    assert_relative_eq!(arc.curve_point(0.), Vector3f::new(1., 4., 3.), epsilon = 1e-9);
+    assert_relative_eq!(arc.curve_point(1.), Vector3f::new(-1., 2., 3.), epsilon = 1e-9);
+    for i in 0..=10 {
+        let p = arc.curve_point(i as Scalar / 10.);
+        assert_relative_eq!((p - Vector3f::new(1., 2., 3.)).norm(), 2., epsilon = 1e-9);
+    }
+
+    // biquadratic bump with a weight of 2 at the center
+    let bump = &model.surfaces[0];
+    assert_eq!((bump.degree_u, bump.degree_v), (2, 2));
+    assert_relative_eq!(bump.evaluate_single(0.5, 0.5), Vector3f::new(0., 0., 1.2), epsilon = 1e-9);
+    assert_relative_eq!(bump.evaluate_single(1., 0.), Vector3f::new(1., -1., 0.), epsilon = 1e-9);
+
+    // plane over [0, 4] x [0, 2], trimmed to [0.4, 3.6] x [0.2, 1.8] with a hole
+    let trimmed = &model.trimmed_surfaces[0];
+    assert_eq!(trimmed.outer.as_ref().unwrap().curves.len(), 4);
+    assert_eq!(trimmed.inner.len(), 1);
+    assert_relative_eq!(trimmed.evaluate_single(0.25, 0.5), Vector3f::new(1., 1., 0.), epsilon = 1e-9);
+    assert!(!trimmed.is_inside(0.05, 0.5));
+    assert!(!trimmed.is_inside(0.5, 0.95));
+    assert!(!trimmed.is_inside(0.5, 0.5));
+    assert!(!trimmed.is_inside(0.6, 0.5));
+    assert!(trimmed.is_inside(0.65, 0.5));
+    assert!(trimmed.is_inside(0.2, 0.2));
+}
+
+#[test]
+fn test_iges_roundtrip() {
+    let model = setup();
+    let copy = roundtrip(&model);
+    assert_eq!(copy.curves.len(), 1);
+    assert_eq!(copy.surfaces.len(), 1);
+    assert_eq!(copy.trimmed_surfaces.len(), 1);
+
+    for i in 0..=10 {
+        let u = i as Scalar / 10.;
+        assert_relative_eq!(copy.curves[0].curve_point(u), model.curves[0].curve_point(u), epsilon = 1e-12);
+        for j in 0..=10 {
+            let v = j as Scalar / 10.;
+            assert_relative_eq!(copy.surfaces[0].evaluate_single(u, v),
+                                model.surfaces[0].evaluate_single(u, v), epsilon = 1e-12);
+            let (a, b) = (&copy.trimmed_surfaces[0], &model.trimmed_surfaces[0]);
+            assert_relative_eq!(a.evaluate_single(u, v), b.evaluate_single(u, v), epsilon = 1e-12);
+            assert_eq!(a.is_inside(u, v), b.is_inside(u, v));
+        }
+    }
+
+    // primitives, and a trimmed surface without outer loop
+    let mut model = IgesModel::new();
+    let origin = Vector3f::zeros();
+    let axis = Vector3f::new(0., 0., 1.);
+    model.surfaces.push(NurbsSurface::torus(&origin, &axis, 2., 0.5));
+    model.curves.push(NurbsCurve::ellipse(&origin, &Vector3f::new(1., 0., 0.),
+                                          &Vector3f::new(0., 1., 0.), 3., 1.));
+    let hole = NurbsCurve::circle(&Vector3f::new(0.5, 0.5, 0.), &Vector3f::new(1., 0., 0.),
+                                  &Vector3f::new(0., 1., 0.), 0.2);
+    model.trimmed_surfaces.push(TrimmedNurbsSurface::new(
+        NurbsSurface::cylinder(&origin, &axis, 1., 2.), None, vec![TrimLoop::new(vec![hole])]));
+
+    let copy = roundtrip(&model);
+    assert!(copy.trimmed_surfaces[0].outer.is_none());
+    for i in 0..=12 {
+        let u = i as Scalar / 12.;
+        let p = copy.curves[0].curve_point(u);
+        assert_relative_eq!(p, model.curves[0].curve_point(u), epsilon = 1e-12);
+        for j in 0..=12 {
+            let v = j as Scalar / 12.;
+            let p = copy.surfaces[0].evaluate_single(u, v);
+            let r = (p[0] * p[0] + p[1] * p[1]).sqrt();
+            assert_relative_eq!((r - 2.).powi(2) + p[2] * p[2], 0.25, epsilon = 1e-12);
+            assert_eq!(copy.trimmed_surfaces[0].is_inside(u, v),
+                       model.trimmed_surfaces[0].is_inside(u, v));
+        }
+    }
+}
+
+#[test]
+fn test_iges_invalid() {
+    let text = std::fs::read_to_string("resource/iges/fixture.igs").unwrap();
+
+    // parameter section cut short
+    let truncated: Vec<&str> = text.lines().take(40).collect();
+    assert!(read_iges(&mut truncated.join("\n").as_bytes()).is_err());
+
+    // unknown section letter
+    let corrupted = text.replacen("D      1", "X      1", 1);
+    assert!(read_iges(&mut corrupted.as_bytes()).is_err());
+
+    // multibyte character across the section column
+    let corrupted = text.replacen("0D      1", "\u{e9}D      1", 1);
+    assert!(read_iges(&mut corrupted.as_bytes()).is_err());
+
+    // transformation matrix transformed by itself
+    let corrupted = text.replacen("     124       1       0       1       0       0       0",
+                                  "     124       1       0       1       0       0       1", 1);
+    assert!(read_iges(&mut corrupted.as_bytes()).is_err());
+
+    // Hollerith string longer than any file
+    let corrupted = text.replacen("124,0,-1,0,1,1,0,0,2,0,0,1,3;", "124,99999999999999999999H1,3;", 1);
+    let err = read_iges(&mut corrupted.as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_iges_unsupported_trimming() {
+    // parametric curve of the inner loop turned into a line (110): the
+    // trimmed surface is read untrimmed, without its trimming curves
+    let text = std::fs::read_to_string("resource/iges/fixture.igs").unwrap();
+    let corrupted = text
+        .replacen("     126      15       0", "     110      15       0", 1)
+        .replacen("     126       0       0       4", "     110       0       0       4", 1);
+    let model = read_iges(&mut corrupted.as_bytes()).unwrap();
+    assert_eq!(model.curves.len(), 1);
+    assert_eq!(model.surfaces.len(), 2);
+    assert!(model.trimmed_surfaces.is_empty());
+}
@@ -0,0 +1,214 @@
+use crate::utils::*;
+use crate::tests::cube;
+
+/// Compares the triangles of the meshes, regardless of the vertex order.
+fn assert_same_mesh(a: &IndexedMesh, b: &IndexedMesh) {
+    assert_eq!(a.faces.len(), b.faces.len());
+    for (fa, fb) in a.faces.iter().zip(b.faces.iter()) {
+        for k in 0..3 {
+            assert_eq!(a.vertices[fa.vertices[k]], b.vertices[fb.vertices[k]]);
+            assert!((fa.normal[k] - fb.normal[k]).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_mesh_stl_roundtrip() {
+    let mesh = cube();
+    mesh.validate().unwrap();
+
+    let mut buffer = Vec::new();
+    write_stl(&mut buffer, &mesh).unwrap();
+    assert_eq!(buffer.len(), 84 + 50 * mesh.faces.len());
+    let copy = read_stl(&mut std::io::Cursor::new(buffer)).unwrap();
+    assert_same_mesh(&mesh, &copy);
+
+    let mut buffer = Vec::new();
+    write_stl_ascii(&mut buffer, &mesh, "cube").unwrap();
+    let copy = read_stl(&mut std::io::Cursor::new(buffer)).unwrap();
+    assert_same_mesh(&mesh, &copy);
+
+    // face referring to a missing vertex
+    let mut broken = mesh.clone();
+    broken.faces[5].vertices[1] = 8;
+    let err = write_stl(&mut Vec::new(), &broken).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = write_stl_ascii(&mut Vec::new(), &broken, "cube").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_mesh_obj() {
+    let mesh = cube();
+    let groups = vec![
+        ObjGroup { name: "bottom".to_string(), faces: 0..2 },
+        ObjGroup { name: "sides".to_string(), faces: 4..12 },
+    ];
+    let mut buffer = Vec::new();
+    write_obj(&mut buffer, &mesh, &groups).unwrap();
+    let (copy, copy_groups) = read_obj_groups(&mut buffer.as_slice()).unwrap();
+    assert_same_mesh(&mesh, &copy);
+    assert_eq!(copy.vertices, mesh.vertices);
+    assert_eq!(copy_groups.len(), 3);
+    assert_eq!(copy_groups[0], groups[0]);
+    assert_eq!(copy_groups[1], ObjGroup { name: "default".to_string(), faces: 2..4 });
+    assert_eq!(copy_groups[2], groups[1]);
+
+    // empty groups are not written
+    let groups = vec![
+        ObjGroup { name: "empty".to_string(), faces: 0..0 },
+        ObjGroup { name: "bottom".to_string(), faces: 0..2 },
+        ObjGroup { name: "none".to_string(), faces: 2..2 },
+    ];
+    let mut buffer = Vec::new();
+    write_obj(&mut buffer, &mesh, &groups).unwrap();
+    let (_, copy_groups) = read_obj_groups(&mut buffer.as_slice()).unwrap();
+    assert_eq!(copy_groups.len(), 2);
+    assert_eq!(copy_groups[0], groups[1]);
+    assert_eq!(copy_groups[1], ObjGroup { name: "default".to_string(), faces: 2..12 });
+
+    // quads, relative indices, texture coordinates and missing normals
+    let obj = "# square\n\
+               o square\n\
+               v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\n\
+               vt 0 0\nvn 0 0 -1\n\
+               usemtl default\n\
+               f -4/1 -3/1 -2/1 -1/1\n\
+               g back\n\
+               f 1//1 4//1 3//1\n";
+    let (mesh, groups) = read_obj_groups(&mut obj.as_bytes()).unwrap();
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.faces.len(), 3);
+    assert_eq!(mesh.faces[1].vertices, [0, 2, 3]);
+    assert_eq!(mesh.faces[0].normal, [0., 0., 1.]);
+    assert_eq!(mesh.faces[2].normal, [0., 0., -1.]);
+    assert_eq!(groups[0], ObjGroup { name: "square".to_string(), faces: 0..2 });
+    assert_eq!(groups[1], ObjGroup { name: "back".to_string(), faces: 2..3 });
+
+    assert!(read_obj(&mut "v 0 0 0\nf 1 2 3\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_mesh_ply() {
+    let mesh = cube();
+    for &format in &[PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian] {
+        let mut buffer = Vec::new();
+        write_ply(&mut buffer, &mesh, format).unwrap();
+        let copy = read_ply(&mut buffer.as_slice()).unwrap();
+        assert_same_mesh(&mesh, &copy);
+    }
+
+    // extra properties and elements, quad face
+    let ply = "ply\nformat ascii 1.0\ncomment square\n\
+               element vertex 4\nproperty double x\nproperty double y\nproperty double z\n\
+               property uchar red\n\
+               element face 1\nproperty list uchar uint vertex_index\n\
+               element edge 1\nproperty int vertex1\nproperty int vertex2\n\
+               end_header\n\
+               0 0 0 255\n1 0 0 255\n1 1 0 255\n0 1 0 255\n\
+               4 0 1 2 3\n\
+               0 1\n";
+    let mesh = read_ply(&mut ply.as_bytes()).unwrap();
+    assert_eq!(mesh.vertices[2], [1., 1., 0.]);
+    assert_eq!(mesh.faces.len(), 2);
+    assert_eq!(mesh.faces[1].vertices, [0, 2, 3]);
+    assert_eq!(mesh.faces[1].normal, [0., 0., 1.]);
+
+    // negative vertex index, and a count larger than the file
+    let header = "ply\nformat ascii 1.0\n\
+                  element vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                  element face 1\nproperty list uint int vertex_indices\n\
+                  end_header\n\
+                  0 0 0\n1 0 0\n0 1 0\n";
+    assert!(read_ply(&mut format!("{}3 0 1 -1\n", header).as_bytes()).is_err());
+    assert!(read_ply(&mut format!("{}4000000000 0 1 2\n", header).as_bytes()).is_err());
+    assert!(read_ply(&mut format!("{}3 0 1 2\n", header).as_bytes()).is_ok());
+}
+
+#[test]
+fn test_mesh_load_by_extension() {
+    let stl = load_indexed_mesh("resource/mesh/universal/link_0.STL").unwrap();
+    assert!(!stl.faces.is_empty());
+
+    let dir = std::env::temp_dir();
+    for name in &["crobot_mesh.stl", "crobot_mesh.obj", "crobot_mesh.ply"] {
+        let path = dir.join(name);
+        save_indexed_mesh(&path, &stl).unwrap();
+        let copy = load_indexed_mesh(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_same_mesh(&stl, &copy);
+    }
+
+    assert_eq!(MeshFormat::from_path("a/b.Obj").unwrap(), MeshFormat::Obj);
+    assert!(MeshFormat::from_path("a/b.xyz").is_err());
+}
+
+#[test]
+fn test_mesh_load_too_many_vertices() {
+    // separate triangles, more vertices than u16 indices can address
+    let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    for k in 0..21846 {
+        let x = k as f32;
+        mesh.vertices.extend_from_slice(&[[x, 0., 0.], [x, 1., 0.], [x, 0., 1.]]);
+        mesh.faces.push(IndexedTriangle { normal: [1., 0., 0.], vertices: [3 * k, 3 * k + 1, 3 * k + 2] });
+    }
+    let path = std::env::temp_dir().join("crobot_mesh_large.ply");
+    save_indexed_mesh(&path, &mesh).unwrap();
+    let result = load_mesh(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.err().unwrap().kind(), std::io::ErrorKind::InvalidData);
+
+    mesh.vertices.truncate(65535);
+    mesh.faces.truncate(21845);
+    assert!(to_kiss3d_mesh(&mesh).is_some());
+}
+
+fn assert_close(a: &Vertex, b: &[f32; 3]) {
+    for k in 0..3 {
+        assert!((a[k] - b[k]).abs() < 1e-6, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn test_mesh_collada() {
+    let mesh = load_indexed_mesh("resource/mesh/collada/fixture.dae").unwrap();
+    assert_eq!(mesh.vertices.len(), 7);
+    assert_eq!(mesh.faces.len(), 3);
+
+    // square in centimeters, translated then converted from y-up to z-up
+    let square = &mesh.faces[1];
+    assert_close(&mesh.vertices[square.vertices[0]], &[0., 0., 1.]);
+    assert_close(&mesh.vertices[square.vertices[1]], &[1., 0., 2.]);
+    assert_close(&mesh.vertices[square.vertices[2]], &[0., 0., 2.]);
+    assert_close(&square.normal, &[0., -1., 0.]);
+
+    // triangle rotated and mirrored, keeping its orientation
+    let triangle = &mesh.faces[2];
+    assert_close(&mesh.vertices[triangle.vertices[0]], &[0., 0., 0.]);
+    assert_close(&mesh.vertices[triangle.vertices[1]], &[1., 0., 0.]);
+    assert_close(&mesh.vertices[triangle.vertices[2]], &[0., 0., 1.]);
+    assert_close(&triangle.normal, &[0., -1., 0.]);
+
+    // without a scene, the geometries are read untransformed
+    let dae = r##"<COLLADA><library_geometries><geometry id="g"><mesh>
+        <source id="p"><float_array>0 0 0 1 0 0 0 1 0 1 1 0</float_array></source>
+        <vertices id="v"><input semantic="POSITION" source="#p"/></vertices>
+        <polygons count="1"><input semantic="VERTEX" source="#v" offset="0"/><p>0 1 3 2</p></polygons>
+        </mesh></geometry></library_geometries></COLLADA>"##;
+    let mesh = read_collada(&mut dae.as_bytes()).unwrap();
+    assert_eq!(mesh.faces.len(), 2);
+    assert_close(&mesh.faces[0].normal, &[0., 0., 1.]);
+
+    let dae = r##"<COLLADA><scene><instance_visual_scene url="#missing"/></scene></COLLADA>"##;
+    assert!(read_collada(&mut dae.as_bytes()).is_err());
+
+    // accessor count overflowing the array size
+    let dae = r##"<COLLADA><library_geometries><geometry id="g"><mesh>
+        <source id="p"><float_array>0 0 0 1 0 0 0 1 0</float_array>
+        <technique_common><accessor count="6148914691236517206" stride="3"/></technique_common></source>
+        <vertices id="v"><input semantic="POSITION" source="#p"/></vertices>
+        <triangles count="1"><input semantic="VERTEX" source="#v" offset="0"/><p>0 1 2</p></triangles>
+        </mesh></geometry></library_geometries></COLLADA>"##;
+    let err = read_collada(&mut dae.as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
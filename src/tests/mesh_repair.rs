@@ -0,0 +1,149 @@
+use crate::utils::*;
+use crate::tests::cube;
+
+/// Unit cube as a triangle soup with outward normals: every triangle has its
+/// own vertices.
+fn cube_soup() -> IndexedMesh {
+    let cube = cube();
+    let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    for face in &cube.faces {
+        let n = mesh.vertices.len();
+        for &i in face.vertices.iter() {
+            mesh.vertices.push(cube.vertices[i]);
+        }
+        mesh.faces.push(IndexedTriangle { normal: face.normal, vertices: [n, n + 1, n + 2] });
+    }
+    return mesh;
+}
+
+/// Checks that the normals of the unit cube point away from its center.
+fn assert_outward(mesh: &IndexedMesh) {
+    for face in &mesh.faces {
+        let v = face.vertices;
+        let normal = triangle_normal(&mesh.vertices[v[0]], &mesh.vertices[v[1]], &mesh.vertices[v[2]]);
+        let mut dot = 0.;
+        for k in 0..3 {
+            assert!((face.normal[k] - normal[k]).abs() < 1e-5);
+            let center = (mesh.vertices[v[0]][k] + mesh.vertices[v[1]][k] + mesh.vertices[v[2]][k]) / 3.;
+            dot += (center - 0.5) * normal[k];
+        }
+        assert!(dot > 0.);
+    }
+}
+
+#[test]
+fn test_mesh_repair_soup() {
+    let mut mesh = cube_soup();
+    assert!(!mesh.is_watertight());
+    assert_eq!(mesh.boundary_loops().len(), 12);
+
+    // jitter below the tolerance, flipped, duplicate and degenerate faces
+    mesh.vertices[5][0] += 1e-7;
+    mesh.faces[3].vertices.swap(1, 2);
+    mesh.faces[7].vertices.swap(0, 2);
+    let duplicate = mesh.faces[0].clone();
+    mesh.faces.push(duplicate);
+    mesh.faces.push(IndexedTriangle { normal: [0.; 3], vertices: [0, 1, 1] });
+    mesh.vertices.push([0., 0.5, 0.]);
+    let n = mesh.vertices.len() - 1;
+    mesh.faces.push(IndexedTriangle { normal: [0.; 3], vertices: [0, n, 1] });
+
+    let report = mesh.repair(&RepairCriteria::default());
+    assert_eq!(report.welded_vertices, 36 + 1 - 9);
+    assert_eq!(report.degenerate_faces, 2);
+    assert_eq!(report.duplicate_faces, 1);
+    assert_eq!(report.flipped_faces, 2);
+    assert!(report.non_manifold_edges.is_empty());
+    assert!(report.holes.is_empty());
+    assert!(report.watertight);
+
+    // the collinear vertex is left unused
+    assert_eq!(mesh.vertices.len(), 8);
+    assert_eq!(mesh.faces.len(), 12);
+    mesh.validate().unwrap();
+    assert!((mesh.volume() - 1.).abs() < 1e-6);
+    assert_outward(&mesh);
+}
+
+#[test]
+fn test_mesh_repair_orientation() {
+    // closed mesh turned inwards is turned outwards
+    let mut mesh = cube_soup();
+    mesh.weld_vertices(0.);
+    assert_eq!(mesh.vertices.len(), 8);
+    for face in mesh.faces.iter_mut() {
+        face.vertices.swap(1, 2);
+    }
+    assert_eq!(mesh.orient_faces(), 12);
+    assert_eq!(mesh.orient_faces(), 0);
+    assert_outward(&mesh);
+}
+
+#[test]
+fn test_mesh_repair_holes() {
+    let mut mesh = cube_soup();
+    mesh.weld_vertices(1e-6);
+
+    // open the top face
+    mesh.faces.remove(3);
+    mesh.faces.remove(2);
+    assert!(!mesh.is_watertight());
+    let holes = mesh.boundary_loops();
+    assert_eq!(holes.len(), 1);
+    assert_eq!(holes[0].len(), 4);
+
+    // too large to be filled
+    assert_eq!(mesh.fill_holes(3), 0);
+    let mut criteria = RepairCriteria::default();
+    let report = mesh.repair(&criteria);
+    assert_eq!(report.holes.len(), 1);
+    assert!(!report.watertight);
+
+    criteria.fill_holes = true;
+    let report = mesh.repair(&criteria);
+    assert_eq!(report.filled_holes, 1);
+    assert_eq!(report.flipped_faces, 0);
+    assert!(report.holes.is_empty());
+    assert!(report.watertight);
+    assert_eq!(mesh.faces.len(), 14);
+    assert!((mesh.volume() - 1.).abs() < 1e-6);
+
+    // hole next to a flipped face
+    let mut mesh = cube_soup();
+    mesh.weld_vertices(1e-6);
+    mesh.faces.remove(3);
+    mesh.faces.remove(2);
+    mesh.faces[3].vertices.swap(1, 2);
+    assert!(mesh.boundary_loops().is_empty());
+    let report = mesh.repair(&criteria);
+    assert_eq!(report.filled_holes, 1);
+    assert_eq!(report.flipped_faces, 1);
+    assert!(report.holes.is_empty());
+    assert!(report.watertight);
+    assert!((mesh.volume() - 1.).abs() < 1e-6);
+    assert_outward(&mesh);
+
+    // a fin on an edge makes it non-manifold
+    mesh.vertices.push([0.5, -1., 0.]);
+    let n = mesh.vertices.len() - 1;
+    let [a, b, _] = mesh.faces[0].vertices;
+    mesh.faces.push(IndexedTriangle { normal: [0.; 3], vertices: [a, b, n] });
+    let report = mesh.repair(&RepairCriteria::default());
+    let edge = if a < b { [a, b] } else { [b, a] };
+    assert_eq!(report.non_manifold_edges, vec![edge]);
+    assert!(!report.watertight);
+}
+
+#[test]
+fn test_mesh_repair_resource() {
+    let mut mesh = load_indexed_mesh("resource/mesh/universal/base.stl").unwrap();
+    let volume = mesh.volume();
+    let report = mesh.repair(&RepairCriteria::default());
+    mesh.validate().unwrap();
+    assert!(report.watertight);
+    assert!(report.non_manifold_edges.is_empty());
+
+    // the file stores the shell twice
+    assert!(report.duplicate_faces > mesh.faces.len() / 2);
+    assert!((2. * mesh.volume() - volume).abs() <= 1e-6 * volume);
+}
@@ -4,4 +4,33 @@ pub mod dcel;
 pub mod kinematics;
 pub mod rbtree;
 pub mod bspline;
-pub mod ccd;
\ No newline at end of file
+pub mod ccd;
+pub mod decomposition;
+pub mod nurbs;
+pub mod iges;
+pub mod mesh_io;
+pub mod mesh_repair;
+pub mod volint;
+#[cfg(feature = "serde")]
+pub mod serialization;
+
+use crate::utils::{IndexedMesh, IndexedTriangle, triangle_normal};
+
+/// Unit cube with outward normals, two triangles per side.
+pub(crate) fn cube() -> IndexedMesh {
+    let vertices = vec![
+        [0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.],
+        [0., 0., 1.], [1., 0., 1.], [1., 1., 1.], [0., 1., 1.],
+    ];
+    let quads = [
+        [0, 3, 2, 1], [4, 5, 6, 7], [0, 1, 5, 4],
+        [2, 3, 7, 6], [1, 2, 6, 5], [0, 4, 7, 3],
+    ];
+    let mut faces = Vec::new();
+    for q in quads.iter() {
+        let normal = triangle_normal(&vertices[q[0]], &vertices[q[1]], &vertices[q[2]]);
+        faces.push(IndexedTriangle { normal, vertices: [q[0], q[1], q[2]] });
+        faces.push(IndexedTriangle { normal, vertices: [q[0], q[2], q[3]] });
+    }
+    IndexedMesh { vertices, faces }
+}
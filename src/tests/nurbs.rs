@@ -0,0 +1,1217 @@
+use crate::geometry::*;
+use crate::ccd::{IntersectionCriteria, intersect_surfaces};
+use crate::ccd::{DistanceCriteria, OBB, OBBTree, surface_distance};
+use crate::math::{Vector3f, Vector4f, Scalar, U4};
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::collections::HashMap;
+
+/// Full unit circle made of four rational quadratic arcs.
+fn nurbs_circle() -> NurbsCurve {
+    let control_points = vec![
+        Vector3f::new( 1.,  0., 0.),
+        Vector3f::new( 1.,  1., 0.),
+        Vector3f::new( 0.,  1., 0.),
+        Vector3f::new(-1.,  1., 0.),
+        Vector3f::new(-1.,  0., 0.),
+        Vector3f::new(-1., -1., 0.),
+        Vector3f::new( 0., -1., 0.),
+        Vector3f::new( 1., -1., 0.),
+        Vector3f::new( 1.,  0., 0.),
+    ];
+    let knot_vector = vec![
+        0., 0., 0., 0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1., 1., 1.
+    ];
+    let w = FRAC_1_SQRT_2 as Scalar;
+    let weights = vec![1., w, 1., w, 1., w, 1., w, 1.];
+
+    NurbsCurve::new(control_points, knot_vector, 2, weights)
+}
+
+fn assert_same_curve(a: &NurbsCurve, b: &NurbsCurve) {
+    for i in 0..=50 {
+        let u = i as Scalar / 50.;
+        assert_relative_eq!(a.curve_point(u), b.curve_point(u), epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn test_nurbs_curve_evaluate() {
+    let curve = nurbs_circle();
+    for i in 0..=100 {
+        let u = i as Scalar / 100.;
+        let ders = curve.derivatives(u, 2);
+        assert_relative_eq!(ders[0], curve.curve_point(u), epsilon = 1e-12);
+        assert_relative_eq!(ders[0].norm(), 1., epsilon = 1e-12);
+        // tangent is perpendicular to the radius
+        assert_relative_eq!(ders[0].dot(&ders[1]), 0., epsilon = 1e-9);
+    }
+
+    // compare first and second derivatives to finite differences
+    let h = 1e-5;
+    for &u in &[0.1, 0.3, 0.6, 0.9] {
+        let ders = curve.derivatives(u, 2);
+        let d1 = (curve.curve_point(u + h) - curve.curve_point(u - h)) / (2. * h);
+        let d2 = (curve.curve_point(u + h) - 2. * curve.curve_point(u)
+            + curve.curve_point(u - h)) / (h * h);
+        assert_relative_eq!(ders[1], d1, epsilon = 1e-5);
+        assert_relative_eq!(ders[2], d2, epsilon = 1e-2);
+    }
+
+    assert_relative_eq!(curve.curve_point(1.), Vector3f::new(1., 0., 0.), epsilon = 1e-12);
+}
+
+#[test]
+fn test_nurbs_curve_knot_operations() {
+    let curve = nurbs_circle();
+
+    let mut inserted = curve.clone();
+    inserted.insert_knot(0.1, 2);
+    assert_eq!(inserted.size(), curve.size() + 2);
+    assert_eq!(inserted.knotvec.len(), curve.knotvec.len() + 2);
+    assert_same_curve(&curve, &inserted);
+
+    let mut refined = curve.clone();
+    refined.refine_knotvec(&vec![0.1, 0.4, 0.4, 0.8]);
+    assert_eq!(refined.size(), curve.size() + 4);
+    assert_same_curve(&curve, &refined);
+
+    // knots that were inserted can be removed again
+    let removed = refined.remove_knot(0.4, 2, 1e-9);
+    assert_eq!(removed, 2);
+    assert_eq!(refined.size(), curve.size() + 2);
+    assert_same_curve(&curve, &refined);
+
+    // knots which are needed to represent the circle cannot
+    let removed = refined.remove_knot(0.5, 2, 1e-9);
+    assert_eq!(removed, 0);
+}
+
+#[test]
+fn test_nurbs_curve_elevate_degree() {
+    let curve = nurbs_circle();
+    let mut elevated = curve.clone();
+    elevated.elevate_degree(2);
+    assert_eq!(elevated.degree, 4);
+    // every distinct interior knot keeps its continuity
+    assert_eq!(elevated.size(), 4 * 4 + 1);
+    assert_eq!(elevated.knotvec.len(), elevated.size() + elevated.degree + 1);
+    assert_same_curve(&curve, &elevated);
+}
+
+#[test]
+fn test_nurbs_curve_from_bspline_domain() {
+    // quarter circle with the knot range [2, 5]
+    let w = FRAC_1_SQRT_2 as Scalar;
+    let ctrlpts = vec![
+        Vector4f::new(1., 0., 0., 1.),
+        Vector4f::new(w, w, 0., w),
+        Vector4f::new(0., 1., 0., 1.),
+    ];
+    let bspline = BSplineCurve::<U4>::new(2, ctrlpts, vec![2., 2., 2., 5., 5., 5.]);
+    let curve = NurbsCurve::from_bspline(bspline);
+    assert_eq!(curve.domain(), (2., 5.));
+
+    let points = curve.get_curve(10);
+    assert_relative_eq!(points[0], Vector3f::new(1., 0., 0.), epsilon = 1e-12);
+    assert_relative_eq!(points[10], Vector3f::new(0., 1., 0.), epsilon = 1e-12);
+    for p in &points {
+        assert_relative_eq!(p.norm(), 1., epsilon = 1e-12);
+    }
+}
+
+/// Quarter of a unit cylinder of height 2 around the z-axis, u along the
+/// arc and v along the axis.
+fn nurbs_quarter_cylinder() -> NurbsSurface {
+    let control_points = vec![
+        Vector3f::new(1., 0., 0.), Vector3f::new(1., 0., 2.),
+        Vector3f::new(1., 1., 0.), Vector3f::new(1., 1., 2.),
+        Vector3f::new(0., 1., 0.), Vector3f::new(0., 1., 2.),
+    ];
+    let w = FRAC_1_SQRT_2 as Scalar;
+    let weights = vec![1., 1., w, w, 1., 1.];
+
+    NurbsSurface::new(
+        control_points,
+        vec![0., 0., 0., 1., 1., 1.],
+        vec![0., 0., 1., 1.],
+        2, 1, 3, 2,
+        weights,
+    )
+}
+
+/// Bicubic freeform surface with non-uniform weights.
+fn nurbs_freeform() -> NurbsSurface {
+    let mut control_points = Vec::new();
+    let mut weights = Vec::new();
+    for i in 0..5 {
+        for j in 0..4 {
+            let x = i as Scalar - 2.;
+            let y = j as Scalar - 1.5;
+            let z = 0.3 * (x * y).sin() + 0.1 * x * x;
+            control_points.push(Vector3f::new(x, y, z));
+            weights.push(1. + 0.25 * ((i + 2 * j) % 3) as Scalar);
+        }
+    }
+
+    NurbsSurface::new(
+        control_points,
+        vec![0., 0., 0., 0., 0.4, 1., 1., 1., 1.],
+        vec![0., 0., 0., 0.5, 1., 1., 1.],
+        3, 2, 5, 4,
+        weights,
+    )
+}
+
+#[test]
+fn test_nurbs_surface_derivatives() {
+    let surf = nurbs_freeform();
+    let h = 1e-5;
+    for &(u, v) in &[(0.1, 0.2), (0.45, 0.7), (0.8, 0.3)] {
+        let skl = surf.derivatives(u, v, 2);
+        assert_relative_eq!(skl[0][0], surf.evaluate_single(u, v), epsilon = 1e-12);
+
+        let su = (surf.evaluate_single(u + h, v) - surf.evaluate_single(u - h, v)) / (2. * h);
+        let sv = (surf.evaluate_single(u, v + h) - surf.evaluate_single(u, v - h)) / (2. * h);
+        assert_relative_eq!(skl[1][0], su, epsilon = 1e-5);
+        assert_relative_eq!(skl[0][1], sv, epsilon = 1e-5);
+
+        let h = 1e-4;
+        let suv = (surf.evaluate_single(u + h, v + h) - surf.evaluate_single(u + h, v - h)
+            - surf.evaluate_single(u - h, v + h) + surf.evaluate_single(u - h, v - h)) / (4. * h * h);
+        let suu = (surf.evaluate_single(u + h, v) - 2. * surf.evaluate_single(u, v)
+            + surf.evaluate_single(u - h, v)) / (h * h);
+        assert_relative_eq!(skl[1][1], suv, epsilon = 1e-3);
+        assert_relative_eq!(skl[2][0], suu, epsilon = 1e-3);
+    }
+}
+
+#[test]
+fn test_nurbs_surface_normal() {
+    let surf = nurbs_quarter_cylinder();
+    for i in 0..=10 {
+        for j in 0..=10 {
+            let u = i as Scalar / 10.;
+            let v = j as Scalar / 10.;
+            let (point, normal) = surf.tangent_plane(u, v);
+            let radial = Vector3f::new(point[0], point[1], 0.);
+            assert_relative_eq!(radial.norm(), 1., epsilon = 1e-12);
+            assert_relative_eq!(normal.cross(&radial).norm(), 0., epsilon = 1e-9);
+
+            // the cylinder is developable with unit radius
+            let (e, f, g) = surf.first_fundamental_form(u, v);
+            let (l, m, n) = surf.second_fundamental_form(u, v);
+            assert_relative_eq!(f, 0., epsilon = 1e-9);
+            assert_relative_eq!(g, 4., epsilon = 1e-9);
+            assert_relative_eq!(m, 0., epsilon = 1e-9);
+            assert_relative_eq!(n, 0., epsilon = 1e-9);
+            assert_relative_eq!((l / e).abs(), 1., epsilon = 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_nurbs_surface_normal_degenerate() {
+    // triangular patch whose v = 0 edge collapses into a single point
+    let control_points = vec![
+        Vector3f::new(0., 0., 0.), Vector3f::new(-1., 1., 0.),
+        Vector3f::new(0., 0., 0.), Vector3f::new( 1., 1., 0.),
+    ];
+    let surf = NurbsSurface::new(
+        control_points,
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    );
+    for &u in &[0., 0.5, 1.] {
+        assert_relative_eq!(surf.normal(u, 0.), Vector3f::new(0., 0., 1.), epsilon = 1e-9);
+    }
+
+    // a surface collapsed into a point has no normal at all
+    let point = NurbsSurface::new(
+        vec![Vector3f::new(1., 2., 3.); 4],
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    );
+    assert_eq!(point.normal(0.5, 0.5), Vector3f::zeros());
+}
+
+#[test]
+fn test_nurbs_curve_project_point() {
+    let curve = nurbs_circle();
+    let criteria = ProjectionCriteria::default();
+    assert!(curve.is_closed());
+
+    for i in 0..16 {
+        let angle = i as Scalar * 0.41 - 3.;
+        let point = Vector3f::new(2. * angle.cos(), 2. * angle.sin(), 0.5);
+        let res = curve.project_point(&point, &criteria);
+        assert_relative_eq!(res.distance, (1.25 as Scalar).sqrt(), epsilon = 1e-8);
+        assert_relative_eq!(res.point, Vector3f::new(angle.cos(), angle.sin(), 0.), epsilon = 1e-8);
+        assert_relative_eq!(res.point, curve.curve_point(res.u), epsilon = 1e-12);
+    }
+
+    // close to the seam of the closed curve
+    let point = Vector3f::new(1.5, -1e-4, 0.);
+    let res = curve.project_point(&point, &criteria);
+    assert_relative_eq!(res.distance, 0.5, epsilon = 1e-7);
+    assert!(res.u > 0.9);
+
+    // a non-finite query point has no projection, but must not panic
+    let res = curve.project_point(&Vector3f::new(Scalar::NAN, 0., 0.), &criteria);
+    assert!(res.distance.is_infinite());
+}
+
+#[test]
+fn test_nurbs_surface_project_point() {
+    let criteria = ProjectionCriteria::default();
+
+    // points around the cylinder
+    let surf = nurbs_quarter_cylinder();
+    assert!(!surf.is_closed_u() && !surf.is_closed_v());
+    let point = Vector3f::new(2. * (0.3 as Scalar).cos(), 2. * (0.3 as Scalar).sin(), 0.7);
+    let res = surf.project_point(&point, &criteria);
+    assert_relative_eq!(res.distance, 1., epsilon = 1e-8);
+    assert_relative_eq!(res.point, point / 2. + Vector3f::new(0., 0., 0.35), epsilon = 1e-8);
+
+    // points outside the domain are clamped to the boundary
+    let point = Vector3f::new(0.5, -1., 3.);
+    let res = surf.project_point(&point, &criteria);
+    assert_relative_eq!(res.u, 0., epsilon = 1e-12);
+    assert_relative_eq!(res.v, 1., epsilon = 1e-12);
+    assert_relative_eq!(res.point, Vector3f::new(1., 0., 2.), epsilon = 1e-12);
+
+    // points offset along the normal of a freeform surface
+    let surf = nurbs_freeform();
+    for &(u, v) in &[(0.2, 0.3), (0.5, 0.5), (0.77, 0.12)] {
+        let (foot, normal) = surf.tangent_plane(u, v);
+        let res = surf.project_point(&(foot + 0.05 * normal), &criteria);
+        assert_relative_eq!(res.u, u, epsilon = 1e-6);
+        assert_relative_eq!(res.v, v, epsilon = 1e-6);
+        assert_relative_eq!(res.distance, 0.05, epsilon = 1e-8);
+    }
+}
+
+/// Counts how many triangles share each undirected edge.
+fn edge_counts(tess: &Tessellation) -> HashMap<(usize, usize), usize> {
+    let mut counts = HashMap::new();
+    for t in &tess.triangles {
+        for k in 0..3 {
+            let (a, b) = (t[k], t[(k + 1) % 3]);
+            *counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+    return counts;
+}
+
+#[test]
+fn test_nurbs_surface_tessellate() {
+    let surf = nurbs_quarter_cylinder();
+    let mut criteria = TessellationCriteria::default();
+    criteria.chord_tolerance = 1e-4;
+    let tess = surf.tessellate(&criteria);
+
+    // vertices lie on the surface
+    for (point, &(u, v)) in tess.vertices.iter().zip(&tess.params) {
+        assert_relative_eq!(*point, surf.evaluate_single(u, v), epsilon = 1e-12);
+    }
+
+    // chordal deviation is bounded at the triangle centroids
+    for t in &tess.triangles {
+        let centroid = (tess.vertices[t[0]] + tess.vertices[t[1]] + tess.vertices[t[2]]) / 3.;
+        let radial = Vector3f::new(centroid[0], centroid[1], 0.).norm();
+        assert!(1. - radial < 2. * criteria.chord_tolerance);
+    }
+
+    // interior edges are shared by exactly two triangles, so the mesh has no
+    // cracks; boundary edges lie on the boundary of the domain
+    for (&(a, b), &count) in &edge_counts(&tess) {
+        if count == 1 {
+            let (pa, pb) = (tess.params[a], tess.params[b]);
+            let on_boundary = |x: Scalar, y: Scalar| (x == y) && (x == 0. || x == 1.);
+            assert!(on_boundary(pa.0, pb.0) || on_boundary(pa.1, pb.1));
+        } else {
+            assert_eq!(count, 2);
+        }
+    }
+
+    let n = tess.vertices.len();
+    let mesh = tess.to_indexed_mesh();
+    assert_eq!(mesh.vertices.len(), n);
+    assert_eq!(mesh.faces.len(), tess.triangles.len());
+
+    // a coarser tolerance gives a coarser mesh
+    criteria.chord_tolerance = 1e-2;
+    assert!(surf.tessellate(&criteria).vertices.len() < n);
+
+    // the freeform surface gives a crack-free mesh as well
+    let tess = nurbs_freeform().tessellate(&TessellationCriteria::default());
+    assert!(edge_counts(&tess).values().all(|&c| c == 1 || c == 2));
+
+    // the finest default grid fits in a kiss3d mesh, a larger one does not
+    let criteria = TessellationCriteria::default();
+    assert!(((1 << criteria.max_level) + 1) * ((1 << criteria.max_level) + 1) <= 65536);
+    let mut tess = tess;
+    tess.vertices = vec![Vector3f::zeros(); 65537];
+    assert!(tess.to_kiss3d_mesh().is_none());
+}
+
+#[test]
+fn test_nurbs_surface_get_mesh() {
+    let mut surf = nurbs_freeform();
+    surf.set_sample_size(20, 30);
+    let mesh = surf.get_mesh();
+    let mesh = mesh.borrow();
+    let num_coords = mesh.coords().read().unwrap().len();
+    assert_eq!(num_coords, 600);
+    let faces = mesh.faces().read().unwrap();
+    assert_eq!(faces.len(), 2 * 19 * 29);
+    for face in faces.data().as_ref().unwrap() {
+        assert!(face.iter().all(|&i| (i as usize) < num_coords));
+    }
+}
+
+/// Bilinear patch on the plane `z = height`.
+fn nurbs_plane(height: Scalar) -> NurbsSurface {
+    let control_points = vec![
+        Vector3f::new(-2., -2., height), Vector3f::new(-2., 2., height),
+        Vector3f::new( 2., -2., height), Vector3f::new( 2., 2., height),
+    ];
+
+    NurbsSurface::new(
+        control_points,
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    )
+}
+
+/// Biquadratic dome over `[-1, 1] x [-1, 1]` with its apex at `z = 1`.
+fn nurbs_dome() -> NurbsSurface {
+    let mut control_points = Vec::new();
+    for i in 0..3 {
+        for j in 0..3 {
+            let z = if i == 1 && j == 1 { 2. } else if i == 1 || j == 1 { 1. } else { 0. };
+            control_points.push(Vector3f::new(i as Scalar - 1., j as Scalar - 1., z));
+        }
+    }
+
+    NurbsSurface::new(
+        control_points,
+        vec![0., 0., 0., 1., 1., 1.],
+        vec![0., 0., 0., 1., 1., 1.],
+        2, 2, 3, 3,
+        vec![1.; 9],
+    )
+}
+
+#[test]
+fn test_nurbs_surface_intersection() {
+    let criteria = IntersectionCriteria::default();
+
+    // quarter cylinder cut by a plane: quarter circle arc at z = 1
+    let cylinder = nurbs_quarter_cylinder();
+    let plane = nurbs_plane(1.);
+    let curves = intersect_surfaces(&cylinder, &plane, &criteria);
+    assert_eq!(curves.len(), 1);
+    let curve = &curves[0];
+    assert!(!curve.closed);
+    assert!(curve.points.len() > 10);
+    for (k, p) in curve.points.iter().enumerate() {
+        assert_relative_eq!(p[2], 1., epsilon = 1e-8);
+        assert_relative_eq!(p[0] * p[0] + p[1] * p[1], 1., epsilon = 1e-8);
+        assert_relative_eq!(curve.params1[k].1, 0.5, epsilon = 1e-8);
+        let (u, v) = curve.params2[k];
+        assert_relative_eq!(plane.evaluate_single(u, v), p, epsilon = 1e-8);
+    }
+    let first = curve.points[0];
+    let last = curve.points[curve.points.len() - 1];
+    let ends = [Vector3f::new(1., 0., 1.), Vector3f::new(0., 1., 1.)];
+    assert!((first - ends[0]).norm() < 1e-6 && (last - ends[1]).norm() < 1e-6 ||
+            (first - ends[1]).norm() < 1e-6 && (last - ends[0]).norm() < 1e-6);
+
+    // dome cut below its apex: single closed loop
+    let dome = nurbs_dome();
+    let plane = nurbs_plane(0.75);
+    let curves = intersect_surfaces(&dome, &plane, &criteria);
+    assert_eq!(curves.len(), 1);
+    let curve = &curves[0];
+    assert!(curve.closed);
+    for (k, p) in curve.points.iter().enumerate() {
+        assert_relative_eq!(p[2], 0.75, epsilon = 1e-8);
+        let (u, v) = curve.params1[k];
+        assert_relative_eq!(dome.evaluate_single(u, v), p, epsilon = 1e-8);
+    }
+
+    // disjoint surfaces
+    let plane = nurbs_plane(3.);
+    assert!(intersect_surfaces(&dome, &plane, &criteria).is_empty());
+}
+
+#[test]
+fn test_nurbs_surface_distance() {
+    let criteria = DistanceCriteria::default();
+
+    // dome below a plane: closest to its apex
+    let dome = nurbs_dome();
+    let plane = nurbs_plane(3.);
+    let res = surface_distance(&dome, &plane, &criteria);
+    assert_relative_eq!(res.distance, 2., epsilon = 1e-8);
+    assert_relative_eq!(res.point1, Vector3f::new(0., 0., 1.), epsilon = 1e-6);
+    assert_relative_eq!(res.point2, Vector3f::new(0., 0., 3.), epsilon = 1e-6);
+    assert_relative_eq!(res.params1.0, 0.5, epsilon = 1e-6);
+    assert_relative_eq!(res.params1.1, 0.5, epsilon = 1e-6);
+    assert_relative_eq!(plane.evaluate_single(res.params2.0, res.params2.1), res.point2, epsilon = 1e-9);
+
+    // quarter cylinder next to the vertical plane x = 3
+    let cylinder = nurbs_quarter_cylinder();
+    let wall = NurbsSurface::new(
+        vec![
+            Vector3f::new(3., -1., -1.), Vector3f::new(3., -1., 3.),
+            Vector3f::new(3.,  2., -1.), Vector3f::new(3.,  2., 3.),
+        ],
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    );
+    let tree1 = OBBTree::from_nurbs_surface(&cylinder, 4);
+    let tree2 = OBBTree::from_nurbs_surface(&wall, 4);
+    let res = tree1.distance(&tree2, &cylinder, &wall, &criteria);
+    assert_relative_eq!(res.distance, 2., epsilon = 1e-8);
+    assert_relative_eq!(res.point1[0], 1., epsilon = 1e-8);
+    assert_relative_eq!(res.point1[1], 0., epsilon = 1e-8);
+    assert_relative_eq!((res.point2 - res.point1).norm(), res.distance, epsilon = 1e-12);
+
+    // intersecting surfaces
+    let plane = nurbs_plane(0.75);
+    let res = surface_distance(&dome, &plane, &criteria);
+    assert!(res.distance < 1e-8);
+}
+
+fn assert_same_surface(a: &NurbsSurface, b: &NurbsSurface, epsilon: Scalar) {
+    for i in 0..=20 {
+        for j in 0..=20 {
+            let u = i as Scalar / 20.;
+            let v = j as Scalar / 20.;
+            assert_relative_eq!(a.evaluate_single(u, v), b.evaluate_single(u, v), epsilon = epsilon);
+        }
+    }
+}
+
+#[test]
+fn test_nurbs_curve_reduce_degree() {
+    // elevated circle reduces back to the original one
+    let circle = nurbs_circle();
+    let mut curve = circle.clone();
+    curve.elevate_degree(1);
+    assert!(curve.reduce_degree(1e-9));
+    assert_eq!(curve.degree, 2);
+    assert_eq!(curve.size(), circle.size());
+    assert_same_curve(&curve, &circle);
+
+    // the circle itself is not a polyline
+    let mut curve = circle.clone();
+    assert!(!curve.reduce_degree(1e-3));
+    assert_eq!(curve.degree, 2);
+    assert_same_curve(&curve, &circle);
+
+    // non-rational spline with simple interior knots
+    let ctrlpts = vec![
+        Vector3f::new(0., 0., 0.), Vector3f::new(1., 2., 0.), Vector3f::new(2., -1., 1.),
+        Vector3f::new(3., 1., 0.), Vector3f::new(4., 0., 2.), Vector3f::new(5., 1., 1.),
+    ];
+    let spline = BSplineCurve::new(2, ctrlpts, vec![0., 0., 0., 0.2, 0.5, 0.7, 1., 1., 1.]);
+    let mut curve = spline.clone();
+    curve.elevate_degree(2);
+    assert_eq!(curve.degree, 4);
+    assert!(curve.reduce_degree(1e-9));
+    assert!(curve.reduce_degree(1e-9));
+    assert_eq!(curve.degree, 2);
+    assert_eq!(curve.size, spline.size);
+    assert!(!curve.reduce_degree(1e-3));
+    for i in 0..=50 {
+        let u = i as Scalar / 50.;
+        assert_relative_eq!(curve.curve_point(u), spline.curve_point(u), epsilon = 1e-9);
+    }
+
+    // loose tolerance bounds the deviation
+    let mut curve = spline.clone();
+    assert!(curve.reduce_degree(3.));
+    assert_eq!(curve.degree, 1);
+    for i in 0..=200 {
+        let u = i as Scalar / 200.;
+        assert!((curve.curve_point(u) - spline.curve_point(u)).norm() <= 3.);
+    }
+}
+
+#[test]
+fn test_nurbs_surface_elevate_reduce_degree() {
+    let surf = nurbs_freeform();
+
+    let mut elevated = surf.clone();
+    elevated.elevate_degree((1, 2));
+    assert_eq!((elevated.degree_u, elevated.degree_v), (4, 4));
+    assert_eq!(elevated.ctrlpts.len(), elevated.ctrlpts_size_u * elevated.ctrlpts_size_v);
+    assert_same_surface(&elevated, &surf, 1e-9);
+
+    let mut reduced = elevated.clone();
+    assert!(reduced.reduce_degree((true, true), 1e-9));
+    assert!(reduced.reduce_degree((false, true), 1e-9));
+    assert_eq!((reduced.degree_u, reduced.degree_v), (3, 2));
+    assert_eq!((reduced.ctrlpts_size_u, reduced.ctrlpts_size_v), (5, 4));
+    assert_same_surface(&reduced, &surf, 1e-9);
+
+    // cannot be reduced any further within the tolerance
+    assert!(!reduced.reduce_degree((true, false), 1e-6));
+    assert!(!reduced.reduce_degree((false, true), 1e-6));
+    assert_eq!((reduced.degree_u, reduced.degree_v), (3, 2));
+}
+
+#[test]
+fn test_nurbs_remove_knots() {
+    // refined circle reduces back to the original one
+    let circle = nurbs_circle();
+    let mut curve = circle.clone();
+    curve.refine_knotvec(&vec![0.1, 0.3, 0.6, 0.6]);
+    assert_eq!(curve.size(), circle.size() + 4);
+    assert_eq!(curve.remove_knots(1e-9), 4);
+    assert_eq!(curve.size(), circle.size());
+    assert_same_curve(&curve, &circle);
+    assert_eq!(curve.remove_knots(1e-9), 0);
+
+    // surface refined in both directions
+    let surf = nurbs_freeform();
+    let mut refined = surf.clone();
+    // the knots of the original surface are not removable
+    assert_eq!(refined.remove_knot((Some(0.4), Some(0.5)), (1, 1), 1e-9), (0, 0));
+    refined.insert_knot((Some(0.3), Some(0.7)), (2, 1));
+    refined.insert_knot((Some(0.4), None), (1, 0));
+    let mut single = refined.clone();
+    assert_eq!(refined.remove_knots(1e-9), (3, 1));
+    assert_eq!((refined.ctrlpts_size_u, refined.ctrlpts_size_v), (5, 4));
+    assert_eq!(refined.knotvec_u, surf.knotvec_u);
+    assert_same_surface(&refined, &surf, 1e-9);
+
+    assert_eq!(single.remove_knot((Some(0.3), Some(0.7)), (5, 5), 1e-9), (2, 1));
+    assert_eq!(single.ctrlpts_size_u, 6);
+    assert_same_surface(&single, &surf, 1e-9);
+
+    // perturbed refinement: removable within a loose tolerance only
+    let mut perturbed = surf.clone();
+    perturbed.insert_knot((Some(0.3), None), (1, 0));
+    let k = 2 * perturbed.ctrlpts_size_v + 1;
+    let w = perturbed.bspline.ctrlpts[k][3];
+    perturbed.bspline.ctrlpts[k][2] += 0.01 * w;
+    perturbed.update_from_bspline();
+    let mut reduced = perturbed.clone();
+    assert_eq!(reduced.remove_knots(1e-9), (0, 0));
+    let tol = 0.2;
+    assert_eq!(reduced.remove_knots(tol), (1, 0));
+    assert_eq!(reduced.ctrlpts_size_u, 5);
+    for i in 0..=40 {
+        for j in 0..=40 {
+            let u = i as Scalar / 40.;
+            let v = j as Scalar / 40.;
+            let p = perturbed.bspline.evaluate_single(u, v);
+            let p = Vector3f::new(p[0], p[1], p[2]) / p[3];
+            assert!((reduced.evaluate_single(u, v) - p).norm() <= tol);
+        }
+    }
+}
+
+#[test]
+fn test_curve_interpolate() {
+    let poly = |t: Scalar| Vector3f::new(t, 2. * t * t - t, t * t * t + 0.5 * t);
+    let points: Vec<Vector3f> = (0..=8).map(|k| poly(k as Scalar / 8.)).collect();
+
+    for method in &[Parameterization::Uniform,
+                    Parameterization::ChordLength,
+                    Parameterization::Centripetal] {
+        let (curve, residuals) = BSplineCurve::interpolate(&points, 3, *method, None).unwrap();
+        assert_eq!(curve.size, points.len());
+        assert_eq!(residuals.len(), points.len());
+        assert!(residuals.iter().all(|r| *r < 1e-10));
+        assert_relative_eq!(curve.curve_point(0.), points[0], epsilon = 1e-12);
+        assert_relative_eq!(curve.curve_point(1.), points[8], epsilon = 1e-12);
+    }
+
+    // a cubic is reproduced exactly with the matching parameterization
+    let (curve, _) = BSplineCurve::interpolate(&points, 3, Parameterization::Uniform, None).unwrap();
+    for i in 0..=40 {
+        let t = i as Scalar / 40.;
+        assert_relative_eq!(curve.curve_point(t), poly(t), epsilon = 1e-10);
+    }
+
+    // end derivatives
+    let d0 = Vector3f::new(1., 0., 0.);
+    let d1 = Vector3f::new(0., 3., -1.);
+    let (curve, residuals) = NurbsCurve::interpolate(
+        &points, 3, Parameterization::ChordLength, Some((d0, d1))).unwrap();
+    assert_eq!(curve.size(), points.len() + 2);
+    assert!(residuals.iter().all(|r| *r < 1e-10));
+    assert_relative_eq!(curve.derivatives(0., 1)[1], d0, epsilon = 1e-9);
+    assert_relative_eq!(curve.derivatives(1., 1)[1], d1, epsilon = 1e-9);
+
+    // quadratic through three points
+    let points = vec![Vector3f::new(0., 0., 0.), Vector3f::new(1., 1., 0.), Vector3f::new(2., 0., 0.)];
+    let (curve, residuals) = NurbsCurve::interpolate(&points, 2, Parameterization::Centripetal, None).unwrap();
+    assert!(residuals.iter().all(|r| *r < 1e-12));
+    assert_eq!(curve.knotvec, vec![0., 0., 0., 1., 1., 1.]);
+
+    // repeated point: two equations at the same parameter
+    let points = vec![Vector3f::new(0., 0., 0.), Vector3f::new(1., 0., 0.),
+                      Vector3f::new(1., 0., 0.), Vector3f::new(2., 1., 0.)];
+    assert!(NurbsCurve::interpolate(&points, 2, Parameterization::Centripetal, None).is_none());
+
+    // not enough points for the degree
+    assert!(NurbsCurve::interpolate(&vec![], 2, Parameterization::Uniform, None).is_none());
+    assert!(NurbsCurve::interpolate(&points[..2].to_vec(), 2, Parameterization::Uniform, None).is_none());
+}
+
+#[test]
+fn test_curve_approximate() {
+    // noisy samples of a quarter circle
+    let points: Vec<Vector3f> = (0..=100)
+        .map(|k| {
+            let t = k as Scalar / 100. * std::f64::consts::FRAC_PI_2;
+            let noise = 1e-3 * ((7 * k) % 5) as Scalar;
+            Vector3f::new(t.cos(), t.sin(), noise)
+        })
+        .collect();
+    let weights = vec![1.; points.len()];
+
+    let (curve, residuals) = NurbsCurve::approximate(
+        &points, &weights, 3, 8, Parameterization::ChordLength).unwrap();
+    assert_eq!(curve.size(), 8);
+    assert_eq!(residuals.len(), points.len());
+    assert!(residuals[0] < 1e-12 && residuals[100] < 1e-12);
+    assert!(residuals.iter().all(|r| *r < 5e-3));
+    let rms = (residuals.iter().map(|r| r * r).sum::<Scalar>() / residuals.len() as Scalar).sqrt();
+
+    // a heavy weight pulls the curve towards its point
+    let mut weights = weights;
+    weights[50] = 1e4;
+    let (_, residuals_w) = BSplineCurve::approximate(
+        &points, &weights, 3, 8, Parameterization::ChordLength).unwrap();
+    assert!(residuals_w[50] < residuals[50]);
+    assert!(residuals_w[50] < 0.1 * rms);
+
+    // as many control points as points minus one
+    let (_, residuals) = BSplineCurve::approximate(
+        &points[..10].to_vec(), &vec![1.; 10], 3, 9, Parameterization::Uniform).unwrap();
+    assert!(residuals.iter().all(|r| *r < 5e-3));
+
+    // invalid input
+    let method = Parameterization::Uniform;
+    assert!(NurbsCurve::approximate(&vec![], &vec![], 3, 4, method).is_none());
+    assert!(NurbsCurve::approximate(&points, &weights, 3, 0, method).is_none());
+    assert!(NurbsCurve::approximate(&points, &weights, 3, 101, method).is_none());
+    assert!(NurbsCurve::approximate(&points, &vec![1.; 10], 3, 8, method).is_none());
+    let mut weights = weights;
+    weights[3] = 0.;
+    assert!(NurbsCurve::approximate(&points, &weights, 3, 8, method).is_none());
+}
+
+#[test]
+fn test_surface_interpolate() {
+    let xs = [0., 0.1, 0.35, 0.5, 0.8, 1.];
+    let ys = [0., 0.3, 0.5, 0.9, 1.];
+    let mut points = Vec::new();
+    for x in &xs {
+        for y in &ys {
+            points.push(Vector3f::new(*x, *y, (3. * x).sin() * (2. * y).cos()));
+        }
+    }
+
+    let (surf, residuals) = NurbsSurface::interpolate(
+        &points, 6, 5, 3, 2, Parameterization::ChordLength).unwrap();
+    assert_eq!((surf.ctrlpts_size_u, surf.ctrlpts_size_v), (6, 5));
+    assert_eq!(residuals.len(), points.len());
+    assert!(residuals.iter().all(|r| *r < 1e-10));
+    assert_relative_eq!(surf.evaluate_single(0., 0.), points[0], epsilon = 1e-12);
+    assert_relative_eq!(surf.evaluate_single(1., 1.), points[29], epsilon = 1e-12);
+
+    // a bicubic polynomial is reproduced exactly
+    let poly = |u: Scalar, v: Scalar| Vector3f::new(u, v, u * u * v - v * v * v + 0.5 * u * v);
+    let mut points = Vec::new();
+    for i in 0..5 {
+        for j in 0..5 {
+            points.push(poly(i as Scalar / 4., j as Scalar / 4.));
+        }
+    }
+    let (surf, _) = BSplineSurface::interpolate(&points, 5, 5, 3, 3, Parameterization::Uniform).unwrap();
+    for i in 0..=10 {
+        for j in 0..=10 {
+            let (u, v) = (i as Scalar / 10., j as Scalar / 10.);
+            assert_relative_eq!(surf.evaluate_single(u, v), poly(u, v), epsilon = 1e-10);
+        }
+    }
+
+    // repeated row of points
+    for j in 0..5 {
+        points[5 + j] = points[10 + j];
+    }
+    assert!(BSplineSurface::interpolate(&points, 5, 5, 3, 3, Parameterization::ChordLength).is_none());
+
+    // grid not matching its size, or too small for the degrees
+    let method = Parameterization::Uniform;
+    assert!(NurbsSurface::interpolate(&vec![], 0, 0, 3, 3, method).is_none());
+    assert!(NurbsSurface::interpolate(&points, 5, 4, 3, 3, method).is_none());
+    assert!(NurbsSurface::interpolate(&points[..15].to_vec(), 5, 3, 3, 3, method).is_none());
+}
+
+#[test]
+fn test_surface_approximate() {
+    // scattered samples of a saddle, in a quasi-random order
+    let saddle = |x: Scalar, y: Scalar| Vector3f::new(x, y, 0.3 * (x * x - y * y));
+    let points: Vec<Vector3f> = (0..400)
+        .map(|k| {
+            let x = (k as Scalar * 0.618034).fract() * 2. - 1.;
+            let y = (k as Scalar * 0.754878 + 0.1).fract() * 2. - 1.;
+            saddle(x, y)
+        })
+        .collect();
+
+    let criteria = SurfaceFitCriteria::default();
+    let (surf, residuals) = NurbsSurface::approximate(&points, None, (3, 3), (6, 6), &criteria).unwrap();
+    assert_eq!(residuals.len(), points.len());
+    assert!(residuals.iter().all(|r| *r < 1e-3));
+    let proj = surf.project_point(&saddle(0.2, -0.4), &ProjectionCriteria::default());
+    assert!(proj.distance < 1e-3);
+
+    // wavy surface with a base plane: reparameterization improves the fit
+    let wave = |x: Scalar, y: Scalar| Vector3f::new(x, y, 0.4 * (2. * x).sin() * (2. * y).cos());
+    let points: Vec<Vector3f> = (0..400)
+        .map(|k| {
+            let x = (k as Scalar * 0.618034).fract() * 2. - 1.;
+            let y = (k as Scalar * 0.754878 + 0.1).fract() * 2. - 1.;
+            wave(x, y)
+        })
+        .collect();
+    let base = NurbsSurface::new(
+        vec![
+            Vector3f::new(-1., -1., 0.), Vector3f::new(-1., 1., 0.),
+            Vector3f::new( 1., -1., 0.), Vector3f::new( 1., 1., 0.),
+        ],
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    );
+    let rms = |r: &Vec<Scalar>| (r.iter().map(|x| x * x).sum::<Scalar>() / r.len() as Scalar).sqrt();
+
+    let mut criteria = SurfaceFitCriteria::default();
+    criteria.iterations = 0;
+    let (_, residuals_0) = NurbsSurface::approximate(&points, Some(&base), (2, 2), (4, 4), &criteria).unwrap();
+    criteria.iterations = 5;
+    let (surf, residuals_5) = NurbsSurface::approximate(&points, Some(&base), (2, 2), (4, 4), &criteria).unwrap();
+    assert!(rms(&residuals_5) < rms(&residuals_0));
+    assert!(rms(&residuals_5) < 0.05);
+    for (point, r) in points.iter().zip(residuals_5.iter()).step_by(20) {
+        let proj = surf.project_point(point, &ProjectionCriteria::default());
+        assert!(proj.distance <= r + 1e-9);
+    }
+
+    // without smoothing, control points far from the data are undetermined
+    criteria.smoothing = 0.;
+    let corner: Vec<Vector3f> = points.iter().filter(|p| p[0] < -0.5 && p[1] < -0.5).cloned().collect();
+    assert!(NurbsSurface::approximate(&corner, Some(&base), (2, 2), (4, 4), &criteria).is_none());
+
+    // fewer points than control points, or sizes too small for the degrees
+    assert!(NurbsSurface::approximate(&vec![], None, (2, 2), (4, 4), &criteria).is_none());
+    assert!(NurbsSurface::approximate(&points[..15].to_vec(), None, (2, 2), (4, 4), &criteria).is_none());
+    assert!(NurbsSurface::approximate(&points, None, (3, 3), (3, 6), &criteria).is_none());
+}
+
+fn nurbs_line(a: Vector3f, b: Vector3f) -> NurbsCurve {
+    NurbsCurve::new(vec![a, b], vec![0., 0., 1., 1.], 1, vec![1., 1.])
+}
+
+#[test]
+fn test_nurbs_surface_extrude_revolve() {
+    // cylinder by extruding a circle
+    let surf = NurbsSurface::extrude(&nurbs_circle(), &Vector3f::new(0., 0., 2.));
+    for i in 0..=10 {
+        for j in 0..=10 {
+            let (u, v) = (i as Scalar / 10., j as Scalar / 10.);
+            let p = surf.evaluate_single(u, v);
+            assert_relative_eq!(p[0] * p[0] + p[1] * p[1], 1., epsilon = 1e-12);
+            assert_relative_eq!(p[2], 2. * v, epsilon = 1e-12);
+        }
+    }
+
+    // cone by revolving a line about the z-axis, full turn and quarter turn
+    let line = nurbs_line(Vector3f::new(0., 0., 0.), Vector3f::new(1., 0., 1.));
+    let origin = Vector3f::zeros();
+    let axis = Vector3f::new(0., 0., 1.);
+    let cone = NurbsSurface::revolve(&line, &origin, &axis, 2. * std::f64::consts::PI);
+    assert_eq!((cone.ctrlpts_size_u, cone.ctrlpts_size_v), (9, 2));
+    assert!(cone.is_closed_u());
+    for i in 0..=10 {
+        for j in 0..=10 {
+            let (u, v) = (i as Scalar / 10., j as Scalar / 10.);
+            let p = cone.evaluate_single(u, v);
+            assert_relative_eq!((p[0] * p[0] + p[1] * p[1]).sqrt(), p[2], epsilon = 1e-12);
+            assert_relative_eq!(p[2], v, epsilon = 1e-12);
+        }
+    }
+
+    let cone = NurbsSurface::revolve(&line, &origin, &axis, std::f64::consts::FRAC_PI_2);
+    assert_eq!(cone.ctrlpts_size_u, 3);
+    assert_relative_eq!(cone.evaluate_single(1., 1.), Vector3f::new(0., 1., 1.), epsilon = 1e-12);
+
+    // torus by revolving an off-axis circle
+    let mut circle = nurbs_circle();
+    circle.bspline.ctrlpts.iter_mut().for_each(|p| {
+        let (x, y, w) = (p[0], p[1], p[3]);
+        *p = Vector4f::new(0.5 * x + 2. * w, 0., 0.5 * y, w);
+    });
+    let circle = NurbsCurve::from_bspline(circle.bspline);
+    let torus = NurbsSurface::revolve(&circle, &origin, &axis, 2. * std::f64::consts::PI);
+    for i in 0..=12 {
+        for j in 0..=12 {
+            let p = torus.evaluate_single(i as Scalar / 12., j as Scalar / 12.);
+            let r = (p[0] * p[0] + p[1] * p[1]).sqrt();
+            assert_relative_eq!((r - 2.).powi(2) + p[2] * p[2], 0.25, epsilon = 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_nurbs_surface_ruled_loft_sweep() {
+    // ruled surface between a line and a circle
+    let line = nurbs_line(Vector3f::new(-1., 0., 1.), Vector3f::new(1., 0., 1.));
+    let circle = nurbs_circle();
+    let surf = NurbsSurface::ruled(&circle, &line);
+    assert_eq!((surf.degree_u, surf.degree_v), (2, 1));
+    for i in 0..=20 {
+        let u = i as Scalar / 20.;
+        assert_relative_eq!(surf.evaluate_single(u, 0.), circle.curve_point(u), epsilon = 1e-12);
+        assert_relative_eq!(surf.evaluate_single(u, 1.), line.curve_point(u), epsilon = 1e-12);
+    }
+
+    // loft through circles of varying radii
+    let scaled = |r: Scalar, z: Scalar| {
+        let mut bspline = nurbs_circle().bspline;
+        bspline.ctrlpts.iter_mut().for_each(|p| {
+            *p = Vector4f::new(r * p[0], r * p[1], z * p[3], p[3]);
+        });
+        NurbsCurve::from_bspline(bspline)
+    };
+    let sections = vec![scaled(1., 0.), scaled(2., 1.), scaled(1.5, 2.), scaled(1., 3.)];
+    let surf = NurbsSurface::loft(&sections, 2, Parameterization::ChordLength).unwrap();
+    assert_eq!(surf.degree_v, 2);
+    assert_eq!(surf.ctrlpts_size_v, 4);
+    let criteria = ProjectionCriteria::default();
+    for section in &sections {
+        for i in 0..8 {
+            let point = section.curve_point(i as Scalar / 8.);
+            assert!(surf.project_point(&point, &criteria).distance < 1e-8);
+        }
+    }
+    for i in 0..=8 {
+        let u = i as Scalar / 8.;
+        assert_relative_eq!(surf.evaluate_single(u, 0.), sections[0].curve_point(u), epsilon = 1e-12);
+        assert_relative_eq!(surf.evaluate_single(u, 1.), sections[3].curve_point(u), epsilon = 1e-12);
+    }
+    let repeated = vec![scaled(1., 0.), scaled(2., 1.), scaled(2., 1.), scaled(1., 3.)];
+    assert!(NurbsSurface::loft(&repeated, 2, Parameterization::ChordLength).is_none());
+
+    // sweep a small circle along a straight and a curved trajectory
+    let profile = scaled(0.2, 0.);
+    let straight = nurbs_line(Vector3f::zeros(), Vector3f::new(0., 0., 3.));
+    let surf = NurbsSurface::sweep(&profile, &straight, 4).unwrap();
+    for i in 0..=10 {
+        for j in 0..=10 {
+            let p = surf.evaluate_single(i as Scalar / 10., j as Scalar / 10.);
+            assert_relative_eq!(p[0] * p[0] + p[1] * p[1], 0.04, epsilon = 1e-12);
+        }
+    }
+
+    let w = FRAC_1_SQRT_2 as Scalar;
+    let arc = NurbsCurve::new(
+        vec![Vector3f::new(0., 0., 0.), Vector3f::new(0., 0., 2.), Vector3f::new(2., 0., 2.)],
+        vec![0., 0., 0., 1., 1., 1.],
+        2,
+        vec![1., w, 1.],
+    );
+    let surf = NurbsSurface::sweep(&profile, &arc, 9).unwrap();
+    let end = Vector3f::new(2., 0., 2.);
+    for i in 0..=10 {
+        let u = i as Scalar / 10.;
+        assert_relative_eq!(surf.evaluate_single(u, 0.), profile.curve_point(u), epsilon = 1e-12);
+        let p = surf.evaluate_single(u, 1.) - end;
+        assert_relative_eq!(p.norm(), 0.2, epsilon = 1e-9);
+        assert_relative_eq!(p[0], 0., epsilon = 1e-9);
+    }
+    for j in 0..=16 {
+        let v = j as Scalar / 16.;
+        let center = arc.curve_point(v);
+        let p = surf.evaluate_single(0.3, v);
+        assert_relative_eq!((p - center).norm(), 0.2, epsilon = 2e-2);
+    }
+
+    // the frames are undefined where the tangent of the trajectory vanishes
+    let stalled = NurbsCurve::new(
+        vec![Vector3f::zeros(), Vector3f::zeros(), Vector3f::new(0., 0., 2.)],
+        vec![0., 0., 0., 1., 1., 1.],
+        2,
+        vec![1.; 3],
+    );
+    assert!(NurbsSurface::sweep(&profile, &stalled, 4).is_none());
+    assert!(NurbsSurface::sweep(&profile, &straight, 1).is_none());
+    assert!(NurbsSurface::loft(&sections[..2].to_vec(), 2, Parameterization::ChordLength).is_none());
+}
+
+#[test]
+fn test_nurbs_conics() {
+    let center = Vector3f::new(1., 2., 3.);
+    let x = Vector3f::new(1., 0., 0.);
+    let y = Vector3f::new(0., 0., 1.);
+
+    let circle = NurbsCurve::circle(&center, &x, &y, 2.);
+    assert_relative_eq!(circle.curve_point(0.), center + 2. * x, epsilon = 1e-12);
+    assert_relative_eq!(circle.curve_point(1.), center + 2. * x, epsilon = 1e-12);
+    for i in 0..=40 {
+        let p = circle.curve_point(i as Scalar / 40.) - center;
+        assert_relative_eq!(p.norm(), 2., epsilon = 1e-12);
+        assert_relative_eq!(p[1], 0., epsilon = 1e-12);
+    }
+
+    let ellipse = NurbsCurve::ellipse(&center, &x, &y, 3., 1.);
+    for i in 0..=40 {
+        let p = ellipse.curve_point(i as Scalar / 40.) - center;
+        assert_relative_eq!(p[0] * p[0] / 9. + p[2] * p[2], 1., epsilon = 1e-12);
+    }
+
+    // arcs of more or less than 90 degrees
+    for &(start, end) in &[(0.3, 1.2), (-1., 2.), (0.5, 5.)] {
+        let arc = NurbsCurve::arc(&center, &x, &y, 0.5, start, end);
+        let point = |t: Scalar| center + 0.5 * (t.cos() * x + t.sin() * y);
+        assert_relative_eq!(arc.curve_point(0.), point(start), epsilon = 1e-12);
+        assert_relative_eq!(arc.curve_point(1.), point(end), epsilon = 1e-12);
+        for i in 0..=20 {
+            let p = arc.curve_point(i as Scalar / 20.) - center;
+            assert_relative_eq!(p.norm(), 0.5, epsilon = 1e-12);
+            let t = p[2].atan2(p[0]);
+            let t = if t < start - 1e-9 { t + 2. * std::f64::consts::PI } else { t };
+            assert!(t >= start - 1e-9 && t <= end + 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_nurbs_primitives() {
+    let center = Vector3f::new(0.5, -1., 2.);
+    let axis = Vector3f::new(1., 1., 0.).normalize();
+    let samples = |surf: &NurbsSurface| {
+        let mut points = Vec::new();
+        for i in 0..=12 {
+            for j in 0..=12 {
+                let (u, v) = (i as Scalar / 12., j as Scalar / 12.);
+                points.push((surf.evaluate_single(u, v), (u, v)));
+            }
+        }
+        points
+    };
+    // distance along the axis and to the axis
+    let cylindrical = |p: Vector3f| {
+        let h = (p - center).dot(&axis);
+        (h, (p - center - h * axis).norm())
+    };
+
+    let cylinder = NurbsSurface::cylinder(&center, &axis, 0.3, 2.);
+    for (p, (_, v)) in samples(&cylinder) {
+        let (h, r) = cylindrical(p);
+        assert_relative_eq!(h, 2. * v, epsilon = 1e-12);
+        assert_relative_eq!(r, 0.3, epsilon = 1e-12);
+    }
+
+    let cone = NurbsSurface::cone(&center, &axis, 1., 0.5, 2.);
+    for (p, _) in samples(&cone) {
+        let (h, r) = cylindrical(p);
+        assert_relative_eq!(r, 1. - h / 4., epsilon = 1e-12);
+    }
+
+    let sphere = NurbsSurface::sphere(&center, &axis, 1.5);
+    for (p, (u, v)) in samples(&sphere) {
+        assert_relative_eq!((p - center).norm(), 1.5, epsilon = 1e-12);
+        if v > 0.01 && v < 0.99 {
+            assert!(sphere.normal(u, v).dot(&(p - center)) > 0.);
+        }
+    }
+    assert_relative_eq!(sphere.evaluate_single(0.3, 0.), center - 1.5 * axis, epsilon = 1e-12);
+    assert_relative_eq!(sphere.evaluate_single(0.3, 1.), center + 1.5 * axis, epsilon = 1e-12);
+
+    let torus = NurbsSurface::torus(&center, &axis, 2., 0.5);
+    for (p, (u, v)) in samples(&torus) {
+        let (h, r) = cylindrical(p);
+        assert_relative_eq!((r - 2.).powi(2) + h * h, 0.25, epsilon = 1e-12);
+        let tube = p - center - h * axis;
+        let tube = p - (center + 2. * tube.normalize());
+        assert!(torus.normal(u, v).dot(&tube) > 0.);
+    }
+
+    let disk = NurbsSurface::disk(&center, &axis, 1.);
+    for (p, (u, v)) in samples(&disk) {
+        let (h, r) = cylindrical(p);
+        assert_relative_eq!(h, 0., epsilon = 1e-12);
+        assert_relative_eq!(r, v, epsilon = 1e-12);
+        if v > 0.01 {
+            assert_relative_eq!(disk.normal(u, v), axis, epsilon = 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_link_geometry_to_nurbs() {
+    use crate::robotics::link::Geometry;
+
+    let geometry = Geometry::Box { depth: 1., width: 2., height: 3. };
+    let faces = geometry.to_nurbs().unwrap();
+    assert_eq!(faces.len(), 6);
+    let half = Vector3f::new(0.5, 1., 1.5);
+    for face in &faces {
+        let center = face.evaluate_single(0.5, 0.5);
+        let normal = face.normal(0.5, 0.5);
+        let a = (0..3).find(|&a| (center[a].abs() - half[a]).abs() < 1e-12).unwrap();
+        assert_relative_eq!(normal[a], center[a].signum(), epsilon = 1e-12);
+    }
+
+    let geometry = Geometry::Capsule { radius: 0.5, length: 2. };
+    let surfaces = geometry.to_nurbs().unwrap();
+    assert_eq!(surfaces.len(), 3);
+    for surf in &surfaces {
+        for i in 0..=10 {
+            for j in 0..=10 {
+                let p = surf.evaluate_single(i as Scalar / 10., j as Scalar / 10.);
+                // distance to the segment of the capsule
+                let z = p[2].max(-1.).min(1.);
+                let d = (p - Vector3f::new(0., 0., z)).norm();
+                assert_relative_eq!(d, 0.5, epsilon = 1e-12);
+            }
+        }
+    }
+    let tree = OBBTree::from_nurbs_surface(&surfaces[1], 3);
+    assert!(tree.collect_base_obb().iter().all(|obb| obb.pos[2] < -1.));
+
+    let geometry = Geometry::Cylinder { radius: 0.5, length: 2. };
+    let surfaces = geometry.to_nurbs().unwrap();
+    assert_eq!(surfaces.len(), 3);
+    assert_relative_eq!(surfaces[1].normal(0.3, 0.5), Vector3f::new(0., 0., -1.), epsilon = 1e-9);
+    assert_relative_eq!(surfaces[2].normal(0.3, 0.5), Vector3f::new(0., 0., 1.), epsilon = 1e-9);
+
+    let geometry = Geometry::Sphere { radius: 0.5 };
+    assert_eq!(geometry.to_nurbs().unwrap().len(), 1);
+}
+
+/// Plane over `[-2, 2] x [-2, 2]` trimmed to the square `[0.1, 0.9]^2` of
+/// its parameters, with a circular hole of radius 0.25 at the center.
+fn trimmed_plane() -> TrimmedNurbsSurface {
+    let outer = TrimLoop::from_polygon(&vec![(0.1, 0.1), (0.9, 0.1), (0.9, 0.9), (0.1, 0.9)]);
+    let hole = NurbsCurve::circle(&Vector3f::new(0.5, 0.5, 0.),
+                                  &Vector3f::new(1., 0., 0.), &Vector3f::new(0., 1., 0.), 0.25);
+    TrimmedNurbsSurface::new(nurbs_plane(0.), Some(outer), vec![TrimLoop::new(vec![hole])])
+}
+
+#[test]
+fn test_trimmed_surface_classification() {
+    let surf = trimmed_plane();
+    assert!(surf.is_inside(0.15, 0.5));
+    assert!(surf.is_inside(0.8, 0.8));
+    assert!(surf.is_inside(0.1, 0.5));
+    assert!(surf.is_inside(0.9, 0.9));
+    assert!(!surf.is_inside(0.05, 0.5));
+    assert!(!surf.is_inside(0.5, 0.5));
+    assert!(!surf.is_inside(0.5, 0.72));
+    assert!(surf.is_inside(0.5, 0.78));
+
+    assert_eq!(surf.classify((0.15, 0.2, 0.15, 0.2)), Containment::Inside);
+    assert_eq!(surf.classify((0.45, 0.55, 0.45, 0.55)), Containment::Outside);
+    assert_eq!(surf.classify((0., 0.05, 0., 1.)), Containment::Outside);
+    assert_eq!(surf.classify((0.05, 0.15, 0.4, 0.5)), Containment::Crossing);
+    assert_eq!(surf.classify((0.6, 0.8, 0.4, 0.6)), Containment::Crossing);
+
+    // without outer loop, the whole domain is inside, boundary included
+    let surf = TrimmedNurbsSurface::new(nurbs_plane(0.), None, vec![]);
+    assert!(surf.is_inside(0.01, 0.99));
+    assert!(surf.is_inside(1., 1.));
+    assert!(surf.is_inside(0., 0.5));
+    assert_eq!(surf.classify((0., 1., 0., 1.)), Containment::Inside);
+    assert_eq!(surf.classify((0.9, 1., 0.9, 1.)), Containment::Inside);
+
+    let hole = TrimLoop::from_polygon(&vec![(0.4, 0.4), (0.6, 0.4), (0.6, 0.6), (0.4, 0.6)]);
+    let surf = TrimmedNurbsSurface::new(nurbs_plane(0.), None, vec![hole]);
+    assert!(surf.is_inside(1., 1.));
+    assert!(!surf.is_inside(0.5, 0.5));
+    assert_eq!(surf.classify((0.9, 1., 0.9, 1.)), Containment::Inside);
+    assert_eq!(surf.classify((0.45, 0.55, 0.45, 0.55)), Containment::Outside);
+}
+
+#[test]
+fn test_trimmed_surface_tessellate() {
+    let surf = trimmed_plane();
+    let tess = surf.tessellate(&TessellationCriteria::default());
+    assert!(!tess.triangles.is_empty());
+
+    let hole = |(u, v): (Scalar, Scalar)| ((u - 0.5).powi(2) + (v - 0.5).powi(2)).sqrt();
+    for &(u, v) in &tess.params {
+        assert!(u >= 0.1 - 1e-12 && u <= 0.9 + 1e-12 && v >= 0.1 - 1e-12 && v <= 0.9 + 1e-12);
+        assert!(hole((u, v)) >= 0.25 - 1e-3);
+    }
+
+    // the area of the mesh is the area of the trimmed domain, scaled by 16
+    let area: Scalar = tess.triangles.iter()
+        .map(|t| {
+            let (a, b, c) = (tess.vertices[t[0]], tess.vertices[t[1]], tess.vertices[t[2]]);
+            (b - a).cross(&(c - a)).norm() / 2.
+        })
+        .sum();
+    let expected = 16. * (0.64 - std::f64::consts::PI * 0.0625);
+    assert_relative_eq!(area, expected, max_relative = 1e-3);
+
+    for (p, n) in tess.vertices.iter().zip(&tess.normals) {
+        assert_relative_eq!(p[2], 0., epsilon = 1e-12);
+        assert_relative_eq!(n[2].abs(), 1., epsilon = 1e-12);
+    }
+}
+
+#[test]
+fn test_trimmed_surface_obb_tree() {
+    let trimmed = trimmed_plane();
+    let tree = OBBTree::from_trimmed_nurbs_surface(&trimmed, 6);
+    let full = OBBTree::from_nurbs_surface(&trimmed.surface, 6);
+    let num_leaves = tree.collect_base_obb().len();
+    assert!(num_leaves > 0 && num_leaves < full.collect_base_obb().len());
+
+    // small vertical patch through the hole
+    let patch = NurbsSurface::new(
+        vec![Vector3f::new(-0.2, 0., -0.2), Vector3f::new(-0.2, 0., 0.2),
+             Vector3f::new( 0.2, 0., -0.2), Vector3f::new( 0.2, 0., 0.2)],
+        vec![0., 0., 1., 1.],
+        vec![0., 0., 1., 1.],
+        1, 1, 2, 2,
+        vec![1.; 4],
+    );
+    let patch_tree = OBBTree::from_nurbs_surface(&patch, 6);
+    assert!(full.intersect(&patch_tree, 1e-9));
+    assert!(!tree.intersect(&patch_tree, 1e-9));
+    assert!(tree.collect_leaf_pairs(&patch_tree, 1e-9).is_empty());
+
+    // the bounding box only covers the patches of the kept half of the plane
+    let half = TrimmedNurbsSurface::new(
+        nurbs_plane(0.),
+        Some(TrimLoop::from_polygon(&vec![(0., 0.), (0.5, 0.), (0.5, 1.), (0., 1.)])),
+        vec![],
+    );
+    let obb = OBB::from(&half);
+    for &(x, inside) in &[(-1.9, true), (-0.1, true), (1.5, false), (1.9, false)] {
+        let local = obb.axis.transpose() * (Vector3f::new(x, 0., 0.) - obb.pos);
+        let contained = (0..3).all(|i| local[i].abs() <= obb.r[i] + 1e-9);
+        assert_eq!(contained, inside);
+    }
+}
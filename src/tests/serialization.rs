@@ -0,0 +1,116 @@
+use crate::geometry::*;
+use crate::math::*;
+use crate::ccd::OBB;
+use crate::robotics::RigidBodyTree;
+use crate::utils::{load_json, save_json};
+
+fn roundtrip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_serialize_curves_and_surfaces() {
+    let curve = NurbsCurve::circle(&Vector3f::zeros(), &Vector3f::x(), &Vector3f::y(), 2.);
+    let copy: NurbsCurve = roundtrip(&curve);
+    assert_eq!(copy.ctrlpts, curve.ctrlpts);
+    assert_eq!(copy.weights, curve.weights);
+    assert_eq!(copy.knotvec, curve.knotvec);
+    for &u in &[0., 0.3, 0.77] {
+        assert!((copy.curve_point(u) - curve.curve_point(u)).norm() < 1e-12);
+    }
+
+    let copy: BSplineCurve<U4> = roundtrip(&curve.bspline);
+    assert_eq!(copy.ctrlpts, curve.bspline.ctrlpts);
+
+    let mut surf = NurbsSurface::torus(&Vector3f::zeros(), &Vector3f::z(), 3., 1.);
+    surf.name = "torus".to_string();
+    let copy: NurbsSurface = roundtrip(&surf);
+    assert_eq!(copy.name, "torus");
+    assert_eq!(copy.ctrlpts, surf.ctrlpts);
+    assert_eq!(copy.weights, surf.weights);
+    for &(u, v) in &[(0.1, 0.2), (0.5, 0.9), (0.8, 0.4)] {
+        assert!((copy.evaluate_single(u, v) - surf.evaluate_single(u, v)).norm() < 1e-12);
+    }
+
+    let copy: BSplineSurface<U4> = roundtrip(&surf.bspline);
+    assert_eq!(copy.ctrlpts, surf.bspline.ctrlpts);
+    assert_eq!((copy.size_u, copy.size_v), (surf.bspline.size_u, surf.bspline.size_v));
+
+    // inconsistent data is rejected
+    let json = r#"{"version":1,"degree":1,"knotvec":[0,0,1,1],"ctrlpts":[[0,0,0],[1,0,0]],"weights":[1,1]}"#;
+    assert!(serde_json::from_str::<NurbsCurve>(json).is_ok());
+    let json = r#"{"version":1,"degree":2,"knotvec":[0,0,1,1],"ctrlpts":[[0,0,0],[1,0,0]],"weights":[1,1]}"#;
+    assert!(serde_json::from_str::<NurbsCurve>(json).is_err());
+    let json = r#"{"version":1,"degree":1,"knotvec":[0,0,1,1],"ctrlpts":[[0,0,0],[1,0,0]],"weights":[1,0]}"#;
+    assert!(serde_json::from_str::<NurbsCurve>(json).is_err());
+}
+
+#[test]
+fn test_serialize_version() {
+    let curve = NurbsCurve::circle(&Vector3f::zeros(), &Vector3f::x(), &Vector3f::y(), 2.);
+    let value = serde_json::to_value(&curve).unwrap();
+    assert_eq!(value["version"], 1);
+
+    // missing or unknown versions are rejected
+    let mut value = value;
+    value["version"] = 2.into();
+    assert!(serde_json::from_value::<NurbsCurve>(value.clone()).is_err());
+    value.as_object_mut().unwrap().remove("version");
+    assert!(serde_json::from_value::<NurbsCurve>(value).is_err());
+
+    let rbtree = RigidBodyTree::from_urdf_file("resource/sample.urdf").unwrap();
+    let value = serde_json::to_value(&rbtree).unwrap();
+    assert_eq!(value["version"], 1);
+}
+
+#[test]
+fn test_serialize_obb() {
+    let points = vec![
+        Vector3f::new(0., 0., 0.),
+        Vector3f::new(1., 2., 0.),
+        Vector3f::new(2., 1., 1.),
+        Vector3f::new(-1., 0.5, 2.),
+    ];
+    let obb = OBB::from_point_cloud(&points);
+    let copy: OBB = roundtrip(&obb);
+    assert_eq!(copy.pos, obb.pos);
+    assert_eq!(copy.r, obb.r);
+    assert_eq!(copy.axis, obb.axis);
+    assert!(copy.scene_node.is_none());
+}
+
+#[test]
+fn test_serialize_rigid_body_tree() {
+    let mut rbtree = RigidBodyTree::from_urdf_file("resource/sample.urdf").unwrap();
+    rbtree.set_gravity(Vector3f::new(0., -9.8, 0.));
+
+    let path = std::env::temp_dir().join("crobot_rbtree.json");
+    save_json(&path, &rbtree).unwrap();
+    let copy: RigidBodyTree = load_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(copy.num_body(), rbtree.num_body());
+    assert_eq!(copy.num_dof(), rbtree.num_dof());
+    assert_eq!(copy.get_base_name(), rbtree.get_base_name());
+    assert_eq!(copy.get_gravity(), rbtree.get_gravity());
+    for name in rbtree.get_body_names() {
+        let (a, b) = (rbtree.get_body(&name), copy.get_body(&name));
+        assert_eq!(a.index, b.index);
+        assert_eq!(a.parent_index, b.parent_index);
+        assert_eq!(a.qpos_dof_map, b.qpos_dof_map);
+        assert_eq!(a.joint.screw_axis, b.joint.screw_axis);
+        assert_eq!(a.joint.tform_jnt2parent, b.joint.tform_jnt2parent);
+        assert_eq!(a.joint.qpos_limit.map(|r| (r.min, r.max)), b.joint.qpos_limit.map(|r| (r.min, r.max)));
+        assert_eq!(a.link.inertial.mass, b.link.inertial.mass);
+        assert_eq!(a.link.visuals.len(), b.link.visuals.len());
+    }
+}
+
+#[test]
+fn test_deserialize_missing_mesh() {
+    let rbtree = RigidBodyTree::from_urdf_file("resource/sample.urdf").unwrap();
+    let json = serde_json::to_string(&rbtree).unwrap();
+    let json = json.replacen("universal/base.stl", "universal/missing.stl", 1);
+    assert!(serde_json::from_str::<RigidBodyTree>(&json).is_err());
+}
@@ -0,0 +1,116 @@
+use crate::geometry::*;
+use crate::math::*;
+use crate::robotics::*;
+use crate::utils::{IndexedMesh, load_indexed_mesh};
+use std::f64::consts::PI;
+
+/// Tessellates the NURBS surfaces of a primitive into a single mesh.
+fn primitive_mesh(geometry: &Geometry) -> IndexedMesh {
+    let criteria = TessellationCriteria::default();
+    let mut mesh = IndexedMesh { vertices: Vec::new(), faces: Vec::new() };
+    for surf in geometry.to_nurbs().unwrap() {
+        let part = surf.tessellate(&criteria).to_indexed_mesh();
+        let offset = mesh.vertices.len();
+        mesh.vertices.extend(part.vertices);
+        mesh.faces.extend(part.faces.into_iter().map(|mut face| {
+            for k in 0..3 {
+                face.vertices[k] += offset;
+            }
+            face
+        }));
+    }
+    return mesh;
+}
+
+fn assert_same_props(a: &MassProperties, b: &MassProperties, epsilon: Scalar) {
+    assert_relative_eq!(a.volume, b.volume, max_relative = epsilon);
+    assert_relative_eq!(a.mass, b.mass, max_relative = epsilon);
+    assert_relative_eq!(a.center_of_mass, b.center_of_mass, epsilon = epsilon);
+    assert_relative_eq!(a.inertia, b.inertia, epsilon = epsilon * a.inertia.norm());
+}
+
+#[test]
+fn test_mass_properties_primitives() {
+    let density = 2.5;
+    let geometries = vec![
+        Geometry::Box { depth: 1., width: 2., height: 3. },
+        Geometry::Cylinder { radius: 0.5, length: 2. },
+        Geometry::Capsule { radius: 0.5, length: 1. },
+        Geometry::Sphere { radius: 0.8 },
+    ];
+    for geometry in &geometries {
+        let expected = geometry.mass_properties(density).unwrap();
+        let mesh = primitive_mesh(geometry);
+        assert_same_props(&mesh.mass_properties(density), &expected, 5e-3);
+        assert_relative_eq!(mesh.volume(), expected.volume, max_relative = 5e-3);
+    }
+
+    // the box is exact, also when moved and with faces turned inwards
+    let geometry = &geometries[0];
+    let tform = Isometry3f::new(Vector3f::new(1., -2., 0.5), Vector3f::new(0.3, -0.2, 0.9));
+    let expected = geometry.mass_properties(density).unwrap().transform(&tform);
+    let mut mesh = primitive_mesh(geometry);
+    for v in mesh.vertices.iter_mut() {
+        let p = tform.transform_point(&Point3f::new(v[0] as Scalar, v[1] as Scalar, v[2] as Scalar));
+        *v = [p[0] as f32, p[1] as f32, p[2] as f32];
+    }
+    assert_same_props(&mesh.mass_properties(density), &expected, 1e-6);
+    for face in mesh.faces.iter_mut() {
+        face.vertices.swap(1, 2);
+    }
+    assert_same_props(&mesh.mass_properties(density), &expected, 1e-6);
+}
+
+#[test]
+fn test_mass_properties_combine() {
+    // two unit cubes side by side make a 2x1x1 box
+    let cube = Geometry::Box { depth: 1., width: 1., height: 1. }.mass_properties(1.).unwrap();
+    let left = cube.transform(&Isometry3f::translation(-0.5, 0., 0.));
+    let right = cube.transform(&Isometry3f::translation(0.5, 0., 0.));
+    let expected = Geometry::Box { depth: 2., width: 1., height: 1. }.mass_properties(1.).unwrap();
+    assert_same_props(&left.combine(&right), &expected, 1e-12);
+}
+
+#[test]
+fn test_link_inertial_from_collisions() {
+    let density = 1000.;
+    let mut link = Link::default();
+    assert!(link.inertial_from_collisions(density).is_err());
+
+    // sphere on top of a box
+    let origin = Isometry3f::translation(0., 0., 1.);
+    link.collisions.push(Collision::new(
+        "box".to_string(), Isometry3f::identity(),
+        Geometry::Box { depth: 1., width: 1., height: 1. }));
+    link.collisions.push(Collision::new(
+        "sphere".to_string(), origin, Geometry::Sphere { radius: 0.5 }));
+    let inertial = link.inertial_from_collisions(density).unwrap();
+
+    let sphere_mass = density * 4. / 3. * PI * 0.125;
+    let mass = density + sphere_mass;
+    assert_relative_eq!(inertial.mass, mass, max_relative = 1e-12);
+    assert_relative_eq!(inertial.bvec_com, Vector3f::new(0., 0., sphere_mass / mass), epsilon = 1e-12);
+    let izz = density / 6. + sphere_mass * 0.1;
+    assert_relative_eq!(inertial.inertia_com[(2, 2)], izz, max_relative = 1e-12);
+    assert_relative_eq!(inertial.inertia_body[(2, 2)], izz, max_relative = 1e-12);
+    assert_relative_eq!(inertial.inertia_body[(0, 0)],
+                        density / 6. + sphere_mass * (0.1 + 1.), max_relative = 1e-12);
+
+    // closed mesh of the sample robot
+    let filename = "resource/mesh/universal/base.stl";
+    let geometry = Geometry::Mesh {
+        filename: filename.to_string(),
+        scale: Vector3f32::new(1., 1., 1.),
+        mesh: crate::utils::load_mesh(filename).unwrap(),
+    };
+    let props = geometry.mass_properties(density).unwrap();
+    let mesh = load_indexed_mesh(filename).unwrap();
+    assert!(props.mass > 0.);
+    assert_relative_eq!(props.volume, mesh.volume(), max_relative = 1e-12);
+    let eigenvalues = props.inertia.symmetric_eigenvalues();
+    for k in 0..3 {
+        assert!(eigenvalues[k] > 0.);
+        // triangle inequality of the principal moments
+        assert!(eigenvalues[k] <= eigenvalues[(k + 1) % 3] + eigenvalues[(k + 2) % 3] + 1e-12);
+    }
+}
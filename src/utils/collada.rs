@@ -0,0 +1,380 @@
+//! Reading of the geometry of [COLLADA](https://www.khronos.org/collada/)
+//! (`.dae`) files.
+//!
+//! The meshes instantiated by the nodes of the scene are merged into a single
+//! mesh, with the node transforms applied. Coordinates are converted to
+//! meters and to the z-up convention of URDF, following the `unit` and
+//! `up_axis` of the asset. The `triangles`, `polylist` and `polygons`
+//! primitives are supported, and polygons are split into triangle fans.
+//! Normals are computed from the vertices.
+
+use crate::math::*;
+use crate::utils::{IndexedMesh, IndexedTriangle, triangle_normal};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result};
+use xml::reader::{EventReader, XmlEvent};
+
+/// Maximum depth of the node hierarchy, which also guards against cyclic
+/// `instance_node` references.
+const MAX_NODE_DEPTH: usize = 64;
+
+fn invalid_data<S: Into<String>>(msg: S) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// Element of an XML document.
+#[derive(Debug, Default)]
+struct XmlNode {
+    name:       String,
+    attributes: HashMap<String, String>,
+    children:   Vec<XmlNode>,
+    text:       String,
+}
+
+impl XmlNode {
+
+    /// Parses an XML document, returning its root element.
+    fn parse<R: Read>(read: R) -> Result<XmlNode> {
+        let mut stack: Vec<XmlNode> = vec![XmlNode::default()];
+        for event in EventReader::new(read) {
+            match event.map_err(|e| invalid_data(e.to_string()))? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    stack.push(XmlNode {
+                        name: name.local_name,
+                        attributes: attributes.into_iter()
+                            .map(|a| (a.name.local_name, a.value))
+                            .collect(),
+                        children: Vec::new(),
+                        text: String::new(),
+                    });
+                },
+                XmlEvent::EndElement { .. } => {
+                    let node = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(node);
+                },
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    let node = stack.last_mut().unwrap();
+                    node.text.push(' ');
+                    node.text.push_str(&text);
+                },
+                _ => {},
+            }
+        }
+        stack.pop().and_then(|document| document.children.into_iter().next())
+            .ok_or_else(|| invalid_data("empty XML document"))
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|s| s.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a XmlNode> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn numbers<T: std::str::FromStr>(&self) -> Result<Vec<T>> {
+        self.text.split_whitespace()
+            .map(|t| t.parse::<T>().map_err(|_| invalid_data(format!("invalid number '{}' in <{}>", t, self.name))))
+            .collect()
+    }
+
+    /// Maps the ids of this element and its descendants to the elements.
+    fn index<'a>(&'a self, ids: &mut HashMap<&'a str, &'a XmlNode>) {
+        if let Some(id) = self.attr("id") {
+            ids.insert(id, self);
+        }
+        for child in &self.children {
+            child.index(ids);
+        }
+    }
+}
+
+/// Input of a mesh primitive: the source of one of the indices of a corner.
+struct Input<'a> {
+    semantic: &'a str,
+    source:   &'a str,
+    offset:   usize,
+}
+
+struct ColladaReader<'a> {
+    ids:  HashMap<&'a str, &'a XmlNode>,
+    mesh: IndexedMesh,
+}
+
+impl<'a> ColladaReader<'a> {
+
+    /// Resolves a `#id` reference.
+    fn lookup(&self, url: &str) -> Result<&'a XmlNode> {
+        let id = url.trim_start_matches('#');
+        self.ids.get(id).cloned()
+            .ok_or_else(|| invalid_data(format!("unresolved reference '{}'", url)))
+    }
+
+    /// Reads the points of a `source` element, as given by its accessor.
+    fn read_points(&self, source: &XmlNode) -> Result<Vec<Vector3f>> {
+        let array: Vec<Scalar> = source.child("float_array")
+            .ok_or_else(|| invalid_data("source without float_array"))?
+            .numbers()?;
+        let accessor = source.child("technique_common").and_then(|t| t.child("accessor"));
+        let param = |name: &str, default: usize| accessor
+            .and_then(|a| a.attr(name))
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(default);
+        let stride = param("stride", 3);
+        let offset = param("offset", 0);
+        if stride < 3 {
+            return Err(invalid_data("source stride smaller than 3"));
+        }
+        let count = param("count", array.len().saturating_sub(offset) / stride);
+        let end = count.checked_mul(stride).and_then(|n| n.checked_add(offset));
+        if end.map_or(true, |end| end > array.len()) {
+            return Err(invalid_data("source accessor exceeds its array"));
+        }
+        Ok((0..count)
+            .map(|i| Vector3f::from_column_slice(&array[offset + i * stride..offset + i * stride + 3]))
+            .collect())
+    }
+
+    /// Reads the positions of the vertices of a mesh, following its
+    /// `vertices` element.
+    fn read_positions(&self, url: &str) -> Result<Vec<Vector3f>> {
+        let vertices = self.lookup(url)?;
+        let url = if vertices.name == "vertices" {
+            vertices.children("input")
+                .find(|i| i.attr("semantic") == Some("POSITION"))
+                .and_then(|i| i.attr("source"))
+                .ok_or_else(|| invalid_data("vertices without POSITION input"))?
+        } else {
+            url
+        };
+        self.read_points(self.lookup(url)?)
+    }
+
+    /// Appends the faces of a geometry, with its vertices transformed.
+    fn add_geometry(&mut self, geometry: &XmlNode, tform: &Matrix4f) -> Result<()> {
+        let mesh = match geometry.child("mesh") {
+            Some(mesh) => mesh,
+            None => return Ok(()),      // splines and convex meshes are ignored
+        };
+
+        // a mirroring transform flips the orientation of the faces
+        let flip = tform.fixed_slice::<U3, U3>(0, 0).determinant() < 0.;
+        // base index and count of the transformed positions of each source
+        let mut bases: HashMap<&str, (usize, usize)> = HashMap::new();
+        for primitive in &mesh.children {
+            match primitive.name.as_str() {
+                "triangles" | "polylist" | "polygons" => {},
+                _ => continue,          // lines and linestrips are ignored
+            }
+            let inputs: Vec<Input> = primitive.children("input")
+                .map(|i| Input {
+                    semantic: i.attr("semantic").unwrap_or(""),
+                    source: i.attr("source").unwrap_or(""),
+                    offset: i.attr("offset").and_then(|o| o.parse().ok()).unwrap_or(0),
+                })
+                .collect();
+            let stride = inputs.iter().map(|i| i.offset + 1).max().unwrap_or(1);
+            let vertex = inputs.iter().find(|i| i.semantic == "VERTEX")
+                .ok_or_else(|| invalid_data(format!("<{}> without VERTEX input", primitive.name)))?;
+
+            let (base, count) = match bases.get(vertex.source) {
+                Some(&base) => base,
+                None => {
+                    let positions = self.read_positions(vertex.source)?;
+                    let base = (self.mesh.vertices.len(), positions.len());
+                    for p in &positions {
+                        let p = tform.transform_point(&Point3f::from(*p));
+                        self.mesh.vertices.push([p[0] as f32, p[1] as f32, p[2] as f32]);
+                    }
+                    bases.insert(vertex.source, base);
+                    base
+                },
+            };
+
+            // position indices of the polygons
+            let mut polygons: Vec<Vec<usize>> = Vec::new();
+            let mut indices: Vec<usize> = Vec::new();
+            for p in primitive.children("p") {
+                let p: Vec<usize> = p.numbers()?;
+                if primitive.name == "polygons" {
+                    polygons.push(p.iter().skip(vertex.offset).step_by(stride).cloned().collect());
+                } else {
+                    indices.extend(p.iter().skip(vertex.offset).step_by(stride));
+                }
+            }
+            let vcount: Vec<usize> = match primitive.name.as_str() {
+                "triangles" => vec![3; indices.len() / 3],
+                "polylist" => primitive.child("vcount")
+                    .ok_or_else(|| invalid_data("polylist without vcount"))?
+                    .numbers()?,
+                _ => Vec::new(),
+            };
+            let mut start = 0;
+            for n in vcount {
+                if start + n > indices.len() {
+                    return Err(invalid_data(format!("<{}> has too few indices", primitive.name)));
+                }
+                polygons.push(indices[start..start + n].to_vec());
+                start += n;
+            }
+
+            for polygon in polygons {
+                if polygon.len() < 3 {
+                    continue;
+                }
+                if let Some(i) = polygon.iter().find(|i| **i >= count) {
+                    return Err(invalid_data(format!("vertex index {} out of range", i)));
+                }
+                for k in 1..polygon.len() - 1 {
+                    let mut face = [base + polygon[0], base + polygon[k], base + polygon[k + 1]];
+                    if flip {
+                        face.swap(1, 2);
+                    }
+                    let vertices = &self.mesh.vertices;
+                    let normal = triangle_normal(&vertices[face[0]], &vertices[face[1]], &vertices[face[2]]);
+                    self.mesh.faces.push(IndexedTriangle { normal, vertices: face });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the transform of a node relative to its parent, the product
+    /// of its transformation elements in document order.
+    fn node_transform(node: &XmlNode) -> Result<Matrix4f> {
+        let mut tform = Matrix4f::identity();
+        for child in &node.children {
+            let local = match child.name.as_str() {
+                "matrix" => {
+                    let m: Vec<Scalar> = child.numbers()?;
+                    if m.len() != 16 {
+                        return Err(invalid_data("matrix needs 16 values"));
+                    }
+                    Matrix4f::from_row_slice(&m)
+                },
+                "translate" => {
+                    let t: Vec<Scalar> = child.numbers()?;
+                    if t.len() != 3 {
+                        return Err(invalid_data("translate needs 3 values"));
+                    }
+                    Matrix4f::new_translation(&Vector3f::new(t[0], t[1], t[2]))
+                },
+                "rotate" => {
+                    let r: Vec<Scalar> = child.numbers()?;
+                    if r.len() != 4 {
+                        return Err(invalid_data("rotate needs 4 values"));
+                    }
+                    let axis = Vector3f::new(r[0], r[1], r[2]);
+                    match na::Unit::try_new(axis, 1e-12) {
+                        Some(axis) => Matrix4f::from_axis_angle(&axis, r[3].to_radians()),
+                        None => Matrix4f::identity(),
+                    }
+                },
+                "scale" => {
+                    let s: Vec<Scalar> = child.numbers()?;
+                    if s.len() != 3 {
+                        return Err(invalid_data("scale needs 3 values"));
+                    }
+                    Matrix4f::new_nonuniform_scaling(&Vector3f::new(s[0], s[1], s[2]))
+                },
+                _ => continue,
+            };
+            tform = tform * local;
+        }
+        Ok(tform)
+    }
+
+    /// Appends the geometries instantiated by a node and its descendants.
+    fn add_node(&mut self, node: &'a XmlNode, parent: &Matrix4f, depth: usize) -> Result<()> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(invalid_data("node hierarchy too deep or cyclic"));
+        }
+        let tform = parent * Self::node_transform(node)?;
+        for child in &node.children {
+            match child.name.as_str() {
+                "instance_geometry" => {
+                    let geometry = self.lookup(child.attr("url").unwrap_or(""))?;
+                    self.add_geometry(geometry, &tform)?;
+                },
+                "instance_node" => {
+                    let instance = self.lookup(child.attr("url").unwrap_or(""))?;
+                    self.add_node(instance, &tform, depth + 1)?;
+                },
+                "node" => self.add_node(child, &tform, depth + 1)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the transform from the asset coordinates to z-up coordinates in
+/// meters.
+fn asset_transform(root: &XmlNode) -> Matrix4f {
+    let asset = root.child("asset");
+    let meter = asset.and_then(|a| a.child("unit"))
+        .and_then(|u| u.attr("meter"))
+        .and_then(|m| m.parse::<Scalar>().ok())
+        .unwrap_or(1.);
+    let up_axis = asset.and_then(|a| a.child("up_axis"))
+        .map(|u| u.text.trim().to_string())
+        .unwrap_or_default();
+
+    let rotation = match up_axis.as_str() {
+        // (x, y, z) -> (x, -z, y)
+        "Y_UP" => Matrix3f::new(1., 0., 0., 0., 0., -1., 0., 1., 0.),
+        // (x, y, z) -> (-z, y, x)
+        "X_UP" => Matrix3f::new(0., 0., -1., 0., 1., 0., 1., 0., 0.),
+        _ => Matrix3f::identity(),
+    };
+    let mut tform = Matrix4f::identity();
+    tform.fixed_slice_mut::<U3, U3>(0, 0).copy_from(&(rotation * meter));
+    return tform;
+}
+
+/// Reads the geometry of a COLLADA document from std::io::Read, as a single
+/// mesh in meters with the z-axis up.
+///
+/// The nodes of the instantiated visual scene are traversed, or those of the
+/// first visual scene if none is instantiated. Without any visual scene, all
+/// the geometries of the library are read without node transforms, only
+/// converted to meters and to the z-up convention.
+pub fn read_collada<R: Read>(read: &mut R) -> Result<IndexedMesh> {
+    let root = XmlNode::parse(read)?;
+    if root.name != "COLLADA" {
+        return Err(invalid_data("COLLADA document does not start with <COLLADA>"));
+    }
+
+    let mut ids = HashMap::new();
+    root.index(&mut ids);
+    let mut reader = ColladaReader {
+        ids,
+        mesh: IndexedMesh { vertices: Vec::new(), faces: Vec::new() },
+    };
+
+    let tform = asset_transform(&root);
+    let scene = match root.child("scene").and_then(|s| s.child("instance_visual_scene")) {
+        Some(instance) => Some(reader.lookup(instance.attr("url").unwrap_or(""))?),
+        None => root.child("library_visual_scenes").and_then(|l| l.child("visual_scene")),
+    };
+    match scene {
+        Some(scene) => {
+            for node in scene.children("node") {
+                reader.add_node(node, &tform, 0)?;
+            }
+        },
+        None => {
+            for library in root.children("library_geometries") {
+                for geometry in library.children("geometry") {
+                    reader.add_geometry(geometry, &tform)?;
+                }
+            }
+        },
+    }
+
+    Ok(reader.mesh)
+}
@@ -0,0 +1,656 @@
+//! Reader and writer of [IGES](https://en.wikipedia.org/wiki/IGES) files,
+//! limited to the free-form entities: rational B-spline curves (126) and
+//! surfaces (128), and trimmed surfaces (144).
+//!
+//! The reader also resolves the entities these refer to: transformation
+//! matrices (124), composite curves (102) and curves on a parametric surface
+//! (142). Other entities are skipped.
+
+use crate::math::*;
+use crate::geometry::{NurbsCurve, NurbsSurface, TrimLoop, TrimmedNurbsSurface};
+use crate::robotics::{tform2rotm, tform2tvec};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+
+/// Free-form geometry exchanged through IGES files.
+#[derive(Debug, Clone)]
+pub struct IgesModel {
+    pub curves:           Vec<NurbsCurve>,
+    pub surfaces:         Vec<NurbsSurface>,
+    pub trimmed_surfaces: Vec<TrimmedNurbsSurface>,
+}
+
+impl IgesModel {
+
+    pub fn new() -> Self {
+        IgesModel {
+            curves:           Vec::new(),
+            surfaces:         Vec::new(),
+            trimmed_surfaces: Vec::new(),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Directory entry of an entity.
+struct DirectoryEntry {
+    entity_type:   u32,
+    param_pointer: usize,
+    transform:     usize,     // DE of the transformation matrix, 0 if none
+}
+
+/// Parameters of an entity, read field after field.
+struct Params<'a> {
+    de:     usize,
+    fields: &'a Vec<String>,
+    pos:    usize,
+}
+
+impl<'a> Params<'a> {
+
+    fn next(&mut self) -> Result<&'a str> {
+        let field = self.fields.get(self.pos).ok_or_else(|| invalid_data(
+            format!("entity DE {}: missing parameter #{}", self.de, self.pos)))?;
+        self.pos += 1;
+        Ok(field.as_str())
+    }
+
+    fn int(&mut self) -> Result<i64> {
+        let field = self.next()?;
+        if field.is_empty() {
+            return Ok(0);
+        }
+        field.parse().map_err(|_| invalid_data(
+            format!("entity DE {}: invalid integer '{}'", self.de, field)))
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        let value = self.int()?;
+        if value < 0 {
+            return Err(invalid_data(format!("entity DE {}: negative count {}", self.de, value)));
+        }
+        Ok(value as usize)
+    }
+
+    fn real(&mut self) -> Result<Scalar> {
+        let field = self.next()?;
+        if field.is_empty() {
+            return Ok(0.);
+        }
+        field.replace('D', "E").replace('d', "e").parse().map_err(|_| invalid_data(
+            format!("entity DE {}: invalid real '{}'", self.de, field)))
+    }
+
+    fn reals(&mut self, n: usize) -> Result<Vec<Scalar>> {
+        (0..n).map(|_| self.real()).collect()
+    }
+
+    fn points(&mut self, n: usize) -> Result<Vec<Vector3f>> {
+        (0..n).map(|_| Ok(Vector3f::new(self.real()?, self.real()?, self.real()?))).collect()
+    }
+}
+
+/// Splits free-formatted data into fields, up to the record delimiter.
+/// Hollerith strings (`nH...`) are read verbatim.
+fn split_fields(data: &str, param_delim: char, record_delim: char) -> Result<Vec<String>> {
+    let chars: Vec<char> = data.chars().collect();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if field.trim().is_empty() && c.is_ascii_digit() {
+            // Hollerith string
+            let digits: String = chars[i..].iter().take_while(|c| c.is_ascii_digit()).collect();
+            let h = i + digits.len();
+            if h < chars.len() && chars[h] == 'H' {
+                let n: usize = digits.parse()
+                    .map_err(|_| invalid_data(format!("invalid Hollerith length {}", digits)))?;
+                let end = (h + 1 + n).min(chars.len());
+                field = chars[h + 1..end].iter().collect();
+                i = end;
+                continue;
+            }
+        }
+        if c == param_delim || c == record_delim {
+            fields.push(field.trim().to_owned());
+            field.clear();
+            if c == record_delim {
+                return Ok(fields);
+            }
+        } else {
+            field.push(c);
+        }
+        i += 1;
+    }
+    if !field.trim().is_empty() {
+        fields.push(field.trim().to_owned());
+    }
+    return Ok(fields);
+}
+
+/// Entities of an IGES file, indexed by the sequence number of their
+/// directory entry (DE).
+struct IgesReader {
+    entries: BTreeMap<usize, DirectoryEntry>,
+    params:  BTreeMap<usize, Vec<String>>,
+}
+
+impl IgesReader {
+
+    fn parse<R: Read>(read: &mut R) -> Result<Self> {
+        let mut global = String::new();
+        let mut directory = Vec::new();
+        let mut parameters = Vec::new();
+        for line in BufReader::new(read).lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line = format!("{:<80}", line);
+            // the columns are bytes, which may split non-ASCII characters
+            let columns = |start: usize, end: usize| line.get(start..end).ok_or_else(|| invalid_data(
+                format!("non-ASCII character in the fixed columns: {}", line.trim_end())));
+            let section = columns(72, 73)?;
+            match section {
+                "S" | "T" => {},
+                "G" => global.push_str(columns(0, 72)?),
+                "D" => directory.push(columns(0, 72)?.to_owned()),
+                "P" => parameters.push(columns(0, 64)?.to_owned()),
+                "C" => return Err(invalid_data("compressed IGES files are not supported".to_owned())),
+                _ => return Err(invalid_data(format!("invalid section '{}'", section))),
+            }
+        }
+
+        // delimiters, as Hollerith strings at the start of the global section
+        let (mut param_delim, mut record_delim) = (',', ';');
+        let global: Vec<char> = global.chars().collect();
+        let mut pos = 0;
+        if global.len() >= 3 && global[0] == '1' && global[1] == 'H' {
+            param_delim = global[2];
+            pos = 3;
+        }
+        if pos < global.len() && global[pos] == param_delim {
+            pos += 1;
+            if global.len() >= pos + 3 && global[pos] == '1' && global[pos + 1] == 'H' {
+                record_delim = global[pos + 2];
+            }
+        }
+
+        if directory.len() % 2 != 0 {
+            return Err(invalid_data("odd number of directory entry lines".to_owned()));
+        }
+        let mut entries = BTreeMap::new();
+        let mut params = BTreeMap::new();
+        for (k, lines) in directory.chunks(2).enumerate() {
+            let de = 2 * k + 1;
+            let field = |line: &str, i: usize| -> Result<usize> {
+                let field = line.get(8 * i..8 * i + 8).unwrap_or("").trim();
+                if field.is_empty() {
+                    return Ok(0);
+                }
+                field.parse::<i64>().map(|x| x.max(0) as usize).map_err(|_| invalid_data(
+                    format!("entity DE {}: invalid directory field '{}'", de, field)))
+            };
+            let entry = DirectoryEntry {
+                entity_type:   field(&lines[0], 0)? as u32,
+                param_pointer: field(&lines[0], 1)?,
+                transform:     field(&lines[0], 6)?,
+            };
+            let num_lines = field(&lines[1], 3)?;
+            if entry.param_pointer == 0 || entry.param_pointer + num_lines - 1 > parameters.len() {
+                return Err(invalid_data(format!("entity DE {}: invalid parameter pointer", de)));
+            }
+            let data = parameters[entry.param_pointer - 1..entry.param_pointer - 1 + num_lines].concat();
+            params.insert(de, split_fields(&data, param_delim, record_delim)?);
+            entries.insert(de, entry);
+        }
+
+        Ok(IgesReader { entries, params })
+    }
+
+    fn params(&self, de: usize, entity_type: u32) -> Result<Params<'_>> {
+        match (self.entries.get(&de), self.params.get(&de)) {
+            (Some(entry), Some(fields)) if entry.entity_type == entity_type => {
+                let mut params = Params { de, fields, pos: 0 };
+                params.int()?;
+                Ok(params)
+            },
+            (Some(entry), _) => Err(invalid_data(format!(
+                "entity DE {}: expected type {}, found {}", de, entity_type, entry.entity_type))),
+            _ => Err(invalid_data(format!("no entity at DE {}", de))),
+        }
+    }
+
+    /// Returns the transformation of the entity to the model space, through
+    /// the chain of transformation matrices.
+    fn transform(&self, de: usize) -> Result<Matrix4f> {
+        let mut tform = Matrix4f::identity();
+        let mut visited = HashSet::new();
+        visited.insert(de);
+        let mut pointer = self.entries[&de].transform;
+        while pointer != 0 {
+            if !visited.insert(pointer) {
+                return Err(invalid_data(format!("entity DE {}: cyclic transformation", de)));
+            }
+            let mut params = self.params(pointer, 124)?;
+            let r = params.reals(12)?;
+            let matrix = Matrix4f::new(
+                r[0], r[1], r[2], r[3],
+                r[4], r[5], r[6], r[7],
+                r[8], r[9], r[10], r[11],
+                0., 0., 0., 1.,
+            );
+            tform = matrix * tform;
+            pointer = self.entries[&pointer].transform;
+        }
+        return Ok(tform);
+    }
+
+    /// Reads a rational B-spline curve (126), in the model space if
+    /// `model_space`, else as a curve of a parametric domain.
+    fn curve(&self, de: usize, model_space: bool) -> Result<NurbsCurve> {
+        let mut params = self.params(de, 126)?;
+        let k = params.count()?;
+        let degree = params.count()?;
+        params.reals(4)?;
+        let knot_vec = params.reals(k + degree + 2)?;
+        let weights = params.reals(k + 1)?;
+        let mut ctrlpts = params.points(k + 1)?;
+
+        if model_space {
+            let tform = self.transform(de)?;
+            let rotm = tform2rotm(tform);
+            let tvec = tform2tvec(tform);
+            ctrlpts.iter_mut().for_each(|p| *p = rotm * *p + tvec);
+        }
+        if degree == 0 || weights.iter().any(|w| *w <= 0.) {
+            return Err(invalid_data(format!("entity DE {}: unsupported curve", de)));
+        }
+
+        return Ok(NurbsCurve::new(ctrlpts, knot_vec, degree, weights));
+    }
+
+    /// Reads a rational B-spline surface (128), and returns it with its
+    /// parametric domain before normalization.
+    fn surface(&self, de: usize) -> Result<(NurbsSurface, (Scalar, Scalar, Scalar, Scalar))> {
+        let mut params = self.params(de, 128)?;
+        let k1 = params.count()?;
+        let k2 = params.count()?;
+        let degree_u = params.count()?;
+        let degree_v = params.count()?;
+        params.reals(5)?;
+        let knot_vec_u = params.reals(k1 + degree_u + 2)?;
+        let knot_vec_v = params.reals(k2 + degree_v + 2)?;
+        let (size_u, size_v) = (k1 + 1, k2 + 1);
+        let size = size_u.checked_mul(size_v)
+            .ok_or_else(|| invalid_data(format!("entity DE {}: too many control points", de)))?;
+        let weights_iges = params.reals(size)?;
+        let ctrlpts_iges = params.points(size)?;
+        if degree_u == 0 || degree_v == 0 || weights_iges.iter().any(|w| *w <= 0.) {
+            return Err(invalid_data(format!("entity DE {}: unsupported surface", de)));
+        }
+
+        // the u-index varies first in IGES
+        let tform = self.transform(de)?;
+        let rotm = tform2rotm(tform);
+        let tvec = tform2tvec(tform);
+        let mut ctrlpts = Vec::with_capacity(size);
+        let mut weights = Vec::with_capacity(size);
+        for i in 0..size_u {
+            for j in 0..size_v {
+                ctrlpts.push(rotm * ctrlpts_iges[j * size_u + i] + tvec);
+                weights.push(weights_iges[j * size_u + i]);
+            }
+        }
+
+        let domain = (knot_vec_u[0], knot_vec_u[knot_vec_u.len() - 1],
+                      knot_vec_v[0], knot_vec_v[knot_vec_v.len() - 1]);
+        let surf = NurbsSurface::new(ctrlpts, knot_vec_u, knot_vec_v,
+                                     degree_u, degree_v, size_u, size_v, weights);
+        return Ok((surf, domain));
+    }
+
+    /// Reads a curve of a parametric domain, a single curve (126) or a
+    /// composite curve (102) of such curves. Returns `None` if it uses other
+    /// entities.
+    fn parametric_curves(&self, de: usize, used: &mut HashSet<usize>) -> Result<Option<Vec<NurbsCurve>>> {
+        used.insert(de);
+        match self.entries.get(&de).map(|e| e.entity_type) {
+            Some(126) => Ok(Some(vec![self.curve(de, false)?])),
+            Some(102) => {
+                let mut params = self.params(de, 102)?;
+                let n = params.count()?;
+                // all the children are marked as used, even after an unsupported one
+                let mut curves = Vec::new();
+                let mut supported = true;
+                for _ in 0..n {
+                    let child = params.count()?;
+                    match self.parametric_curves(child, used)? {
+                        Some(child_curves) => curves.extend(child_curves),
+                        None => supported = false,
+                    }
+                }
+                Ok(if supported { Some(curves) } else { None })
+            },
+            Some(_) => Ok(None),
+            None => Err(invalid_data(format!("no entity at DE {}", de))),
+        }
+    }
+
+    /// Reads a curve on a parametric surface (142) as a trimming loop of
+    /// the normalized domain of the surface. Returns `None` if the curve of
+    /// the parametric domain is missing or uses unsupported entities.
+    fn trim_loop(&self, de: usize, domain: (Scalar, Scalar, Scalar, Scalar),
+                 used: &mut HashSet<usize>) -> Result<Option<TrimLoop>> {
+        used.insert(de);
+        let mut params = self.params(de, 142)?;
+        params.int()?;
+        params.count()?;            // surface, marked as used with the trimmed surface
+        let pointer = params.count()?;
+        used.insert(params.count()?);
+        if pointer == 0 {
+            return Ok(None);
+        }
+
+        let (u0, u1, v0, v1) = domain;
+        let mut curves = match self.parametric_curves(pointer, used)? {
+            Some(curves) => curves,
+            None => return Ok(None),
+        };
+        for curve in curves.iter_mut() {
+            let mut bspline = curve.bspline.clone();
+            for p in bspline.ctrlpts.iter_mut() {
+                p[0] = (p[0] - u0 * p[3]) / (u1 - u0);
+                p[1] = (p[1] - v0 * p[3]) / (v1 - v0);
+                p[2] = 0.;
+            }
+            *curve = NurbsCurve::from_bspline(bspline);
+        }
+        return Ok(Some(TrimLoop::new(curves)));
+    }
+
+    /// Reads a trimmed surface (144). Returns `None` if it uses unsupported
+    /// entities, in which case its surface is not marked as used and is read
+    /// untrimmed if supported.
+    fn trimmed_surface(&self, de: usize, used: &mut HashSet<usize>) -> Result<Option<TrimmedNurbsSurface>> {
+        let mut params = self.params(de, 144)?;
+        let surface = params.count()?;
+        let has_outer = params.int()? != 0;
+        let num_inner = params.count()?;
+        let outer = params.count()?;
+        let inner: Vec<usize> = (0..num_inner).map(|_| params.count()).collect::<Result<_>>()?;
+
+        let base = match self.entries.get(&surface).map(|e| e.entity_type) {
+            Some(128) => Some(self.surface(surface)?),
+            _ => None,
+        };
+
+        // the trimming curves are used even if the trimmed surface is skipped
+        let domain = base.as_ref().map_or((0., 1., 0., 1.), |(_, domain)| *domain);
+        let outer = if has_outer {
+            Some(self.trim_loop(outer, domain, used)?)
+        } else {
+            None
+        };
+        let inner: Vec<Option<TrimLoop>> = inner.iter()
+            .map(|pointer| self.trim_loop(*pointer, domain, used))
+            .collect::<Result<_>>()?;
+
+        let (surf, outer, inner) = match (base, outer, inner.into_iter().collect::<Option<Vec<_>>>()) {
+            (Some((surf, _)), None, Some(inner)) => (surf, None, inner),
+            (Some((surf, _)), Some(Some(outer)), Some(inner)) => (surf, Some(outer), inner),
+            _ => return Ok(None),
+        };
+        used.insert(surface);
+        return Ok(Some(TrimmedNurbsSurface::new(surf, outer, inner)));
+    }
+}
+
+/// Reads the free-form entities of an IGES file from std::io::Read.
+///
+/// The entities used by a trimmed surface are not listed separately. The
+/// trimming curves are mapped to the normalized domain of their surface.
+/// Trimmed surfaces using unsupported entities are skipped, with their
+/// surface read untrimmed if supported.
+pub fn read_iges<R: Read>(read: &mut R) -> Result<IgesModel> {
+    let reader = IgesReader::parse(read)?;
+
+    let mut used = HashSet::new();
+    let mut trimmed = BTreeMap::new();
+    for (de, entry) in &reader.entries {
+        if entry.entity_type == 144 {
+            if let Some(surf) = reader.trimmed_surface(*de, &mut used)? {
+                trimmed.insert(*de, surf);
+            }
+        }
+    }
+
+    let mut model = IgesModel::new();
+    for (de, entry) in &reader.entries {
+        if used.contains(de) {
+            continue;
+        }
+        match entry.entity_type {
+            126 => model.curves.push(reader.curve(*de, true)?),
+            128 => model.surfaces.push(reader.surface(*de)?.0),
+            144 => model.trimmed_surfaces.extend(trimmed.remove(de)),
+            _ => {},
+        }
+    }
+
+    return Ok(model);
+}
+
+/// Reads the free-form entities of an IGES file.
+pub fn load_iges<P: AsRef<Path>>(path: P) -> Result<IgesModel> {
+    let mut file = File::open(path)?;
+    return read_iges(&mut file);
+}
+
+/// Formats a real with a decimal point, as required by IGES.
+fn format_real(x: Scalar) -> String {
+    let s = format!("{:E}", x);
+    match s.find('E') {
+        Some(e) if !s[..e].contains('.') => format!("{}.{}", &s[..e], &s[e..]),
+        _ => s,
+    }
+}
+
+fn hollerith(s: &str) -> String {
+    format!("{}H{}", s.len(), s)
+}
+
+/// Accumulates the directory entries and parameter data of the entities.
+struct IgesWriter {
+    directory:  Vec<String>,
+    parameters: Vec<String>,
+}
+
+impl IgesWriter {
+
+    /// Appends an entity and returns its DE.
+    ///
+    /// # Arguments
+    ///
+    /// - `entity_type`: entity type number
+    /// - `status`: status number, 8 digits
+    /// - `fields`: parameters, without the entity type
+    fn add(&mut self, entity_type: u32, status: &str, fields: Vec<String>) -> usize {
+        let de = self.directory.len() + 1;
+        let pointer = self.parameters.len() + 1;
+
+        let mut line = format!("{},", entity_type);
+        let num_fields = fields.len();
+        for (k, field) in fields.into_iter().enumerate() {
+            let field = field + if k + 1 == num_fields { ";" } else { "," };
+            if line.len() + field.len() > 64 {
+                self.parameters.push(format!("{:<64}{:>8}", line, de));
+                line.clear();
+            }
+            line.push_str(&field);
+        }
+        self.parameters.push(format!("{:<64}{:>8}", line, de));
+        let num_lines = self.parameters.len() + 1 - pointer;
+
+        self.directory.push(format!("{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+                                    entity_type, pointer, 0, 0, 0, 0, 0, 0, status));
+        self.directory.push(format!("{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+                                    entity_type, 0, 0, num_lines, 0, "", "", "", 0));
+        return de;
+    }
+
+    fn curve(&mut self, curve: &NurbsCurve, status: &str) -> usize {
+        let n = curve.size();
+        let ctrlpts = &curve.bspline.ctrlpts;
+        let rational = ctrlpts.iter().any(|p| (p[3] - ctrlpts[0][3]).abs() > 1e-12);
+        let closed = (curve.curve_point(0.) - curve.curve_point(1.)).norm() < 1e-9;
+
+        let mut fields = vec![(n - 1).to_string(), curve.degree.to_string(),
+                              "0".to_owned(), (closed as i32).to_string(),
+                              (!rational as i32).to_string(), "0".to_owned()];
+        fields.extend(curve.bspline.knotvec.iter().map(|u| format_real(*u)));
+        fields.extend(ctrlpts.iter().map(|p| format_real(p[3])));
+        for p in ctrlpts {
+            fields.extend((0..3).map(|i| format_real(p[i] / p[3])));
+        }
+        let knot_vec = &curve.bspline.knotvec;
+        fields.push(format_real(knot_vec[0]));
+        fields.push(format_real(knot_vec[knot_vec.len() - 1]));
+        return self.add(126, status, fields);
+    }
+
+    fn surface(&mut self, surf: &NurbsSurface, status: &str) -> usize {
+        let (size_u, size_v) = (surf.ctrlpts_size_u, surf.ctrlpts_size_v);
+        let ctrlpts = &surf.bspline.ctrlpts;
+        let rational = ctrlpts.iter().any(|p| (p[3] - ctrlpts[0][3]).abs() > 1e-12);
+        let (knot_vec_u, knot_vec_v) = (&surf.knotvec_u, &surf.knotvec_v);
+
+        let mut fields = vec![(size_u - 1).to_string(), (size_v - 1).to_string(),
+                              surf.degree_u.to_string(), surf.degree_v.to_string(),
+                              (surf.is_closed_u() as i32).to_string(),
+                              (surf.is_closed_v() as i32).to_string(),
+                              (!rational as i32).to_string(), "0".to_owned(), "0".to_owned()];
+        fields.extend(knot_vec_u.iter().map(|u| format_real(*u)));
+        fields.extend(knot_vec_v.iter().map(|v| format_real(*v)));
+        // the u-index varies first in IGES
+        for j in 0..size_v {
+            for i in 0..size_u {
+                fields.push(format_real(ctrlpts[i * size_v + j][3]));
+            }
+        }
+        for j in 0..size_v {
+            for i in 0..size_u {
+                let p = ctrlpts[i * size_v + j];
+                fields.extend((0..3).map(|k| format_real(p[k] / p[3])));
+            }
+        }
+        fields.push(format_real(knot_vec_u[0]));
+        fields.push(format_real(knot_vec_u[knot_vec_u.len() - 1]));
+        fields.push(format_real(knot_vec_v[0]));
+        fields.push(format_real(knot_vec_v[knot_vec_v.len() - 1]));
+        return self.add(128, status, fields);
+    }
+
+    /// Writes a trimming loop as a curve on a parametric surface (142).
+    fn trim_loop(&mut self, trim: &TrimLoop, surface: usize) -> usize {
+        let curves: Vec<usize> = trim.curves.iter()
+            .map(|c| self.curve(c, "00010500"))
+            .collect();
+        let pointer = if curves.len() == 1 {
+            curves[0]
+        } else {
+            let mut fields = vec![curves.len().to_string()];
+            fields.extend(curves.iter().map(|de| de.to_string()));
+            self.add(102, "00010500", fields)
+        };
+        return self.add(142, "00010000", vec!["0".to_owned(), surface.to_string(),
+                                              pointer.to_string(), "0".to_owned(), "1".to_owned()]);
+    }
+
+    fn trimmed_surface(&mut self, trimmed: &TrimmedNurbsSurface) -> usize {
+        let surface = self.surface(&trimmed.surface, "00010000");
+        let outer = match &trimmed.outer {
+            Some(outer) => self.trim_loop(outer, surface),
+            None => 0,
+        };
+        let inner: Vec<usize> = trimmed.inner.iter()
+            .map(|trim| self.trim_loop(trim, surface))
+            .collect();
+
+        let mut fields = vec![surface.to_string(), ((outer != 0) as i32).to_string(),
+                              inner.len().to_string(), outer.to_string()];
+        fields.extend(inner.iter().map(|de| de.to_string()));
+        return self.add(144, "00000000", fields);
+    }
+}
+
+/// Writes the model as an IGES file to std::io::Write.
+///
+/// The trimming curves are written in the normalized domain of their
+/// surface. Lengths are written in millimeters, without scaling.
+pub fn write_iges<W: Write>(writer: &mut W, model: &IgesModel) -> Result<()> {
+    let mut iges = IgesWriter {
+        directory:  Vec::new(),
+        parameters: Vec::new(),
+    };
+    for curve in &model.curves {
+        iges.curve(curve, "00000000");
+    }
+    for surf in &model.surfaces {
+        iges.surface(surf, "00000000");
+    }
+    for trimmed in &model.trimmed_surfaces {
+        iges.trimmed_surface(trimmed);
+    }
+
+    let global_fields = vec![
+        "1H,".to_owned(), "1H;".to_owned(), hollerith("crobot"), hollerith("model.igs"),
+        hollerith("crobot"), hollerith("crobot"), "32".to_owned(), "38".to_owned(),
+        "6".to_owned(), "308".to_owned(), "15".to_owned(), hollerith("crobot"),
+        "1.0".to_owned(), "2".to_owned(), hollerith("MM"), "1".to_owned(), "1.0".to_owned(),
+        hollerith("20000101.000000"), "1.0E-6".to_owned(), "0.0".to_owned(),
+        hollerith(""), hollerith(""), "11".to_owned(), "0".to_owned(),
+        hollerith("20000101.000000"),
+    ];
+    let mut global = Vec::new();
+    let mut line = String::new();
+    let num_fields = global_fields.len();
+    for (k, field) in global_fields.into_iter().enumerate() {
+        let field = field + if k + 1 == num_fields { ";" } else { "," };
+        if line.len() + field.len() > 72 {
+            global.push(line.clone());
+            line.clear();
+        }
+        line.push_str(&field);
+    }
+    global.push(line);
+
+    let mut w = BufWriter::new(writer);
+    writeln!(w, "{:<72}S{:>7}", "crobot IGES export", 1)?;
+    for (k, line) in global.iter().enumerate() {
+        writeln!(w, "{:<72}G{:>7}", line, k + 1)?;
+    }
+    for (k, line) in iges.directory.iter().enumerate() {
+        writeln!(w, "{:<72}D{:>7}", line, k + 1)?;
+    }
+    for (k, line) in iges.parameters.iter().enumerate() {
+        writeln!(w, "{:<72}P{:>7}", line, k + 1)?;
+    }
+    let terminate = format!("S{:>7}G{:>7}D{:>7}P{:>7}",
+                            1, global.len(), iges.directory.len(), iges.parameters.len());
+    writeln!(w, "{:<72}T{:>7}", terminate, 1)?;
+    w.flush()
+}
+
+/// Writes the model as an IGES file.
+pub fn save_iges<P: AsRef<Path>>(path: P, model: &IgesModel) -> Result<()> {
+    let mut file = File::create(path)?;
+    return write_iges(&mut file, model);
+}
@@ -0,0 +1,106 @@
+use crate::utils::{IndexedMesh, PlyFormat, read_collada, read_obj, read_ply, read_stl, write_obj, write_ply, write_stl};
+use kiss3d::resource::Mesh;
+use na::{Point3, Vector3};
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Error, ErrorKind, Result};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Mesh file formats, as given by the file extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshFormat {
+    Stl,        // .stl
+    Obj,        // .obj
+    Ply,        // .ply
+    Collada,    // .dae, read only
+}
+
+impl MeshFormat {
+
+    /// Returns the format of the file from its extension, ignoring case.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        match extension.as_ref().map(|ext| ext.as_str()) {
+            Some("stl") => Ok(MeshFormat::Stl),
+            Some("obj") => Ok(MeshFormat::Obj),
+            Some("ply") => Ok(MeshFormat::Ply),
+            Some("dae") => Ok(MeshFormat::Collada),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported mesh format: {}", path.display()),
+            )),
+        }
+    }
+}
+
+/// Reads a mesh file, choosing the reader from the file extension.
+pub fn load_indexed_mesh<P: AsRef<Path>>(path: P) -> Result<IndexedMesh> {
+    let format = MeshFormat::from_path(&path)?;
+    let mut file = OpenOptions::new().read(true).open(path)?;
+    match format {
+        MeshFormat::Stl => read_stl(&mut file),
+        MeshFormat::Obj => read_obj(&mut BufReader::new(file)),
+        MeshFormat::Ply => read_ply(&mut file),
+        MeshFormat::Collada => read_collada(&mut BufReader::new(file)),
+    }
+}
+
+/// Writes a mesh file, choosing the writer from the file extension. STL
+/// and PLY files are written in binary (little-endian) form, and COLLADA
+/// files are not supported.
+pub fn save_indexed_mesh<P: AsRef<Path>>(path: P, mesh: &IndexedMesh) -> Result<()> {
+    let format = MeshFormat::from_path(&path)?;
+    if format == MeshFormat::Collada {
+        return Err(Error::new(ErrorKind::InvalidInput, "writing COLLADA meshes is not supported"));
+    }
+    let mut file = File::create(path)?;
+    match format {
+        MeshFormat::Stl => write_stl(&mut file, mesh),
+        MeshFormat::Obj => write_obj(&mut file, mesh, &[]),
+        MeshFormat::Ply => write_ply(&mut file, mesh, PlyFormat::BinaryLittleEndian),
+        MeshFormat::Collada => unreachable!(),
+    }
+}
+
+/// Converts the mesh to a kiss3d mesh with per-face normals.
+///
+/// kiss3d indexes vertices with `u16`: returns `None` if the mesh has more
+/// than 65536 vertices.
+pub fn to_kiss3d_mesh(mesh: &IndexedMesh) -> Option<Rc<RefCell<Mesh>>> {
+    if mesh.vertices.len() > u16::max_value() as usize + 1 {
+        return None;
+    }
+
+    let coords = mesh.vertices.iter()
+        .map(|vertex| Point3::from_slice(vertex))
+        .collect();
+    let normals = mesh.faces.iter()
+        .map(|face| Vector3::new(face.normal[0], face.normal[1], face.normal[2]))
+        .collect();
+    let indices = mesh.faces.iter()
+        .map(|face| Point3::new(
+            face.vertices[0] as u16, face.vertices[1] as u16, face.vertices[2] as u16))
+        .collect();
+
+    let mesh = Rc::new(RefCell::new(Mesh::new(
+        coords, indices, Some(normals), None, false,
+    )));
+
+    return Some(mesh);
+}
+
+/// Loads a mesh file for rendering, choosing the reader from the file
+/// extension. Meshes with more vertices than kiss3d can index (65536) are
+/// rejected with `InvalidData`.
+pub fn load_mesh<P>(path: P) -> Result<Rc<RefCell<Mesh>>>
+where P: AsRef<Path> {
+    let mesh = load_indexed_mesh(path)?;
+    return to_kiss3d_mesh(&mesh).ok_or_else(|| Error::new(
+        ErrorKind::InvalidData,
+        format!("{} vertices exceed the 16-bit indices of kiss3d meshes", mesh.vertices.len()),
+    ));
+}
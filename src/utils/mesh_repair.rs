@@ -0,0 +1,374 @@
+//! Repair and validation of indexed triangle meshes.
+//!
+//! Meshes from CAD exports often come as triangle soups, with duplicated
+//! vertices, slivers, faces repeated or flipped, and small holes. The
+//! repair pipeline welds the vertices, removes the degenerate and duplicate
+//! faces, orients the faces consistently (outwards for closed parts), and
+//! optionally fills the small holes.
+
+use crate::utils::{IndexedMesh, IndexedTriangle, Vertex, triangle_normal};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Criteria of the mesh repair pipeline.
+#[derive(Debug, Clone, Copy)]
+pub struct RepairCriteria {
+    pub weld_tolerance: f32,    // maximum distance between welded vertices
+    pub fill_holes:     bool,   // whether to fill the small holes
+    pub max_hole_edges: usize,  // maximum number of edges of a filled hole
+}
+
+impl RepairCriteria {
+    pub fn default() -> Self {
+        RepairCriteria {
+            weld_tolerance: 1e-6,
+            fill_holes:     false,
+            max_hole_edges: 16,
+        }
+    }
+}
+
+/// Summary of the changes made by the repair pipeline, and of the defects
+/// left in the mesh.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    pub welded_vertices:    usize,              // number of vertices merged into others
+    pub degenerate_faces:   usize,              // number of degenerate faces removed
+    pub duplicate_faces:    usize,              // number of duplicate faces removed
+    pub flipped_faces:      usize,              // number of faces reoriented
+    pub filled_holes:       usize,              // number of holes filled
+    pub non_manifold_edges: Vec<[usize; 2]>,    // edges shared by more than two faces
+    pub holes:              Vec<Vec<usize>>,    // boundary loops left open
+    pub watertight:         bool,               // closed two-manifold mesh
+}
+
+fn sub(a: &Vertex, b: &Vertex) -> [f64; 3] {
+    [(a[0] - b[0]) as f64, (a[1] - b[1]) as f64, (a[2] - b[2]) as f64]
+}
+
+fn cross(u: &[f64; 3], v: &[f64; 3]) -> [f64; 3] {
+    [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]
+}
+
+fn dot(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+/// Undirected edge key.
+fn edge_key(a: usize, b: usize) -> [usize; 2] {
+    if a < b { [a, b] } else { [b, a] }
+}
+
+impl IndexedMesh {
+
+    /// Maps each undirected edge to the faces using it.
+    fn edge_faces(&self) -> HashMap<[usize; 2], Vec<usize>> {
+        let mut edges: HashMap<[usize; 2], Vec<usize>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for k in 0..3 {
+                let (a, b) = (face.vertices[k], face.vertices[(k + 1) % 3]);
+                edges.entry(edge_key(a, b)).or_insert_with(Vec::new).push(f);
+            }
+        }
+        return edges;
+    }
+
+    /// Recomputes the normal of a face from its vertices.
+    fn update_normal(&mut self, f: usize) {
+        let v = self.faces[f].vertices;
+        self.faces[f].normal = triangle_normal(
+            &self.vertices[v[0]], &self.vertices[v[1]], &self.vertices[v[2]]);
+    }
+
+    /// Removes the vertices not used by any face, returning their number.
+    pub fn remove_unused_vertices(&mut self) -> usize {
+        let mut map = vec![usize::max_value(); self.vertices.len()];
+        let mut vertices = Vec::new();
+        for face in self.faces.iter_mut() {
+            for i in face.vertices.iter_mut() {
+                if map[*i] == usize::max_value() {
+                    map[*i] = vertices.len();
+                    vertices.push(self.vertices[*i]);
+                }
+                *i = map[*i];
+            }
+        }
+        let removed = self.vertices.len() - vertices.len();
+        self.vertices = vertices;
+        return removed;
+    }
+
+    /// Merges the vertices closer than the tolerance, and removes the unused
+    /// vertices. Returns the number of vertices removed.
+    ///
+    /// Each vertex is merged into the first vertex found within the
+    /// tolerance, so chains of close vertices may be merged only partially.
+    pub fn weld_vertices(&mut self, tolerance: f32) -> usize {
+        let num_vertices = self.vertices.len();
+        let cell = tolerance.max(1e-6);
+        let tol2 = (tolerance as f64) * (tolerance as f64);
+        let key = |v: &Vertex| [
+            (v[0] / cell).floor() as i64, (v[1] / cell).floor() as i64, (v[2] / cell).floor() as i64,
+        ];
+
+        // spatial hash of the representative vertices
+        let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        let mut map = Vec::with_capacity(num_vertices);
+        for (i, v) in self.vertices.iter().enumerate() {
+            let k = key(v);
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        if let Some(cands) = grid.get(&[k[0].wrapping_add(dx), k[1].wrapping_add(dy), k[2].wrapping_add(dz)]) {
+                            for &j in cands {
+                                let d = sub(v, &self.vertices[j]);
+                                if dot(&d, &d) <= tol2 {
+                                    found = Some(j);
+                                    break 'search;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            match found {
+                Some(j) => map.push(j),
+                None => {
+                    grid.entry(k).or_insert_with(Vec::new).push(i);
+                    map.push(i);
+                },
+            }
+        }
+
+        for face in self.faces.iter_mut() {
+            for i in face.vertices.iter_mut() {
+                *i = map[*i];
+            }
+        }
+        self.remove_unused_vertices();
+        return num_vertices - self.vertices.len();
+    }
+
+    /// Removes the faces with repeated vertices or a zero area, relative to
+    /// their longest edge. Returns the number of faces removed.
+    pub fn remove_degenerate_faces(&mut self) -> usize {
+        let num_faces = self.faces.len();
+        let vertices = &self.vertices;
+        self.faces.retain(|face| {
+            let [a, b, c] = face.vertices;
+            if a == b || b == c || c == a {
+                return false;
+            }
+            let (u, v, w) = (sub(&vertices[b], &vertices[a]),
+                             sub(&vertices[c], &vertices[a]),
+                             sub(&vertices[c], &vertices[b]));
+            let longest = dot(&u, &u).max(dot(&v, &v)).max(dot(&w, &w));
+            let n = cross(&u, &v);
+            dot(&n, &n).sqrt() > 1e-12 * longest
+        });
+        return num_faces - self.faces.len();
+    }
+
+    /// Removes the faces using the same vertices as a previous face,
+    /// whatever their orientation. Returns the number of faces removed.
+    pub fn remove_duplicate_faces(&mut self) -> usize {
+        let num_faces = self.faces.len();
+        let mut seen = HashSet::new();
+        self.faces.retain(|face| {
+            let mut key = face.vertices;
+            key.sort();
+            seen.insert(key)
+        });
+        return num_faces - self.faces.len();
+    }
+
+    /// Orients the faces of each connected part consistently with its first
+    /// face, through the edges shared by two faces. Closed parts are then
+    /// oriented outwards. Returns the number of faces flipped.
+    pub fn orient_faces(&mut self) -> usize {
+        let edges = self.edge_faces();
+        let mut flipped = vec![false; self.faces.len()];
+        let mut visited = vec![false; self.faces.len()];
+
+        for seed in 0..self.faces.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut component = vec![seed];
+            let mut closed = true;
+            let mut queue = VecDeque::new();
+            queue.push_back(seed);
+
+            while let Some(f) = queue.pop_front() {
+                let mut v = self.faces[f].vertices;
+                if flipped[f] {
+                    v.swap(1, 2);
+                }
+                for k in 0..3 {
+                    let (a, b) = (v[k], v[(k + 1) % 3]);
+                    let faces = &edges[&edge_key(a, b)];
+                    if faces.len() != 2 {
+                        closed = false;
+                        continue;
+                    }
+                    let g = if faces[0] == f { faces[1] } else { faces[0] };
+                    if visited[g] {
+                        continue;
+                    }
+                    // a consistent neighbor uses the edge from b to a
+                    let w = self.faces[g].vertices;
+                    let same_direction = (0..3).any(|i| w[i] == a && w[(i + 1) % 3] == b);
+                    flipped[g] = same_direction;
+                    visited[g] = true;
+                    component.push(g);
+                    queue.push_back(g);
+                }
+            }
+
+            if closed {
+                // signed volume of the closed part
+                let mut volume = 0.;
+                for &f in &component {
+                    let mut v = self.faces[f].vertices;
+                    if flipped[f] {
+                        v.swap(1, 2);
+                    }
+                    let p: Vec<[f64; 3]> = v.iter()
+                        .map(|&i| sub(&self.vertices[i], &[0.; 3]))
+                        .collect();
+                    volume += dot(&p[0], &cross(&p[1], &p[2]));
+                }
+                if volume < 0. {
+                    for &f in &component {
+                        flipped[f] = !flipped[f];
+                    }
+                }
+            }
+        }
+
+        let mut count = 0;
+        for f in 0..self.faces.len() {
+            if flipped[f] {
+                self.faces[f].vertices.swap(1, 2);
+                self.update_normal(f);
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Returns the edges shared by more than two faces.
+    pub fn non_manifold_edges(&self) -> Vec<[usize; 2]> {
+        let mut edges: Vec<[usize; 2]> = self.edge_faces().into_iter()
+            .filter(|(_, faces)| faces.len() > 2)
+            .map(|(edge, _)| edge)
+            .collect();
+        edges.sort();
+        return edges;
+    }
+
+    /// Returns the closed loops of boundary edges (edges used by a single
+    /// face), each oriented as the boundary of the missing surface. The faces
+    /// around the holes should be consistently oriented.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let edges = self.edge_faces();
+
+        // boundary half-edges, reversed to run along the holes
+        let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut starts = Vec::new();
+        for face in &self.faces {
+            for k in 0..3 {
+                let (a, b) = (face.vertices[k], face.vertices[(k + 1) % 3]);
+                if edges[&edge_key(a, b)].len() == 1 {
+                    next.entry(b).or_insert_with(Vec::new).push(a);
+                    starts.push(b);
+                }
+            }
+        }
+
+        let mut loops = Vec::new();
+        for start in starts {
+            let mut polygon = Vec::new();
+            let mut curr = start;
+            while let Some(v) = next.get_mut(&curr).and_then(|v| v.pop()) {
+                polygon.push(curr);
+                curr = v;
+                if curr == start {
+                    break;
+                }
+            }
+            // open walks come from inconsistently oriented faces
+            if !polygon.is_empty() && curr == start {
+                loops.push(polygon);
+            }
+        }
+        return loops;
+    }
+
+    /// Fills the holes with at most `max_edges` edges by triangle fans about
+    /// their centroid. Returns the number of holes filled.
+    pub fn fill_holes(&mut self, max_edges: usize) -> usize {
+        let mut count = 0;
+        for polygon in self.boundary_loops() {
+            if polygon.len() < 3 || polygon.len() > max_edges {
+                continue;
+            }
+            let face_start = self.faces.len();
+            if polygon.len() == 3 {
+                self.faces.push(IndexedTriangle { normal: [0.; 3], vertices: [polygon[0], polygon[1], polygon[2]] });
+            } else {
+                let mut center = [0.; 3];
+                for &i in &polygon {
+                    for k in 0..3 {
+                        center[k] += self.vertices[i][k] / polygon.len() as f32;
+                    }
+                }
+                let c = self.vertices.len();
+                self.vertices.push(center);
+                for k in 0..polygon.len() {
+                    let (a, b) = (polygon[k], polygon[(k + 1) % polygon.len()]);
+                    self.faces.push(IndexedTriangle { normal: [0.; 3], vertices: [a, b, c] });
+                }
+            }
+            for f in face_start..self.faces.len() {
+                self.update_normal(f);
+            }
+            count += 1;
+        }
+        return count;
+    }
+
+    /// Whether the mesh is a closed two-manifold: every edge is shared by
+    /// exactly two faces.
+    pub fn is_watertight(&self) -> bool {
+        !self.faces.is_empty() && self.edge_faces().values().all(|faces| faces.len() == 2)
+    }
+
+    /// Runs the repair pipeline: welds the vertices, removes the degenerate
+    /// and duplicate faces, orients the faces, and optionally fills the
+    /// small holes, orienting the faces again once the holes are closed.
+    ///
+    /// # Arguments
+    ///
+    /// - `criteria`: repair criteria
+    pub fn repair(&mut self, criteria: &RepairCriteria) -> RepairReport {
+        let mut report = RepairReport::default();
+        report.welded_vertices = self.weld_vertices(criteria.weld_tolerance);
+        report.degenerate_faces = self.remove_degenerate_faces();
+        report.duplicate_faces = self.remove_duplicate_faces();
+        report.flipped_faces = self.orient_faces();
+        if criteria.fill_holes {
+            report.filled_holes = self.fill_holes(criteria.max_hole_edges);
+            if report.filled_holes > 0 {
+                report.flipped_faces += self.orient_faces();
+            }
+        }
+        self.remove_unused_vertices();
+
+        report.non_manifold_edges = self.non_manifold_edges();
+        report.holes = self.boundary_loops();
+        report.watertight = self.is_watertight();
+        return report;
+    }
+}
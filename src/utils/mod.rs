@@ -6,12 +6,28 @@ mod urdf;
 mod consts;
 mod rotation;
 mod stl;
+mod obj;
+mod ply;
+mod collada;
+mod mesh_io;
+mod mesh_repair;
+mod iges;
 mod color;
+#[cfg(feature = "serde")]
+mod serialization;
 
 pub use self::common::*;
 pub use self::urdf::*;
 pub use self::consts::*;
 pub use self::rotation::*;
 pub use self::stl::*;
+pub use self::obj::*;
+pub use self::ply::*;
+pub use self::collada::*;
+pub use self::mesh_io::*;
+pub use self::mesh_repair::*;
+pub use self::iges::*;
 pub use self::trajectory::*;
-pub use self::color::*;
\ No newline at end of file
+pub use self::color::*;
+#[cfg(feature = "serde")]
+pub use self::serialization::*;
\ No newline at end of file
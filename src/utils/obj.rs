@@ -0,0 +1,175 @@
+//! Reading and writing of [Wavefront OBJ](https://en.wikipedia.org/wiki/Wavefront_.obj_file)
+//! meshes.
+//!
+//! Only the geometry is kept: vertices, vertex normals, faces and groups.
+//! Polygonal faces are split into triangle fans, and texture coordinates,
+//! materials and smoothing groups are ignored.
+
+use crate::utils::{IndexedMesh, IndexedTriangle, Normal, Vertex, triangle_normal};
+use std::io::{BufRead, BufReader, BufWriter};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::ops::Range;
+
+/// Named group of consecutive faces of an OBJ mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjGroup {
+    /// Name of the group.
+    pub name: String,
+    /// Indices of the faces of the group in the mesh.
+    pub faces: Range<usize>,
+}
+
+fn invalid_data<S: Into<String>>(line: usize, msg: S) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg.into()))
+}
+
+/// Parses the three first coordinates of a `v` or `vn` statement.
+fn parse_coords(tokens: &[&str], line: usize) -> Result<[f32; 3]> {
+    if tokens.len() < 3 {
+        return Err(invalid_data(line, "expected three coordinates"));
+    }
+    let mut coords = [0.; 3];
+    for i in 0..3 {
+        coords[i] = tokens[i].parse::<f32>()
+            .map_err(|e| invalid_data(line, e.to_string()))?;
+    }
+    Ok(coords)
+}
+
+/// Resolves a 1-based, possibly negative (relative) OBJ index.
+fn parse_index(token: &str, len: usize, line: usize) -> Result<usize> {
+    let index = token.parse::<i64>().map_err(|e| invalid_data(line, e.to_string()))?;
+    let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(invalid_data(line, format!("index {} out of range", index)));
+    }
+    Ok(resolved as usize)
+}
+
+/// Reads an OBJ mesh from std::io::Read, discarding its groups.
+pub fn read_obj<R: Read>(read: &mut R) -> Result<IndexedMesh> {
+    let (mesh, _) = read_obj_groups(read)?;
+    Ok(mesh)
+}
+
+/// Reads an OBJ mesh and its named face groups (`g` and `o` statements).
+///
+/// The normal of a face is the average of the normals of its corners if
+/// they are all given, or else the normal of its first triangle.
+pub fn read_obj_groups<R: Read>(read: &mut R) -> Result<(IndexedMesh, Vec<ObjGroup>)> {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut normals: Vec<Normal> = Vec::new();
+    let mut faces: Vec<IndexedTriangle> = Vec::new();
+    let mut groups: Vec<ObjGroup> = Vec::new();
+    let mut group: Option<(String, usize)> = None;
+
+    for (i, line) in BufReader::new(read).lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() {
+            continue;
+        }
+        match tokens[0] {
+            "v" => vertices.push(parse_coords(&tokens[1..], i + 1)?),
+            "vn" => normals.push(parse_coords(&tokens[1..], i + 1)?),
+            "g" | "o" => {
+                if let Some((name, start)) = group.take() {
+                    if start < faces.len() {
+                        groups.push(ObjGroup { name, faces: start..faces.len() });
+                    }
+                }
+                group = Some((tokens[1..].join(" "), faces.len()));
+            },
+            "f" => {
+                if tokens.len() < 4 {
+                    return Err(invalid_data(i + 1, "a face needs at least three vertices"));
+                }
+                let mut corners = Vec::new();
+                let mut corner_normals = Vec::new();
+                for token in &tokens[1..] {
+                    let mut refs = token.split('/');
+                    corners.push(parse_index(refs.next().unwrap(), vertices.len(), i + 1)?);
+                    if let Some(normal) = refs.nth(1) {
+                        if !normal.is_empty() {
+                            corner_normals.push(normals[parse_index(normal, normals.len(), i + 1)?]);
+                        }
+                    }
+                }
+
+                let normal = if corner_normals.len() == corners.len() {
+                    let mut sum = [0.; 3];
+                    for n in &corner_normals {
+                        for k in 0..3 {
+                            sum[k] += n[k];
+                        }
+                    }
+                    let norm = (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]).sqrt();
+                    if norm > 0. { [sum[0] / norm, sum[1] / norm, sum[2] / norm] } else { sum }
+                } else {
+                    triangle_normal(&vertices[corners[0]], &vertices[corners[1]], &vertices[corners[2]])
+                };
+                for k in 1..corners.len() - 1 {
+                    faces.push(IndexedTriangle {
+                        normal,
+                        vertices: [corners[0], corners[k], corners[k + 1]],
+                    });
+                }
+            },
+            _ => {},    // vt, s, usemtl, mtllib, l, p, ...
+        }
+    }
+    if let Some((name, start)) = group {
+        if start < faces.len() {
+            groups.push(ObjGroup { name, faces: start..faces.len() });
+        }
+    }
+
+    Ok((IndexedMesh { vertices, faces }, groups))
+}
+
+/// Writes an OBJ mesh to std::io::Write, with one normal per face.
+///
+/// # Arguments
+///
+/// - `writer`: output
+/// - `mesh`: mesh to write
+/// - `groups`: face groups, sorted and disjoint, possibly empty
+pub fn write_obj<W: Write>(writer: &mut W, mesh: &IndexedMesh, groups: &[ObjGroup]) -> Result<()> {
+    for pair in groups.windows(2) {
+        if pair[0].faces.end > pair[1].faces.start {
+            return Err(Error::new(ErrorKind::InvalidInput, "groups are not sorted and disjoint"));
+        }
+    }
+
+    let mut writer = BufWriter::new(writer);
+    for v in &mesh.vertices {
+        writeln!(writer, "v {} {} {}", v[0], v[1], v[2])?;
+    }
+    for face in &mesh.faces {
+        let n = &face.normal;
+        writeln!(writer, "vn {} {} {}", n[0], n[1], n[2])?;
+    }
+
+    // faces following a group without starting another one are put in the
+    // default group, so that they are not read back in the previous one;
+    // empty groups are skipped
+    let mut groups = groups.iter().filter(|group| !group.faces.is_empty()).peekable();
+    let mut group_end = None;
+    for (i, face) in mesh.faces.iter().enumerate() {
+        match groups.peek() {
+            Some(group) if group.faces.start == i => {
+                writeln!(writer, "g {}", group.name)?;
+                group_end = Some(group.faces.end);
+                groups.next();
+            },
+            _ => if group_end == Some(i) {
+                writeln!(writer, "g default")?;
+                group_end = None;
+            },
+        }
+        let v = &face.vertices;
+        writeln!(writer, "f {}//{} {}//{} {}//{}", v[0] + 1, i + 1, v[1] + 1, i + 1, v[2] + 1, i + 1)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,306 @@
+//! Reading and writing of [PLY (Polygon File Format)](https://en.wikipedia.org/wiki/PLY_(file_format))
+//! meshes, in ascii and binary form.
+//!
+//! Only the `x`, `y`, `z` properties of the `vertex` element and the
+//! `vertex_indices` list of the `face` element are kept. Other properties
+//! and elements are skipped, and polygonal faces are split into triangle
+//! fans.
+
+use crate::utils::{IndexedMesh, IndexedTriangle, Vertex, triangle_normal};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{BufRead, BufReader, BufWriter};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+/// Encoding of the body of a PLY file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlyType {
+    Int8, UInt8, Int16, UInt16, Int32, UInt32, Float32, Float64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => PlyType::Int8,
+            "uchar" | "uint8" => PlyType::UInt8,
+            "short" | "int16" => PlyType::Int16,
+            "ushort" | "uint16" => PlyType::UInt16,
+            "int" | "int32" => PlyType::Int32,
+            "uint" | "uint32" => PlyType::UInt32,
+            "float" | "float32" => PlyType::Float32,
+            "double" | "float64" => PlyType::Float64,
+            _ => return Err(invalid_data(format!("unknown property type '{}'", name))),
+        })
+    }
+}
+
+#[derive(Debug)]
+enum PlyProperty {
+    Scalar { name: String, ty: PlyType },
+    List   { name: String, count: PlyType, item: PlyType },
+}
+
+#[derive(Debug)]
+struct PlyElement {
+    name:       String,
+    count:      usize,
+    properties: Vec<PlyProperty>,
+}
+
+fn invalid_data<S: Into<String>>(msg: S) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads the header, up to and including the `end_header` line.
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(PlyFormat, Vec<PlyElement>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err(invalid_data("PLY does not start with \"ply\""));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "EOF while expecting end_header"));
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(PlyFormat::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(PlyFormat::BinaryLittleEndian),
+            ["format", "binary_big_endian", _] => format = Some(PlyFormat::BinaryBigEndian),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid_data(format!("invalid count '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid_data("property before element"))?;
+                element.properties.push(PlyProperty::List {
+                    name: name.to_string(),
+                    count: PlyType::parse(count)?,
+                    item: PlyType::parse(item)?,
+                });
+            },
+            ["property", ty, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid_data("property before element"))?;
+                element.properties.push(PlyProperty::Scalar {
+                    name: name.to_string(),
+                    ty: PlyType::parse(ty)?,
+                });
+            },
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {},
+            _ => return Err(invalid_data(format!("invalid header line: {:?}", line.trim_end()))),
+        }
+    }
+
+    match format {
+        Some(format) => Ok((format, elements)),
+        None => Err(invalid_data("missing format line")),
+    }
+}
+
+/// Source of the property values of the body of a PLY file.
+trait ValueReader {
+    fn read_value(&mut self, ty: PlyType) -> Result<f64>;
+
+    /// Called at the end of each element instance.
+    fn end_instance(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct AsciiValueReader<R: BufRead> {
+    reader: R,
+    tokens: Vec<String>,
+    index:  usize,
+}
+
+impl<R: BufRead> ValueReader for AsciiValueReader<R> {
+    fn read_value(&mut self, _ty: PlyType) -> Result<f64> {
+        while self.index == self.tokens.len() {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "EOF while expecting a value"));
+            }
+            self.tokens = line.split_whitespace().map(|t| t.to_string()).collect();
+            self.index = 0;
+        }
+        self.index += 1;
+        self.tokens[self.index - 1].parse::<f64>()
+            .map_err(|e| invalid_data(e.to_string()))
+    }
+
+    fn end_instance(&mut self) -> Result<()> {
+        if self.index != self.tokens.len() {
+            return Err(invalid_data("unexpected values at the end of an element"));
+        }
+        Ok(())
+    }
+}
+
+struct BinaryValueReader<R: Read, B: ByteOrder> {
+    reader: R,
+    order:  std::marker::PhantomData<B>,
+}
+
+impl<R: Read, B: ByteOrder> ValueReader for BinaryValueReader<R, B> {
+    fn read_value(&mut self, ty: PlyType) -> Result<f64> {
+        let r = &mut self.reader;
+        Ok(match ty {
+            PlyType::Int8 => r.read_i8()? as f64,
+            PlyType::UInt8 => r.read_u8()? as f64,
+            PlyType::Int16 => r.read_i16::<B>()? as f64,
+            PlyType::UInt16 => r.read_u16::<B>()? as f64,
+            PlyType::Int32 => r.read_i32::<B>()? as f64,
+            PlyType::UInt32 => r.read_u32::<B>()? as f64,
+            PlyType::Float32 => r.read_f32::<B>()? as f64,
+            PlyType::Float64 => r.read_f64::<B>()?,
+        })
+    }
+}
+
+/// Converts a list count or a vertex index, rejecting negative and
+/// fractional values.
+fn to_index(value: f64) -> Result<usize> {
+    if !(value >= 0.) || value.fract() != 0. {
+        return Err(invalid_data(format!("invalid count or index {}", value)));
+    }
+    Ok(value as usize)
+}
+
+/// Reads the body of a PLY file described by its elements.
+fn read_body<V: ValueReader>(values: &mut V, elements: &Vec<PlyElement>) -> Result<IndexedMesh> {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut polygons: Vec<Vec<usize>> = Vec::new();
+
+    for element in elements {
+        for _ in 0..element.count {
+            let mut vertex = [0.; 3];
+            for property in &element.properties {
+                match property {
+                    PlyProperty::Scalar { name, ty } => {
+                        let value = values.read_value(*ty)?;
+                        if element.name == "vertex" {
+                            match name.as_str() {
+                                "x" => vertex[0] = value as f32,
+                                "y" => vertex[1] = value as f32,
+                                "z" => vertex[2] = value as f32,
+                                _ => {},
+                            }
+                        }
+                    },
+                    PlyProperty::List { name, count, item } => {
+                        // the count is not trusted for preallocation
+                        let count = to_index(values.read_value(*count)?)?;
+                        let mut list = Vec::new();
+                        for _ in 0..count {
+                            list.push(to_index(values.read_value(*item)?)?);
+                        }
+                        if element.name == "face" && (name == "vertex_indices" || name == "vertex_index") {
+                            polygons.push(list);
+                        }
+                    },
+                }
+            }
+            values.end_instance()?;
+            if element.name == "vertex" {
+                vertices.push(vertex);
+            }
+        }
+    }
+
+    let mut faces = Vec::new();
+    for polygon in polygons {
+        if polygon.len() < 3 {
+            return Err(invalid_data("a face needs at least three vertices"));
+        }
+        if let Some(i) = polygon.iter().find(|i| **i >= vertices.len()) {
+            return Err(invalid_data(format!("vertex index {} out of range", i)));
+        }
+        let normal = triangle_normal(
+            &vertices[polygon[0]], &vertices[polygon[1]], &vertices[polygon[2]]);
+        for k in 1..polygon.len() - 1 {
+            faces.push(IndexedTriangle {
+                normal,
+                vertices: [polygon[0], polygon[k], polygon[k + 1]],
+            });
+        }
+    }
+
+    Ok(IndexedMesh { vertices, faces })
+}
+
+/// Reads an ascii or binary PLY mesh from std::io::Read. Face normals are
+/// computed from the vertices.
+pub fn read_ply<R: Read>(read: &mut R) -> Result<IndexedMesh> {
+    let mut reader = BufReader::new(read);
+    let (format, elements) = read_header(&mut reader)?;
+    match format {
+        PlyFormat::Ascii => read_body(
+            &mut AsciiValueReader { reader, tokens: Vec::new(), index: 0 }, &elements),
+        PlyFormat::BinaryLittleEndian => read_body(
+            &mut BinaryValueReader::<_, LittleEndian> { reader, order: std::marker::PhantomData }, &elements),
+        PlyFormat::BinaryBigEndian => read_body(
+            &mut BinaryValueReader::<_, BigEndian> { reader, order: std::marker::PhantomData }, &elements),
+    }
+}
+
+/// Writes a PLY mesh to std::io::Write, with `float` vertex coordinates and
+/// `int` vertex indices.
+pub fn write_ply<W: Write>(writer: &mut W, mesh: &IndexedMesh, format: PlyFormat) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {} 1.0", match format {
+        PlyFormat::Ascii => "ascii",
+        PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        PlyFormat::BinaryBigEndian => "binary_big_endian",
+    })?;
+    writeln!(writer, "element vertex {}", mesh.vertices.len())?;
+    writeln!(writer, "property float x")?;
+    writeln!(writer, "property float y")?;
+    writeln!(writer, "property float z")?;
+    writeln!(writer, "element face {}", mesh.faces.len())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    match format {
+        PlyFormat::Ascii => {
+            for v in &mesh.vertices {
+                writeln!(writer, "{} {} {}", v[0], v[1], v[2])?;
+            }
+            for face in &mesh.faces {
+                let v = &face.vertices;
+                writeln!(writer, "3 {} {} {}", v[0], v[1], v[2])?;
+            }
+        },
+        PlyFormat::BinaryLittleEndian => write_binary::<_, LittleEndian>(&mut writer, mesh)?,
+        PlyFormat::BinaryBigEndian => write_binary::<_, BigEndian>(&mut writer, mesh)?,
+    }
+    writer.flush()
+}
+
+fn write_binary<W: Write, B: ByteOrder>(writer: &mut W, mesh: &IndexedMesh) -> Result<()> {
+    for v in &mesh.vertices {
+        for c in v {
+            writer.write_f32::<B>(*c)?;
+        }
+    }
+    for face in &mesh.faces {
+        writer.write_u8(3)?;
+        for i in &face.vertices {
+            writer.write_i32::<B>(*i as i32)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,677 @@
+//! Serialization of the geometry and robot model types, enabled by the
+//! `serde` feature.
+//!
+//! Each type is (de)serialized through a schema struct holding only the
+//! defining data, e.g. the control points, weights and knot vectors of a
+//! surface, and not the caches derived from them. Vectors are written as
+//! arrays and matrices as arrays of rows. Deserialization validates the
+//! data and rebuilds the derived fields.
+//!
+//! The serialized form of each type carries the `version` of its schema,
+//! and data written with another version is rejected.
+
+use crate::math::*;
+use crate::geometry::{BSplineCurve, BSplineSurface, NurbsCurve, NurbsSurface};
+use crate::ccd::OBB;
+use crate::robotics::{Collision, Geometry, Inertial, Joint, JointDynamics, JointSafetyController,
+                      JointType, Link, Material, Range, Texture, Visual};
+use crate::utils::{Color, load_mesh};
+use na::{DimName, Dim, DefaultAllocator, Dynamic, UnitQuaternion, Quaternion, Translation3};
+use na::allocator::Allocator;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{DeserializeOwned, Error as DeError};
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::Path;
+
+/// Version of the schemas, to be increased on any incompatible change.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// Serialized form of a schema, tagged with the schema version.
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    #[serde(flatten)]
+    data:    T,
+}
+
+/// Serializes the schema `data` with the current schema version.
+pub(crate) fn serialize_versioned<T: Serialize, S: Serializer>(
+    data: T, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    Versioned { version: SCHEMA_VERSION, data }.serialize(serializer)
+}
+
+/// Deserializes a schema, checking its version.
+pub(crate) fn deserialize_versioned<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D) -> std::result::Result<T, D::Error> {
+    let versioned = Versioned::<T>::deserialize(deserializer)?;
+    if versioned.version != SCHEMA_VERSION {
+        return Err(D::Error::custom(format!("unsupported schema version {}, expected {}",
+                                            versioned.version, SCHEMA_VERSION)));
+    }
+    Ok(versioned.data)
+}
+
+/// Implements `Serialize` and `Deserialize` for `$ty` through the schema
+/// `$data`, which implements `From<&$ty>` and `TryInto<$ty>`.
+macro_rules! serde_via {
+    ($ty:ty, $data:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serialize_versioned(<$data>::from(self), serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                deserialize_versioned::<$data, D>(deserializer)?.try_into().map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) type Vec3 = [Scalar; 3];
+pub(crate) type Mat3 = [[Scalar; 3]; 3];
+pub(crate) type Mat4 = [[Scalar; 4]; 4];
+
+pub(crate) fn vec3(v: &Vector3f) -> Vec3 {
+    [v[0], v[1], v[2]]
+}
+
+fn mat3(m: &Matrix3f) -> Mat3 {
+    let mut rows = [[0.; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            rows[i][j] = m[(i, j)];
+        }
+    }
+    rows
+}
+
+fn mat4(m: &Matrix4f) -> Mat4 {
+    let mut rows = [[0.; 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            rows[i][j] = m[(i, j)];
+        }
+    }
+    rows
+}
+
+fn matrix3(rows: &Mat3) -> Matrix3f {
+    Matrix3f::from_fn(|i, j| rows[i][j])
+}
+
+fn matrix4(rows: &Mat4) -> Matrix4f {
+    Matrix4f::from_fn(|i, j| rows[i][j])
+}
+
+/// Checks the sizes of a knot vector and of its control points.
+fn check_knotvec(knotvec: &Vec<Scalar>, degree: usize, size: usize) -> std::result::Result<(), String> {
+    if degree == 0 || size <= degree {
+        return Err(format!("{} control points are not enough for degree {}", size, degree));
+    }
+    if knotvec.len() != size + degree + 1 {
+        return Err(format!("expected {} knots, found {}", size + degree + 1, knotvec.len()));
+    }
+    if knotvec.windows(2).any(|k| k[1] < k[0]) {
+        return Err("knot vector is not non-decreasing".to_owned());
+    }
+    Ok(())
+}
+
+fn check_weights(weights: &Vec<Scalar>, size: usize) -> std::result::Result<(), String> {
+    if weights.len() != size {
+        return Err(format!("expected {} weights, found {}", size, weights.len()));
+    }
+    if weights.iter().any(|w| !(*w > 0.)) {
+        return Err("weights must be positive".to_owned());
+    }
+    Ok(())
+}
+
+fn points<D: Dim + DimName>(ctrlpts: &Vec<Vec<Scalar>>) -> std::result::Result<Vec<VectorNf<D>>, String>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    ctrlpts.iter()
+        .map(|p| if p.len() == D::dim() {
+            Ok(VectorNf::<D>::from_column_slice(p))
+        } else {
+            Err(format!("expected control points of dimension {}, found {}", D::dim(), p.len()))
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct BSplineCurveData {
+    degree:  usize,
+    knotvec: Vec<Scalar>,
+    ctrlpts: Vec<Vec<Scalar>>,
+}
+
+impl<'a, D: Dim + DimName> From<&'a BSplineCurve<D>> for BSplineCurveData
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn from(curve: &'a BSplineCurve<D>) -> Self {
+        BSplineCurveData {
+            degree:  curve.degree,
+            knotvec: curve.knotvec.clone(),
+            ctrlpts: curve.ctrlpts.iter().map(|p| p.iter().cloned().collect()).collect(),
+        }
+    }
+}
+
+impl<D: Dim + DimName> TryFrom<BSplineCurveData> for BSplineCurve<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    type Error = String;
+
+    fn try_from(data: BSplineCurveData) -> std::result::Result<Self, String> {
+        check_knotvec(&data.knotvec, data.degree, data.ctrlpts.len())?;
+        Ok(BSplineCurve::new(data.degree, points::<D>(&data.ctrlpts)?, data.knotvec))
+    }
+}
+
+impl<D: Dim + DimName> Serialize for BSplineCurve<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_versioned(BSplineCurveData::from(self), serializer)
+    }
+}
+
+impl<'de, D: Dim + DimName> Deserialize<'de> for BSplineCurve<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> std::result::Result<Self, De::Error> {
+        deserialize_versioned::<BSplineCurveData, De>(deserializer)?.try_into().map_err(De::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BSplineSurfaceData {
+    degree_u:  usize,
+    degree_v:  usize,
+    size_u:    usize,
+    size_v:    usize,
+    knotvec_u: Vec<Scalar>,
+    knotvec_v: Vec<Scalar>,
+    ctrlpts:   Vec<Vec<Scalar>>,     // control point (i_u, i_v) at i_u * size_v + i_v
+}
+
+impl<'a, D: Dim + DimName> From<&'a BSplineSurface<D>> for BSplineSurfaceData
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn from(surf: &'a BSplineSurface<D>) -> Self {
+        BSplineSurfaceData {
+            degree_u:  surf.degree_u,
+            degree_v:  surf.degree_v,
+            size_u:    surf.size_u,
+            size_v:    surf.size_v,
+            knotvec_u: surf.knotvec_u.clone(),
+            knotvec_v: surf.knotvec_v.clone(),
+            ctrlpts:   surf.ctrlpts.iter().map(|p| p.iter().cloned().collect()).collect(),
+        }
+    }
+}
+
+impl<D: Dim + DimName> TryFrom<BSplineSurfaceData> for BSplineSurface<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    type Error = String;
+
+    fn try_from(data: BSplineSurfaceData) -> std::result::Result<Self, String> {
+        check_knotvec(&data.knotvec_u, data.degree_u, data.size_u)?;
+        check_knotvec(&data.knotvec_v, data.degree_v, data.size_v)?;
+        if data.ctrlpts.len() != data.size_u * data.size_v {
+            return Err(format!("expected {} control points, found {}",
+                               data.size_u * data.size_v, data.ctrlpts.len()));
+        }
+        Ok(BSplineSurface {
+            ctrlpts:   points::<D>(&data.ctrlpts)?,
+            knotvec_u: data.knotvec_u,
+            knotvec_v: data.knotvec_v,
+            degree_u:  data.degree_u,
+            degree_v:  data.degree_v,
+            size_u:    data.size_u,
+            size_v:    data.size_v,
+        })
+    }
+}
+
+impl<D: Dim + DimName> Serialize for BSplineSurface<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_versioned(BSplineSurfaceData::from(self), serializer)
+    }
+}
+
+impl<'de, D: Dim + DimName> Deserialize<'de> for BSplineSurface<D>
+    where DefaultAllocator: Allocator<Scalar, D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> std::result::Result<Self, De::Error> {
+        deserialize_versioned::<BSplineSurfaceData, De>(deserializer)?.try_into().map_err(De::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct NurbsCurveData {
+    degree:  usize,
+    knotvec: Vec<Scalar>,
+    ctrlpts: Vec<Vec3>,
+    weights: Vec<Scalar>,
+}
+
+impl<'a> From<&'a NurbsCurve> for NurbsCurveData {
+    fn from(curve: &'a NurbsCurve) -> Self {
+        NurbsCurveData {
+            degree:  curve.degree,
+            knotvec: curve.knotvec.clone(),
+            ctrlpts: curve.ctrlpts.iter().map(vec3).collect(),
+            weights: curve.weights.clone(),
+        }
+    }
+}
+
+impl TryFrom<NurbsCurveData> for NurbsCurve {
+    type Error = String;
+
+    fn try_from(data: NurbsCurveData) -> std::result::Result<Self, String> {
+        check_knotvec(&data.knotvec, data.degree, data.ctrlpts.len())?;
+        check_weights(&data.weights, data.ctrlpts.len())?;
+        let ctrlpts = data.ctrlpts.iter().map(|p| Vector3f::from_column_slice(p)).collect();
+        Ok(NurbsCurve::new(ctrlpts, data.knotvec, data.degree, data.weights))
+    }
+}
+
+serde_via!(NurbsCurve, NurbsCurveData);
+
+#[derive(Serialize, Deserialize)]
+struct NurbsSurfaceData {
+    name:      String,
+    degree_u:  usize,
+    degree_v:  usize,
+    size_u:    usize,
+    size_v:    usize,
+    knotvec_u: Vec<Scalar>,
+    knotvec_v: Vec<Scalar>,
+    ctrlpts:   Vec<Vec3>,            // control point (i_u, i_v) at i_u * size_v + i_v
+    weights:   Vec<Scalar>,
+}
+
+impl<'a> From<&'a NurbsSurface> for NurbsSurfaceData {
+    fn from(surf: &'a NurbsSurface) -> Self {
+        NurbsSurfaceData {
+            name:      surf.name.clone(),
+            degree_u:  surf.degree_u,
+            degree_v:  surf.degree_v,
+            size_u:    surf.ctrlpts_size_u,
+            size_v:    surf.ctrlpts_size_v,
+            knotvec_u: surf.knotvec_u.clone(),
+            knotvec_v: surf.knotvec_v.clone(),
+            ctrlpts:   surf.ctrlpts.iter().map(vec3).collect(),
+            weights:   surf.weights.clone(),
+        }
+    }
+}
+
+impl TryFrom<NurbsSurfaceData> for NurbsSurface {
+    type Error = String;
+
+    fn try_from(data: NurbsSurfaceData) -> std::result::Result<Self, String> {
+        check_knotvec(&data.knotvec_u, data.degree_u, data.size_u)?;
+        check_knotvec(&data.knotvec_v, data.degree_v, data.size_v)?;
+        let size = data.size_u * data.size_v;
+        if data.ctrlpts.len() != size {
+            return Err(format!("expected {} control points, found {}", size, data.ctrlpts.len()));
+        }
+        check_weights(&data.weights, size)?;
+
+        let ctrlpts = data.ctrlpts.iter().map(|p| Vector3f::from_column_slice(p)).collect();
+        let mut surf = NurbsSurface::new(ctrlpts, data.knotvec_u, data.knotvec_v,
+                                         data.degree_u, data.degree_v,
+                                         data.size_u, data.size_v, data.weights);
+        surf.name = data.name;
+        Ok(surf)
+    }
+}
+
+serde_via!(NurbsSurface, NurbsSurfaceData);
+
+#[derive(Serialize, Deserialize)]
+struct OBBData {
+    pos:  Vec3,     // center
+    r:    Vec3,     // half-sizes along the local axes
+    axis: Mat3,     // local axes as columns
+}
+
+impl<'a> From<&'a OBB> for OBBData {
+    fn from(obb: &'a OBB) -> Self {
+        OBBData {
+            pos:  vec3(&obb.pos),
+            r:    vec3(&obb.r),
+            axis: mat3(&obb.axis),
+        }
+    }
+}
+
+impl TryFrom<OBBData> for OBB {
+    type Error = String;
+
+    fn try_from(data: OBBData) -> std::result::Result<Self, String> {
+        if data.r.iter().any(|r| !(*r >= 0.)) {
+            return Err("half-sizes must be non-negative".to_owned());
+        }
+        Ok(OBB {
+            pos:  Vector3f::from_column_slice(&data.pos),
+            r:    Vector3f::from_column_slice(&data.r),
+            axis: matrix3(&data.axis),
+            scene_node: None,
+        })
+    }
+}
+
+serde_via!(OBB, OBBData);
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JointTypeData {
+    Prismatic { axis: Vec3 },
+    Revolute  { axis: Vec3 },
+    Fixed,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RangeData {
+    min: Scalar,
+    max: Scalar,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JointDynamicsData {
+    damping:      Scalar,
+    friction:     Scalar,
+    effort_limit: Scalar,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JointSafetyControllerData {
+    soft_lower_limit: Scalar,
+    soft_upper_limit: Scalar,
+    k_position:       Scalar,
+    k_velocity:       Scalar,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct JointData {
+    name:             String,
+    joint_type:       JointTypeData,
+    screw_axis:       Vec<[Scalar; 6]>,     // columns of the screw axis
+    qpos_home:        Scalar,
+    qpos_limit:       Option<RangeData>,
+    qvel_limit:       Option<RangeData>,
+    dynamics:         Option<JointDynamicsData>,
+    safe_ctrl:        Option<JointSafetyControllerData>,
+    tform_jnt2parent: Mat4,
+    tform_child2jnt:  Mat4,
+}
+
+impl<'a> From<&'a Joint> for JointData {
+    fn from(joint: &'a Joint) -> Self {
+        let axis = |axis: &Matrix3Df| [axis[0], axis[1], axis[2]];
+        let range = |range: &Option<Range>| range.as_ref().map(|r| RangeData { min: r.min, max: r.max });
+        JointData {
+            name: joint.name.clone(),
+            joint_type: match &joint.joint_type {
+                JointType::Prismatic { axis: a } => JointTypeData::Prismatic { axis: axis(a) },
+                JointType::Revolute { axis: a } => JointTypeData::Revolute { axis: axis(a) },
+                JointType::Fixed => JointTypeData::Fixed,
+            },
+            screw_axis: joint.screw_axis.column_iter()
+                .map(|c| [c[0], c[1], c[2], c[3], c[4], c[5]])
+                .collect(),
+            qpos_home: joint.qpos_home,
+            qpos_limit: range(&joint.qpos_limit),
+            qvel_limit: range(&joint.qvel_limit),
+            dynamics: joint.dynamics.as_ref().map(|d| JointDynamicsData {
+                damping: d.damping,
+                friction: d.friction,
+                effort_limit: d.effort_limit,
+            }),
+            safe_ctrl: joint.safe_ctrl.as_ref().map(|s| JointSafetyControllerData {
+                soft_lower_limit: s.soft_lower_limit,
+                soft_upper_limit: s.soft_upper_limit,
+                k_position: s.k_position,
+                k_velocity: s.k_velocity,
+            }),
+            tform_jnt2parent: mat4(&joint.tform_jnt2parent),
+            tform_child2jnt: mat4(&joint.tform_child2jnt),
+        }
+    }
+}
+
+impl TryFrom<JointData> for Joint {
+    type Error = String;
+
+    fn try_from(data: JointData) -> std::result::Result<Self, String> {
+        let axis = |axis: &Vec3| Matrix3Df::from_column_slice(axis);
+        let range = |range: &Option<RangeData>| range.as_ref().map(|r| Range::new(r.min, r.max));
+        let joint_type = match &data.joint_type {
+            JointTypeData::Prismatic { axis: a } => JointType::Prismatic { axis: axis(a) },
+            JointTypeData::Revolute { axis: a } => JointType::Revolute { axis: axis(a) },
+            JointTypeData::Fixed => JointType::Fixed,
+        };
+        let screw_axis: Vec<Scalar> = data.screw_axis.iter().flat_map(|c| c.iter().cloned()).collect();
+
+        let mut joint = Joint::new(data.name, joint_type);
+        joint.screw_axis = Matrix6Df::from_column_slice_generic(
+            U6, Dynamic::new(data.screw_axis.len()), &screw_axis);
+        joint.qpos_home = data.qpos_home;
+        joint.qpos_limit = range(&data.qpos_limit);
+        joint.qvel_limit = range(&data.qvel_limit);
+        joint.dynamics = data.dynamics.map(|d| JointDynamics {
+            damping: d.damping,
+            friction: d.friction,
+            effort_limit: d.effort_limit,
+        });
+        joint.safe_ctrl = data.safe_ctrl.map(|s| JointSafetyController {
+            soft_lower_limit: s.soft_lower_limit,
+            soft_upper_limit: s.soft_upper_limit,
+            k_position: s.k_position,
+            k_velocity: s.k_velocity,
+        });
+        joint.tform_jnt2parent = matrix4(&data.tform_jnt2parent);
+        joint.tform_child2jnt = matrix4(&data.tform_child2jnt);
+        Ok(joint)
+    }
+}
+
+serde_via!(Joint, JointData);
+
+#[derive(Serialize, Deserialize)]
+struct InertialData {
+    mass:         Scalar,
+    com:          Vec3,
+    inertia_body: Mat3,
+    inertia_com:  Mat3,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OriginData {
+    translation: Vec3,
+    rotation:    [Scalar; 4],   // unit quaternion (w, x, y, z)
+}
+
+impl OriginData {
+
+    fn new(origin: &Isometry3f) -> Self {
+        let t = &origin.translation.vector;
+        let q = origin.rotation.quaternion();
+        OriginData {
+            translation: vec3(t),
+            rotation: [q.w, q.i, q.j, q.k],
+        }
+    }
+
+    fn isometry(&self) -> Isometry3f {
+        let r = &self.rotation;
+        Isometry3f::from_parts(
+            Translation3::new(self.translation[0], self.translation[1], self.translation[2]),
+            UnitQuaternion::from_quaternion(Quaternion::new(r[0], r[1], r[2], r[3])),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum GeometryData {
+    Box      { depth: Scalar, width: Scalar, height: Scalar },
+    Cylinder { radius: Scalar, length: Scalar },
+    Capsule  { radius: Scalar, length: Scalar },
+    Sphere   { radius: Scalar },
+    Mesh     { filename: String, scale: [f32; 3] },
+}
+
+impl<'a> From<&'a Geometry> for GeometryData {
+    fn from(geometry: &'a Geometry) -> Self {
+        match geometry {
+            Geometry::Box { depth, width, height } =>
+                GeometryData::Box { depth: *depth, width: *width, height: *height },
+            Geometry::Cylinder { radius, length } =>
+                GeometryData::Cylinder { radius: *radius, length: *length },
+            Geometry::Capsule { radius, length } =>
+                GeometryData::Capsule { radius: *radius, length: *length },
+            Geometry::Sphere { radius } =>
+                GeometryData::Sphere { radius: *radius },
+            Geometry::Mesh { filename, scale, .. } =>
+                GeometryData::Mesh { filename: filename.clone(), scale: [scale[0], scale[1], scale[2]] },
+        }
+    }
+}
+
+impl TryFrom<GeometryData> for Geometry {
+    type Error = String;
+
+    /// Meshes are loaded again from their files.
+    fn try_from(data: GeometryData) -> std::result::Result<Self, String> {
+        Ok(match data {
+            GeometryData::Box { depth, width, height } => Geometry::Box { depth, width, height },
+            GeometryData::Cylinder { radius, length } => Geometry::Cylinder { radius, length },
+            GeometryData::Capsule { radius, length } => Geometry::Capsule { radius, length },
+            GeometryData::Sphere { radius } => Geometry::Sphere { radius },
+            GeometryData::Mesh { filename, scale } => {
+                let mesh = load_mesh(&filename)
+                    .map_err(|e| format!("mesh file '{}': {}", filename, e))?;
+                Geometry::Mesh { filename, scale: Vector3f32::from_column_slice(&scale), mesh }
+            },
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MaterialData {
+    name:    String,
+    color:   [f32; 4],      // (r, g, b, a)
+    texture: String,        // texture file name
+}
+
+#[derive(Serialize, Deserialize)]
+struct VisualData {
+    name:     String,
+    origin:   OriginData,
+    geometry: GeometryData,
+    material: MaterialData,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CollisionData {
+    name:     String,
+    origin:   OriginData,
+    geometry: GeometryData,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LinkData {
+    name:       String,
+    inertial:   InertialData,
+    visuals:    Vec<VisualData>,
+    collisions: Vec<CollisionData>,
+}
+
+impl<'a> From<&'a Link> for LinkData {
+    fn from(link: &'a Link) -> Self {
+        let inertial = &link.inertial;
+        LinkData {
+            name: link.name.clone(),
+            inertial: InertialData {
+                mass: inertial.mass,
+                com: vec3(&inertial.bvec_com),
+                inertia_body: mat3(&inertial.inertia_body),
+                inertia_com: mat3(&inertial.inertia_com),
+            },
+            visuals: link.visuals.iter()
+                .map(|visual| {
+                    let color = &visual.material.color;
+                    VisualData {
+                        name: visual.name.clone(),
+                        origin: OriginData::new(visual.origin()),
+                        geometry: GeometryData::from(&visual.geometry),
+                        material: MaterialData {
+                            name: visual.material.name.clone(),
+                            color: [color.r, color.g, color.b, color.a],
+                            texture: visual.material.texture.filename.clone(),
+                        },
+                    }
+                })
+                .collect(),
+            collisions: link.collisions.iter()
+                .map(|collision| CollisionData {
+                    name: collision.name.clone(),
+                    origin: OriginData::new(collision.origin()),
+                    geometry: GeometryData::from(&collision.geometry),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<LinkData> for Link {
+    type Error = String;
+
+    fn try_from(data: LinkData) -> std::result::Result<Self, String> {
+        let inertial = &data.inertial;
+        let mut visuals = Vec::new();
+        for visual in data.visuals {
+            let color = &visual.material.color;
+            let material = Material {
+                name: visual.material.name.clone(),
+                color: Color::new(color[0], color[1], color[2], color[3]),
+                texture: Texture { filename: visual.material.texture.clone() },
+            };
+            visuals.push(Visual::new(visual.name, visual.origin.isometry(),
+                                     visual.geometry.try_into()?, material));
+        }
+        let mut collisions = Vec::new();
+        for collision in data.collisions {
+            collisions.push(Collision::new(collision.name, collision.origin.isometry(),
+                                           collision.geometry.try_into()?));
+        }
+
+        Ok(Link {
+            name: data.name,
+            inertial: Inertial::new(Vector3f::from_column_slice(&inertial.com), inertial.mass,
+                                    matrix3(&inertial.inertia_com), matrix3(&inertial.inertia_body)),
+            visuals,
+            collisions,
+        })
+    }
+}
+
+serde_via!(Link, LinkData);
+
+/// Writes a value as pretty-printed JSON.
+pub fn save_json<T: Serialize, P: AsRef<Path>>(path: P, value: &T) -> Result<()> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(BufWriter::new(file), value)
+        .map_err(|e| Error::new(ErrorKind::Other, e))
+}
+
+/// Reads a value from JSON.
+pub fn load_json<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let file = File::open(path)?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
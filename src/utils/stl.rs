@@ -1,6 +1,6 @@
 //! ```stl_io``` is a crate for reading and writing [STL (STereoLithography)](https://en.wikipedia.org/wiki/STL_(file_format)) files.
 //! It can read both, binary and ascii STL in a safe manner.
-//! Writing is limited to binary STL, which is more compact anyway.
+//! Writing supports both binary and ascii STL.
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{BufRead, BufReader, BufWriter};
@@ -100,32 +100,90 @@ impl IndexedMesh {
     }
 }
 
-// /// Write to std::io::Write as documented in
-// /// [Wikipedia](https://en.wikipedia.org/wiki/STL_(file_format)#Binary_STL).
-// pub fn write_stl<'a, W, I>(writer: &mut W, mesh: I) -> Result<()>
-//     where
-//         W: ::std::io::Write,
-//         I: ::std::iter:: <Item = &'a Triangle>,
-// {
-//     let mut writer = BufWriter::new(writer);
-//
-//     // Write 80 byte header
-//     writer.write_all(&[0u8; 80])?;
-//     writer.write_u32::<LittleEndian>(mesh.len() as u32)?;
-//     for t in mesh {
-//         for f in &t.normal {
-//             writer.write_f32::<LittleEndian>(*f as f32)?;
-//         }
-//         for &p in &t.vertices {
-//             for c in &p {
-//                 writer.write_f32::<LittleEndian>(*c as f32)?;
-//             }
-//         }
-//         // Attribute byte count
-//         writer.write_u16::<LittleEndian>(0)?;
-//     }
-//     writer.flush()
-// }
+/// Checks that the faces refer to vertices of the mesh, as the writers look
+/// the vertices up by index.
+fn check_face_indices(mesh: &IndexedMesh) -> Result<()> {
+    for (fi, face) in mesh.faces.iter().enumerate() {
+        if let Some(i) = face.vertices.iter().find(|i| **i >= mesh.vertices.len()) {
+            return Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                format!("face #{} refers to missing vertex #{}", fi, i),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Writes binary STL to std::io::Write as documented in
+/// [Wikipedia](https://en.wikipedia.org/wiki/STL_(file_format)#Binary_STL).
+pub fn write_stl<W>(writer: &mut W, mesh: &IndexedMesh) -> Result<()>
+    where
+        W: ::std::io::Write,
+{
+    check_face_indices(mesh)?;
+    if mesh.faces.len() > u32::max_value() as usize {
+        return Err(::std::io::Error::new(
+            ::std::io::ErrorKind::InvalidInput,
+            format!("{} faces exceed the 32-bit face count of binary STL", mesh.faces.len()),
+        ));
+    }
+    let mut writer = BufWriter::new(writer);
+
+    // Write 80 byte header
+    writer.write_all(&[0u8; 80])?;
+    writer.write_u32::<LittleEndian>(mesh.faces.len() as u32)?;
+    for face in &mesh.faces {
+        for f in &face.normal {
+            writer.write_f32::<LittleEndian>(*f)?;
+        }
+        for &i in &face.vertices {
+            for c in &mesh.vertices[i] {
+                writer.write_f32::<LittleEndian>(*c)?;
+            }
+        }
+        // Attribute byte count
+        writer.write_u16::<LittleEndian>(0)?;
+    }
+    writer.flush()
+}
+
+/// Writes ascii STL to std::io::Write, with the given solid name.
+pub fn write_stl_ascii<W>(writer: &mut W, mesh: &IndexedMesh, name: &str) -> Result<()>
+    where
+        W: ::std::io::Write,
+{
+    check_face_indices(mesh)?;
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, "solid {}", name)?;
+    for face in &mesh.faces {
+        let n = &face.normal;
+        writeln!(writer, "  facet normal {:e} {:e} {:e}", n[0], n[1], n[2])?;
+        writeln!(writer, "    outer loop")?;
+        for &i in &face.vertices {
+            let v = &mesh.vertices[i];
+            writeln!(writer, "      vertex {:e} {:e} {:e}", v[0], v[1], v[2])?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {}", name)?;
+    writer.flush()
+}
+
+/// Computes the unit normal of the triangle `abc` following the right-hand
+/// rule, or zero for a degenerate triangle.
+pub(crate) fn triangle_normal(a: &Vertex, b: &Vertex, c: &Vertex) -> Normal {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+    let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if norm > 0. {
+        [n[0] / norm, n[1] / norm, n[2] / norm]
+    } else {
+        [0.; 3]
+    }
+}
 
 /// Attempts to read either ascii or binary STL from std::io::Read.
 pub fn read_stl<R>(read: &mut R) -> Result<IndexedMesh>
@@ -401,37 +459,3 @@ impl<'a> AsciiStlReader<'a> {
         Ok(())
     }
 }
-
-use std::cell::RefCell;
-use std::rc::Rc;
-use kiss3d::resource::Mesh;
-use std::path::Path;
-use std::fs::OpenOptions;
-use na::{Point3, Vector3};
-use std::collections::LinkedList;
-
-pub fn load_mesh<P>(path: P) -> Rc<RefCell<Mesh>>
-where P: AsRef<Path> {
-    let mut file = OpenOptions::new().read(true).open(path).unwrap();
-    let stl = read_stl(&mut file).unwrap();
-
-    let mut coords: Vec<Point3<f32>> = Vec::new();
-    let mut indices: Vec<Point3<u16>> = Vec::new();
-    let mut normals: Vec<Vector3<f32>> = Vec::new();
-
-    for vertex in stl.vertices.iter() {
-        coords.push(Point3::from_slice(vertex));
-    }
-    for face in stl.faces.iter() {
-        indices.push(Point3::new(
-            face.vertices[0] as u16, face.vertices[1] as u16, face.vertices[2] as u16));
-        normals.push(Vector3::new(
-            face.normal[0], face.normal[1], face.normal[2]));
-    }
-
-    let mesh = Rc::new(RefCell::new(Mesh::new(
-        coords, indices, Some(normals), None, false,
-    )));
-
-    return mesh;
-}
\ No newline at end of file
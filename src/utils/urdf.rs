@@ -7,6 +7,7 @@ use crate::math::*;
 use crate::robotics::*;
 use crate::utils::*;
 use kiss3d::resource::Mesh;
+use log::error;
 
 impl<'a> From<&'a urdf_rs::Color> for Color {
     fn from(urdf_color: &urdf_rs::Color) -> Self {
@@ -147,7 +148,10 @@ impl From<urdf_rs::Geometry> for Geometry {
                     na::convert(scale[1]),
                     na::convert(scale[2]),
                 ),
-                mesh: load_mesh(filename),
+                mesh: load_mesh(&filename).unwrap_or_else(|e| {
+                    error!("mesh file {}: {}", filename, e);
+                    std::process::exit(ERROR_CODE_URDF_PARSING);
+                }),
             },
             urdf_rs::Geometry::Capsule { radius, length } => Geometry::Capsule {
                 radius: na::convert(radius),